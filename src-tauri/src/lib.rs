@@ -4,38 +4,175 @@ use reqwest::header::CONTENT_TYPE;
 use scraper::{Html, Selector};
 use std::net::IpAddr;
 use std::time::{SystemTime, UNIX_EPOCH};
-use tauri::Manager;
+use tauri::{Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
 use url::Url;
 use walkdir::WalkDir;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-  tauri::Builder::default()
+  let builder = tauri::Builder::default();
+  // Must be the first plugin: a second launch hands its arguments to the
+  // running app and exits before anything else starts.
+  #[cfg(desktop)]
+  let builder = builder.plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
+    focus_main_window(app);
+    let _ = app.emit("second-instance", SecondInstance { args, cwd });
+  }));
+  builder
     .plugin(tauri_plugin_dialog::init())
+    .plugin(tauri_plugin_deep_link::init())
     .invoke_handler(tauri::generate_handler![
       list_boards,
+      set_active_board,
       list_trashed_boards,
+      refresh_board_meta,
+      force_reindex,
+      pin_board,
+      unpin_board,
+      find_duplicate_links_global,
+      suggest_existing_board,
+      boards_containing_url,
+      find_duplicate_boards,
       create_backup,
+      storage_summary,
+      cancel_storage_summary,
+      move_data_dir,
       cleanup_assets,
+      delete_assets_matching,
+      reconcile_assets,
+      gc_board_assets,
       create_board,
+      rename_board,
+      duplicate_board,
+      save_board_as_template,
+      list_templates,
+      create_board_from_template,
+      import_reading_list,
       delete_board,
       empty_trash,
+      tidy_trash,
       restore_board,
+      preview_restore,
       fetch_link_metadata,
+      is_image_url,
+      backfill_link_metadata,
+      cancel_backfill,
+      prefetch_stale_links,
+      repair_card_image,
       ollama_chat,
+      ollama_chat_stream,
+      cancel_ollama,
+      ensure_model,
+      list_ollama_models,
+      chat_capabilities,
+      recent_chat_runs,
       load_chat,
+      export_chat_json,
+      repair_chat,
       save_chat,
+      save_metrics,
+      set_chat_passphrase,
+      search_chats,
       open_external_url,
+      get_board_deeplink,
+      take_pending_board_link,
+      get_settings,
+      set_settings,
+      reload_http_client,
+      get_ollama_url,
+      set_ollama_url,
       load_board,
+      sanitize_board_geometry,
+      load_board_paged,
+      export_board_html,
+      export_board_canvas,
+      export_board,
+      export_board_subset,
+      import_board_zip,
       save_board,
+      move_card_to_column,
+      list_board_versions,
+      diff_board_versions,
+      board_activity,
+      list_deleted_cards,
+      restore_deleted_card,
+      compact_board,
+      snap_board_to_grid,
+      offset_cards,
+      duplicate_card,
+      flatten_columns,
+      columnize,
+      tag_cards,
+      all_tags,
       save_image,
-      get_assets_dir
+      begin_image_upload,
+      append_image_chunk,
+      finish_image_upload,
+      abort_image_upload,
+      import_image_file,
+      rename_asset,
+      find_similar_images,
+      get_assets_dir,
+      get_asset_url,
+      get_asset_pool_dir,
+      migrate_asset_storage,
+      resolve_asset_path,
+      get_thumbnail,
+      generate_all_thumbnails,
+      get_board_preview,
+      get_board_chat_config,
+      get_board_fetch_config,
+      set_board_fetch_config,
+      set_board_chat_config
     ])
     .setup(|app| {
       let paths = AppPaths::new(app.handle())?;
       ensure_root_dir(&paths)?;
       ensure_board_index(&paths)?;
+      allow_asset_scope(app.handle(), &paths)?;
+      finish_data_dir_move(app.handle(), &paths);
+      let settings = read_settings(&paths);
+      let clients = HttpClients::build(&settings)?;
+      app.manage(HttpState(std::sync::Mutex::new(clients)));
+      app.manage(WorkLimiter::new(settings.max_concurrency()));
+      app.manage(SettingsState(std::sync::Mutex::new(settings)));
       app.manage(paths);
+      app.manage(BoardLocks::default());
+      app.manage(ActiveBoards::default());
+      app.manage(ChatRuns::default());
+      app.manage(ChatStreams::default());
+      app.manage(Backfills::default());
+      app.manage(ImageUploads::default());
+      app.manage(ChatKey::default());
+      app.manage(ChatCaps::default());
+      app.manage(StorageScan::default());
+      app.manage(SaveCounters::default());
+      app.manage(LinkCache::default());
+
+      // Linux and Windows dev builds only learn the lana:// scheme at runtime.
+      #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+      app.deep_link().register_all()?;
+      // A link that launched the app arrives before the frontend can listen for it.
+      let pending = match app.deep_link().get_current() {
+        Ok(urls) => urls.unwrap_or_default().iter().find_map(board_id_from_deeplink),
+        Err(e) => {
+          log::warn!("reading launch deep link failed: {e}");
+          None
+        }
+      };
+      app.manage(PendingBoardLink(std::sync::Mutex::new(pending)));
+      let handle = app.handle().clone();
+      app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+          match board_id_from_deeplink(&url) {
+            Some(board_id) => {
+              let _ = handle.emit("open-board", board_id);
+            }
+            None => log::warn!("ignoring unrecognized deep link: {url}"),
+          }
+        }
+      });
 
       if cfg!(debug_assertions) {
         app.handle().plugin(
@@ -46,39 +183,581 @@ pub fn run() {
       }
       Ok(())
     })
+    .on_window_event(|window, event| {
+      if let tauri::WindowEvent::Destroyed = event {
+        if let Some(active) = window.try_state::<ActiveBoards>() {
+          active.forget(window.label());
+        }
+      }
+    })
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
 
+/// Arguments of a launch that found LANA already running.
+#[derive(Debug, Clone, serde::Serialize)]
+struct SecondInstance {
+  args: Vec<String>,
+  cwd: String,
+}
+
+fn focus_main_window(app: &tauri::AppHandle) {
+  if let Some(window) = app.get_webview_window("main") {
+    let _ = window.unminimize();
+    let _ = window.show();
+    let _ = window.set_focus();
+  }
+}
+
+/// Error returned by commands. Serializes as `{ code, message }` so the UI can
+/// branch on `code` and still show `message`.
+#[derive(Debug, Clone)]
+enum AppError {
+  InvalidBoardId,
+  Invalid(String),
+  NotFound(String),
+  Deleted(String),
+  Conflict(String),
+  QuotaExceeded(String),
+  Blocked(String),
+  Network(String),
+  Timeout(String),
+  Io(String),
+  Serialize(String),
+  Other(String),
+}
+
+impl AppError {
+  fn code(&self) -> &'static str {
+    match self {
+      AppError::InvalidBoardId => "invalidBoardId",
+      AppError::Invalid(_) => "invalid",
+      AppError::NotFound(_) => "notFound",
+      AppError::Deleted(_) => "deleted",
+      AppError::Conflict(_) => "conflict",
+      AppError::QuotaExceeded(_) => "quotaExceeded",
+      AppError::Blocked(_) => "blocked",
+      AppError::Network(_) => "network",
+      AppError::Timeout(_) => "timeout",
+      AppError::Io(_) => "io",
+      AppError::Serialize(_) => "serialize",
+      AppError::Other(_) => "other",
+    }
+  }
+
+  fn message(&self) -> &str {
+    match self {
+      AppError::InvalidBoardId => "invalid board id",
+      AppError::Invalid(m)
+      | AppError::NotFound(m)
+      | AppError::Deleted(m)
+      | AppError::Conflict(m)
+      | AppError::QuotaExceeded(m)
+      | AppError::Blocked(m)
+      | AppError::Network(m)
+      | AppError::Timeout(m)
+      | AppError::Io(m)
+      | AppError::Serialize(m)
+      | AppError::Other(m) => m,
+    }
+  }
+}
+
+impl std::error::Error for AppError {}
+
+/// Fallback for text-only errors; errors raised here pick their variant where
+/// they happen.
+impl From<String> for AppError {
+  fn from(message: String) -> Self {
+    AppError::Other(message)
+  }
+}
+
+impl std::fmt::Display for AppError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(self.message())
+  }
+}
+
+impl serde::Serialize for AppError {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeStruct;
+    let mut state = serializer.serialize_struct("AppError", 2)?;
+    state.serialize_field("code", self.code())?;
+    state.serialize_field("message", self.message())?;
+    state.end()
+  }
+}
+
 #[derive(Clone)]
 struct AppPaths {
+  data_dir: std::path::PathBuf,
   root_dir: std::path::PathBuf,
   index_file: std::path::PathBuf,
+  settings_file: std::path::PathBuf,
+  templates_dir: std::path::PathBuf,
+  pool_dir: std::path::PathBuf,
+  link_cache_file: std::path::PathBuf,
+  link_cache_dir: std::path::PathBuf,
 }
 
 impl AppPaths {
-  fn new(app: &tauri::AppHandle) -> Result<Self, String> {
+  fn new(app: &tauri::AppHandle) -> Result<Self, AppError> {
+    if let Some(dir) = read_data_dir_override(app) {
+      return Ok(Self::at(dir));
+    }
     let documents_dir = app
       .path()
       .document_dir()
-      .map_err(|e| format!("failed to resolve Documents dir: {e}"))?;
+      .map_err(|e| AppError::Io(format!("failed to resolve Documents dir: {e}")))?;
+    Ok(Self::at(documents_dir.join("LANA")))
+  }
 
-    let root_dir = documents_dir.join("LANA").join("boards");
+  fn at(lana_dir: std::path::PathBuf) -> Self {
+    let root_dir = lana_dir.join("boards");
     let index_file = root_dir.join("boards.json");
+    let settings_file = lana_dir.join("settings.json");
+    let templates_dir = lana_dir.join("templates");
+    let pool_dir = lana_dir.join("assets-pool");
+    let link_cache_file = lana_dir.join("link-cache.json");
+    let link_cache_dir = lana_dir.join("link-cache");
 
-    Ok(Self {
+    Self {
+      data_dir: lana_dir,
       root_dir,
       index_file,
+      settings_file,
+      templates_dir,
+      pool_dir,
+      link_cache_file,
+      link_cache_dir,
+    }
+  }
+}
+
+/// Records a relocated data dir (see `move_data_dir`); lives in the app config
+/// dir since the data dir itself may move.
+fn data_dir_pointer(app: &tauri::AppHandle) -> Result<std::path::PathBuf, AppError> {
+  app
+    .path()
+    .app_config_dir()
+    .map(|dir| dir.join("data_dir.json"))
+    .map_err(|e| AppError::Io(format!("failed to resolve config dir: {e}")))
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct DataDirPointer {
+  #[serde(rename = "storageRoot")]
+  storage_root: std::path::PathBuf,
+  /// Data dir a move copied from; its originals stay until a launch has
+  /// loaded `storage_root`.
+  #[serde(
+    default,
+    skip_serializing_if = "Option::is_none",
+    rename = "previousRoot"
+  )]
+  previous_root: Option<std::path::PathBuf>,
+}
+
+fn read_data_dir_pointer(app: &tauri::AppHandle) -> Option<DataDirPointer> {
+  let text = std::fs::read_to_string(data_dir_pointer(app).ok()?).ok()?;
+  serde_json::from_str(&text).ok()
+}
+
+fn write_data_dir_pointer(
+  file: &std::path::Path,
+  pointer: &DataDirPointer,
+) -> Result<(), AppError> {
+  if let Some(dir) = file.parent() {
+    std::fs::create_dir_all(dir)
+      .map_err(|e| AppError::Io(format!("create config dir failed: {e}")))?;
+  }
+  let json = serde_json::to_string_pretty(pointer)
+    .map_err(|e| AppError::Serialize(format!("serialize failed: {e}")))?;
+  let tmp = file.with_extension("json.tmp");
+  std::fs::write(&tmp, json).map_err(|e| AppError::Io(format!("write temp failed: {e}")))?;
+  replace_file(&tmp, file).map_err(|e| AppError::Io(format!("rename failed: {e}")))
+}
+
+fn read_data_dir_override(app: &tauri::AppHandle) -> Option<std::path::PathBuf> {
+  let pointer = read_data_dir_pointer(app)?;
+  if pointer.storage_root.is_absolute() && pointer.storage_root.is_dir() {
+    Some(pointer.storage_root)
+  } else {
+    log::warn!("ignoring data dir override {}", pointer.storage_root.display());
+    None
+  }
+}
+
+/// Deletes what `move_data_dir` copied away from the previous data dir, now
+/// that this launch has loaded the new one. Originals that changed after the
+/// copy are kept.
+fn finish_data_dir_move(app: &tauri::AppHandle, paths: &AppPaths) {
+  let mut pointer = match read_data_dir_pointer(app) {
+    Some(pointer) if pointer.storage_root == paths.data_dir => pointer,
+    _ => return,
+  };
+  let previous = match pointer.previous_root.take() {
+    Some(previous) if previous != paths.data_dir => previous,
+    _ => return,
+  };
+  // Forget the old location first: a crash mid-delete must not retry against
+  // a folder the user may have reused.
+  let written = data_dir_pointer(app).and_then(|file| write_data_dir_pointer(&file, &pointer));
+  if let Err(e) = written {
+    log::warn!("keeping previous data dir {}: {e}", previous.display());
+    return;
+  }
+  let current = paths.data_dir.clone();
+  tauri::async_runtime::spawn_blocking(move || {
+    if let Some(file) = first_unmoved_change(&previous, &current) {
+      log::warn!(
+        "keeping previous data dir {}: {} changed after the move",
+        previous.display(),
+        file.display()
+      );
+      return;
+    }
+    let old = AppPaths::at(previous);
+    for dir in [
+      &old.root_dir,
+      &old.templates_dir,
+      &old.pool_dir,
+      &old.link_cache_dir,
+    ] {
+      if dir.exists() {
+        if let Err(e) = std::fs::remove_dir_all(dir) {
+          log::warn!("removing {} failed: {e}", dir.display());
+        }
+      }
+    }
+    let _ = std::fs::remove_file(&old.settings_file);
+    let _ = std::fs::remove_file(&old.link_cache_file);
+    let _ = std::fs::remove_dir(&old.data_dir);
+  });
+}
+
+/// The first file under `old` that has no copy under `new`, or was modified
+/// after its copy was made.
+fn first_unmoved_change(
+  old: &std::path::Path,
+  new: &std::path::Path,
+) -> Option<std::path::PathBuf> {
+  let modified = |path: &std::path::Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+  WalkDir::new(old)
+    .into_iter()
+    .filter_map(Result::ok)
+    .filter(|e| e.file_type().is_file())
+    .map(|e| e.into_path())
+    .find(|file| {
+      let copy = match file.strip_prefix(old) {
+        Ok(rel) => new.join(rel),
+        Err(_) => return true,
+      };
+      match (modified(file), modified(&copy)) {
+        (Some(original), Some(copied)) => original > copied,
+        (_, None) => true,
+        (None, Some(_)) => false,
+      }
     })
+}
+
+/// Grants the asset protocol what the static scope in `tauri.conf.json`
+/// grants under Documents/LANA, wherever the data dir actually is.
+fn allow_asset_scope(app: &tauri::AppHandle, paths: &AppPaths) -> Result<(), AppError> {
+  app
+    .asset_protocol_scope()
+    .allow_directory(&paths.pool_dir, true)
+    .map_err(|e| AppError::Other(format!("asset scope failed: {e}")))?;
+  for entry in std::fs::read_dir(&paths.root_dir)
+    .into_iter()
+    .flatten()
+    .flatten()
+  {
+    let name = entry.file_name().to_string_lossy().to_string();
+    if is_plain_dir(&entry) && is_valid_board_id(&name) {
+      allow_board_scope(app, &board_paths(&paths.root_dir, &name))?;
+    }
+  }
+  Ok(())
+}
+
+fn allow_board_scope(app: &tauri::AppHandle, board_paths: &BoardPaths) -> Result<(), AppError> {
+  let scope = app.asset_protocol_scope();
+  scope
+    .allow_directory(&board_paths.assets_dir, true)
+    .and_then(|_| scope.allow_directory(&board_paths.thumbs_dir, true))
+    .and_then(|_| scope.allow_file(board_paths.dir.join("preview.png")))
+    .map_err(|e| AppError::Other(format!("asset scope failed: {e}")))
+}
+
+/// Serializes read-modify-write cycles on a single board's files.
+#[derive(Default)]
+struct BoardLocks(
+  std::sync::Mutex<std::collections::HashMap<String, std::sync::Arc<std::sync::Mutex<()>>>>,
+);
+
+impl BoardLocks {
+  fn for_board(&self, board_id: &str) -> std::sync::Arc<std::sync::Mutex<()>> {
+    let mut locks = self.0.lock().unwrap_or_else(|e| e.into_inner());
+    locks.entry(board_id.to_string()).or_default().clone()
+  }
+}
+
+/// Which board each window is showing, keyed by window label.
+#[derive(Default)]
+struct ActiveBoards(std::sync::Mutex<std::collections::HashMap<String, String>>);
+
+impl ActiveBoards {
+  /// Labels of the windows showing `board_id`, or `None` while no window has
+  /// reported a board yet (events then go to every window).
+  fn viewers(&self, board_id: &str) -> Option<Vec<String>> {
+    let active = self.0.lock().unwrap_or_else(|e| e.into_inner());
+    if active.is_empty() {
+      return None;
+    }
+    Some(active.iter().filter(|(_, b)| *b == board_id).map(|(w, _)| w.clone()).collect())
+  }
+
+  fn forget(&self, window: &str) {
+    self.0.lock().unwrap_or_else(|e| e.into_inner()).remove(window);
+  }
+}
+
+/// Emits a board-scoped event only to the windows viewing that board.
+fn emit_to_board<S: serde::Serialize + Clone>(
+  app: &tauri::AppHandle,
+  board_id: &str,
+  event: &str,
+  payload: S,
+) {
+  match app.state::<ActiveBoards>().viewers(board_id) {
+    Some(windows) => {
+      for window in windows {
+        let _ = app.emit_to(window.as_str(), event, payload.clone());
+      }
+    }
+    None => {
+      let _ = app.emit(event, payload);
+    }
+  }
+}
+
+/// Records the board shown in the calling window; `None` clears it.
+#[tauri::command]
+fn set_active_board(
+  window: tauri::Window,
+  active: tauri::State<'_, ActiveBoards>,
+  board_id: Option<String>,
+) -> Result<(), AppError> {
+  let label = window.label().to_string();
+  match board_id {
+    Some(board_id) => {
+      if !is_valid_board_id(&board_id) {
+        return Err(AppError::InvalidBoardId);
+      }
+      active.0.lock().unwrap_or_else(|e| e.into_inner()).insert(label, board_id);
+    }
+    None => active.forget(&label),
+  }
+  Ok(())
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ChatRun {
+  id: String,
+  model: String,
+  #[serde(rename = "messageCount")]
+  message_count: usize,
+  #[serde(rename = "startedAt")]
+  started_at: i64,
+  #[serde(rename = "durationMs")]
+  duration_ms: u64,
+  status: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  error: Option<String>,
+}
+
+const MAX_CHAT_RUNS: usize = 20;
+
+/// In-memory log of the most recent chat requests; cleared on restart.
+#[derive(Default)]
+struct ChatRuns {
+  next_id: std::sync::atomic::AtomicU64,
+  runs: std::sync::Mutex<std::collections::VecDeque<ChatRun>>,
+}
+
+impl ChatRuns {
+  fn correlation_id(&self) -> String {
+    let seq = self.next_id.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    format!("chat-{}-{seq}", now_millis())
+  }
+
+  fn record(&self, run: ChatRun) {
+    let mut runs = self.runs.lock().unwrap_or_else(|e| e.into_inner());
+    runs.push_back(run);
+    while runs.len() > MAX_CHAT_RUNS {
+      runs.pop_front();
+    }
+  }
+}
+
+/// Cancel tokens for streaming chats, keyed by the caller's request id.
+#[derive(Default)]
+struct ChatStreams(
+  std::sync::Mutex<std::collections::HashMap<String, tokio_util::sync::CancellationToken>>,
+);
+
+impl ChatStreams {
+  fn start(&self, request_id: &str) -> Result<tokio_util::sync::CancellationToken, AppError> {
+    let mut streams = self.0.lock().unwrap_or_else(|e| e.into_inner());
+    if streams.contains_key(request_id) {
+      return Err(AppError::Conflict(
+        "a chat with this request id is already running".to_string(),
+      ));
+    }
+    let token = tokio_util::sync::CancellationToken::new();
+    streams.insert(request_id.to_string(), token.clone());
+    Ok(token)
+  }
+
+  fn finish(&self, request_id: &str) {
+    self.0.lock().unwrap_or_else(|e| e.into_inner()).remove(request_id);
+  }
+
+  fn cancel(&self, request_id: &str) -> bool {
+    match self.0.lock().unwrap_or_else(|e| e.into_inner()).get(request_id) {
+      Some(token) => {
+        token.cancel();
+        true
+      }
+      None => false,
+    }
+  }
+}
+
+/// Cancel flags for in-flight metadata backfills, one per board.
+#[derive(Default)]
+struct Backfills(
+  std::sync::Mutex<
+    std::collections::HashMap<String, std::sync::Arc<std::sync::atomic::AtomicBool>>,
+  >,
+);
+
+impl Backfills {
+  fn start(
+    &self,
+    board_id: &str,
+  ) -> Result<std::sync::Arc<std::sync::atomic::AtomicBool>, AppError> {
+    let mut runs = self.0.lock().unwrap_or_else(|e| e.into_inner());
+    if runs.contains_key(board_id) {
+      return Err(AppError::Conflict("backfill already running".to_string()));
+    }
+    let flag = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    runs.insert(board_id.to_string(), flag.clone());
+    Ok(flag)
+  }
+
+  fn finish(&self, board_id: &str) {
+    self.0.lock().unwrap_or_else(|e| e.into_inner()).remove(board_id);
+  }
+
+  fn cancel(&self, board_id: &str) -> bool {
+    match self.0.lock().unwrap_or_else(|e| e.into_inner()).get(board_id) {
+      Some(flag) => {
+        flag.store(true, std::sync::atomic::Ordering::Relaxed);
+        true
+      }
+      None => false,
+    }
+  }
+}
+
+/// Counting semaphore shared by batch jobs (backfill, prefetch, thumbnail
+/// generation) so their combined concurrency stays under the configured limit.
+#[derive(Clone)]
+struct WorkLimiter {
+  permits: std::sync::Arc<tokio::sync::Semaphore>,
+  limit: std::sync::Arc<std::sync::Mutex<usize>>,
+}
+
+/// Holds one slot of the limiter until dropped.
+type WorkPermit = tokio::sync::OwnedSemaphorePermit;
+
+impl WorkLimiter {
+  fn new(limit: usize) -> Self {
+    let limit = limit.max(1);
+    Self {
+      permits: std::sync::Arc::new(tokio::sync::Semaphore::new(limit)),
+      limit: std::sync::Arc::new(std::sync::Mutex::new(limit)),
+    }
+  }
+
+  /// Raising the limit frees slots at once; lowering it retires slots as the
+  /// jobs holding them finish.
+  fn set_limit(&self, limit: usize) {
+    let limit = limit.max(1);
+    let mut current = self.limit.lock().unwrap_or_else(|e| e.into_inner());
+    if limit > *current {
+      self.permits.add_permits(limit - *current);
+    } else if limit < *current {
+      let (permits, excess) = (self.permits.clone(), (*current - limit) as u32);
+      tauri::async_runtime::spawn(async move {
+        if let Ok(retired) = permits.acquire_many_owned(excess).await {
+          retired.forget();
+        }
+      });
+    }
+    *current = limit;
+  }
+
+  async fn acquire(&self) -> Result<WorkPermit, AppError> {
+    self
+      .permits
+      .clone()
+      .acquire_owned()
+      .await
+      .map_err(|e| AppError::Other(format!("work limiter closed: {e}")))
+  }
+
+  /// A slot if one is free right now.
+  fn try_acquire(&self) -> Option<WorkPermit> {
+    self.permits.clone().try_acquire_owned().ok()
   }
 }
 
+/// Board id from the deep link the app was launched with, until the frontend takes it.
+struct PendingBoardLink(std::sync::Mutex<Option<String>>);
+
+/// Cancel flag for a running `storage_summary` scan.
+#[derive(Default)]
+struct StorageScan(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+/// An image being streamed in over several `append_image_chunk` calls.
+struct PendingUpload {
+  board_id: String,
+  filename: String,
+  tmp: std::path::PathBuf,
+  written: u64,
+}
+
+#[derive(Default)]
+struct ImageUploads {
+  next_id: std::sync::atomic::AtomicU64,
+  pending: std::sync::Mutex<std::collections::HashMap<String, PendingUpload>>,
+}
+
 #[derive(Clone)]
 struct BoardPaths {
   dir: std::path::PathBuf,
   file: std::path::PathBuf,
   tmp: std::path::PathBuf,
   assets_dir: std::path::PathBuf,
+  thumbs_dir: std::path::PathBuf,
+  history_dir: std::path::PathBuf,
+  /// Chunked uploads in progress; kept out of `assets/` so asset cleanup
+  /// never sees them.
+  uploads_dir: std::path::PathBuf,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -86,9 +765,13 @@ struct Card {
   id: String,
   #[serde(rename = "type")]
   r#type: String,
+  #[serde(deserialize_with = "deserialize_geometry")]
   x: f64,
+  #[serde(deserialize_with = "deserialize_geometry")]
   y: f64,
+  #[serde(deserialize_with = "deserialize_geometry")]
   width: f64,
+  #[serde(deserialize_with = "deserialize_geometry")]
   height: f64,
   #[serde(default)]
   text: String,
@@ -106,12 +789,19 @@ struct Card {
   description: Option<String>,
   #[serde(default)]
   image: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  favicon: Option<String>,
   #[serde(default, rename = "siteName")]
   site_name: Option<String>,
+  /// When link metadata was last fetched, in epoch millis.
+  #[serde(default, skip_serializing_if = "Option::is_none", rename = "fetchedAt")]
+  fetched_at: Option<i64>,
   #[serde(default)]
   note: Option<String>,
   #[serde(default, rename = "noteExpanded")]
   note_expanded: Option<bool>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  tags: Vec<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -129,11 +819,83 @@ struct Column {
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 struct Board {
+  #[serde(default = "default_board_version")]
+  version: u32,
   id: String,
   name: String,
   cards: Vec<Card>,
   #[serde(default)]
   columns: Vec<Column>,
+  #[serde(default)]
+  settings: BoardSettings,
+}
+
+const DEFAULT_GRID_SIZE: f64 = 22.0;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct BoardSettings {
+  background: Option<String>,
+  #[serde(rename = "gridSize")]
+  grid_size: f64,
+  #[serde(rename = "snapToGrid")]
+  snap_to_grid: bool,
+  #[serde(rename = "defaultCardWidth")]
+  default_card_width: f64,
+}
+
+impl Default for BoardSettings {
+  fn default() -> Self {
+    Self {
+      background: None,
+      grid_size: DEFAULT_GRID_SIZE,
+      snap_to_grid: true,
+      default_card_width: DEFAULT_GRID_SIZE * 12.0,
+    }
+  }
+}
+
+impl BoardSettings {
+  /// Grid size to snap against; falls back to the default for unusable values.
+  fn grid(&self) -> f64 {
+    if self.grid_size.is_finite() && self.grid_size >= 1.0 {
+      self.grid_size
+    } else {
+      DEFAULT_GRID_SIZE
+    }
+  }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct SimilarImageGroup {
+  assets: Vec<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ScanProgress {
+  #[serde(rename = "boardId")]
+  board_id: String,
+  done: usize,
+  total: usize,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct BoardPage {
+  id: String,
+  name: String,
+  columns: Vec<Column>,
+  cards: Vec<Card>,
+  settings: BoardSettings,
+  offset: usize,
+  total: usize,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct CompactSummary {
+  #[serde(rename = "removedColumns")]
+  removed_columns: Vec<String>,
+  #[serde(rename = "removedCardRefs")]
+  removed_card_refs: usize,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -144,6 +906,8 @@ struct BoardMeta {
   updated_at: i64,
   #[serde(default, skip_serializing_if = "Option::is_none", rename = "deletedAt")]
   deleted_at: Option<i64>,
+  #[serde(default)]
+  pinned: bool,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -156,10 +920,103 @@ struct BoardIndex {
 struct LinkMetadata {
   url: String,
   title: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  description: Option<String>,
   #[serde(skip_serializing_if = "Option::is_none")]
   image: Option<String>,
+  /// The site's icon, saved under assets like `image`.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  favicon: Option<String>,
   #[serde(skip_serializing_if = "Option::is_none", rename = "siteName")]
   site_name: Option<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  keywords: Vec<String>,
+  #[serde(skip_serializing_if = "Option::is_none", rename = "imageWidth")]
+  image_width: Option<u32>,
+  #[serde(skip_serializing_if = "Option::is_none", rename = "imageHeight")]
+  image_height: Option<u32>,
+  /// Other usable preview images found on the page, as absolute URLs.
+  #[serde(default, skip_serializing_if = "Vec::is_empty", rename = "imageCandidates")]
+  image_candidates: Vec<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct LinkCardRef {
+  #[serde(rename = "boardId")]
+  board_id: String,
+  #[serde(rename = "boardName")]
+  board_name: String,
+  #[serde(rename = "cardId")]
+  card_id: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct Thumbnail {
+  url: String,
+  width: u32,
+  height: u32,
+  /// The source has more than one frame; the UI can offer a play affordance.
+  animated: bool,
+  /// True when `url` is the original asset rather than a generated thumbnail.
+  original: bool,
+}
+
+/// Card ids an interrupted backfill already fetched, kept in
+/// `<board>/backfill.json` until the run completes. Failed fetches aren't
+/// recorded, so a resumed run tries them again.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct BackfillProgress {
+  #[serde(default)]
+  done: Vec<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct BackfillSummary {
+  #[serde(rename = "boardId")]
+  board_id: String,
+  updated: usize,
+  failed: usize,
+  skipped: usize,
+  cancelled: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct TemplateMeta {
+  id: String,
+  name: String,
+  #[serde(rename = "cardCount")]
+  card_count: usize,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct BoardOverlap {
+  #[serde(rename = "boardId")]
+  board_id: String,
+  #[serde(rename = "boardName")]
+  board_name: String,
+  overlap: usize,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct DuplicateBoardPair {
+  #[serde(rename = "boardId")]
+  board_id: String,
+  #[serde(rename = "boardName")]
+  board_name: String,
+  #[serde(rename = "otherBoardId")]
+  other_board_id: String,
+  #[serde(rename = "otherBoardName")]
+  other_board_name: String,
+  /// 0..1; mostly content overlap, with a smaller weight on the names.
+  score: f64,
+  #[serde(rename = "sharedItems")]
+  shared_items: usize,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct DuplicateLinkGroup {
+  url: String,
+  cards: Vec<LinkCardRef>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -178,38 +1035,494 @@ struct OllamaChatRequest {
 #[derive(Debug, Clone, serde::Deserialize)]
 struct OllamaChatResponse {
   message: OllamaMessage,
+  #[serde(flatten)]
+  stats: OllamaStats,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-struct ChatEntry {
-  id: String,
-  role: String,
-  content: String,
-  #[serde(rename = "createdAt")]
-  created_at: i64,
-  #[serde(skip_serializing_if = "Option::is_none", rename = "sessionId")]
-  session_id: Option<String>,
+/// One NDJSON line of a streamed `/api/chat` reply. Stats arrive on the
+/// final line, the one with `done` set.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct OllamaChatChunk {
+  #[serde(default)]
+  message: Option<OllamaMessage>,
+  #[serde(default)]
+  done: bool,
+  #[serde(default)]
+  error: Option<String>,
+  #[serde(flatten)]
+  stats: OllamaStats,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-struct ChatStore {
-  version: u32,
-  messages: Vec<ChatEntry>,
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+struct OllamaStats {
+  /// Nanoseconds, as are the other durations Ollama reports.
   #[serde(default)]
-  summary: Option<String>,
-  #[serde(default, rename = "summaryUpTo")]
-  summary_up_to: usize,
-  #[serde(default, rename = "lastSessionId")]
-  last_session_id: Option<String>,
+  total_duration: Option<u64>,
+  #[serde(default)]
+  eval_duration: Option<u64>,
+  #[serde(default)]
+  eval_count: Option<u64>,
+  #[serde(default)]
+  prompt_eval_count: Option<u64>,
+}
+
+impl OllamaStats {
+  fn metrics(&self) -> ChatMetrics {
+    let tokens_per_second = match (self.eval_count, self.eval_duration) {
+      (Some(count), Some(ns)) if ns > 0 => Some(count as f64 * 1e9 / ns as f64),
+      _ => None,
+    };
+    ChatMetrics {
+      total_duration_ms: self.total_duration.map(|ns| ns / 1_000_000),
+      eval_count: self.eval_count,
+      prompt_eval_count: self.prompt_eval_count,
+      tokens_per_second,
+    }
+  }
+}
+
+/// Timing and token counts for one chat reply. Backends report different
+/// subsets, so every field is optional.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+struct ChatMetrics {
+  #[serde(rename = "totalDurationMs", skip_serializing_if = "Option::is_none")]
+  total_duration_ms: Option<u64>,
+  #[serde(rename = "evalCount", skip_serializing_if = "Option::is_none")]
+  eval_count: Option<u64>,
+  #[serde(rename = "promptEvalCount", skip_serializing_if = "Option::is_none")]
+  prompt_eval_count: Option<u64>,
+  #[serde(rename = "tokensPerSecond", skip_serializing_if = "Option::is_none")]
+  tokens_per_second: Option<f64>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ChatResult {
+  message: OllamaMessage,
+  metrics: ChatMetrics,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct OllamaTag {
+  name: String,
+  #[serde(default)]
+  size: u64,
+  #[serde(default)]
+  modified_at: Option<String>,
+  #[serde(default)]
+  details: OllamaTagDetails,
+}
+
+/// An installed model as offered in the chat model picker.
+#[derive(Debug, Clone, serde::Serialize)]
+struct OllamaModel {
+  name: String,
+  size: u64,
+  #[serde(rename = "modifiedAt")]
+  modified_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+struct OllamaTagDetails {
+  #[serde(default)]
+  families: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct OllamaTagsResponse {
+  #[serde(default)]
+  models: Vec<OllamaTag>,
+}
+
+/// One NDJSON line of an `/api/pull` stream.
+#[derive(Debug, Clone, serde::Deserialize)]
+struct OllamaPullStatus {
+  #[serde(default)]
+  status: String,
+  #[serde(default)]
+  total: Option<u64>,
+  #[serde(default)]
+  completed: Option<u64>,
+  #[serde(default)]
+  error: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ModelPullProgress {
+  model: String,
+  status: String,
+  total: Option<u64>,
+  completed: Option<u64>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ModelStatus {
+  model: String,
+  pulled: bool,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct OpenAIChatChoice {
+  message: OllamaMessage,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct OpenAIChatResponse {
+  choices: Vec<OpenAIChatChoice>,
+  #[serde(default)]
+  usage: Option<OpenAIUsage>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct OpenAIUsage {
+  #[serde(default)]
+  prompt_tokens: Option<u64>,
+  #[serde(default)]
+  completion_tokens: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind")]
+enum ChatBackend {
+  #[default]
+  #[serde(rename = "ollama")]
+  Ollama,
+  #[serde(rename = "openaiCompatible")]
+  OpenAICompatible {
+    #[serde(rename = "baseUrl")]
+    base_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none", rename = "apiKey")]
+    api_key: Option<String>,
+  },
+}
+
+const BROWSER_USER_AGENT: &str = "Mozilla/5.0 (Macintosh; Intel Mac OS X 14_5) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/125.0.0.0 Safari/537.36";
+
+/// User agent sent with link metadata and preview image fetches.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+enum UserAgentSetting {
+  /// Browser-like UA so sites serve the same OG tags a user would see.
+  #[default]
+  #[serde(rename = "browser")]
+  Browser,
+  /// Identifies the app honestly.
+  #[serde(rename = "lana")]
+  Lana,
+  #[serde(rename = "custom")]
+  Custom(String),
+}
+
+impl UserAgentSetting {
+  fn value(&self) -> String {
+    match self {
+      UserAgentSetting::Lana => lana_user_agent(),
+      UserAgentSetting::Custom(value) if !value.trim().is_empty() => value.trim().to_string(),
+      _ => format!("{BROWSER_USER_AGENT} LANA/{}", env!("CARGO_PKG_VERSION")),
+    }
+  }
+}
+
+fn lana_user_agent() -> String {
+  format!("LANA/{}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Persisted in `LANA/settings.json`; missing or unknown fields fall back to
+/// defaults so older and newer files both load.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct AppSettings {
+  #[serde(default, rename = "chatBackend")]
+  chat_backend: ChatBackend,
+  #[serde(default, rename = "userAgent")]
+  user_agent: UserAgentSetting,
+  /// Longest side, in pixels, for stored link preview images.
+  #[serde(default, rename = "maxImageDimension")]
+  max_image_dimension: Option<u32>,
+  #[serde(default, rename = "assetStorage")]
+  asset_storage: AssetStorage,
+  /// When false, deleting a board removes it outright instead of trashing it.
+  #[serde(default, rename = "useTrash")]
+  use_trash: Option<bool>,
+  /// Encrypt chat.json with the session passphrase (see `set_chat_passphrase`).
+  #[serde(default, rename = "encryptChats")]
+  encrypt_chats: bool,
+  /// Batch fetches and thumbnail jobs allowed to run at once, across all batches.
+  #[serde(default, rename = "maxConcurrency")]
+  max_concurrency: Option<u32>,
+  /// Ollama server, e.g. `http://192.168.1.20:11434`; defaults to this machine.
+  #[serde(default, rename = "ollamaUrl", skip_serializing_if = "Option::is_none")]
+  ollama_url: Option<String>,
+  /// How long fetched link metadata is reused before refetching; 0 disables the cache.
+  #[serde(default, rename = "linkCacheTtlHours", skip_serializing_if = "Option::is_none")]
+  link_cache_ttl_hours: Option<u32>,
+  /// Whole-request limit for link page and image fetches.
+  #[serde(default, rename = "fetchTimeoutMs", skip_serializing_if = "Option::is_none")]
+  fetch_timeout_ms: Option<u64>,
+}
+
+/// Where newly saved images go.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum AssetStorage {
+  /// `<board>/assets/`, so a board folder is self-contained and portable.
+  #[default]
+  #[serde(rename = "board")]
+  Board,
+  /// Content-addressed `LANA/assets-pool/`, shared across boards.
+  #[serde(rename = "pool")]
+  Pool,
+}
+
+/// Per-request knobs for link metadata fetches, taken from settings.
+#[derive(Debug, Clone)]
+struct FetchOptions {
+  max_image_dimension: u32,
+  asset_storage: AssetStorage,
+  link_cache_ttl_ms: i64,
+}
+
+const DEFAULT_MAX_CONCURRENCY: u32 = 4;
+const MAX_MAX_CONCURRENCY: u32 = 32;
+const DEFAULT_MAX_IMAGE_DIMENSION: u32 = 2048;
+const MIN_MAX_IMAGE_DIMENSION: u32 = 64;
+const DEFAULT_LINK_CACHE_TTL_HOURS: u32 = 24;
+const MAX_LINK_CACHE_TTL_HOURS: u32 = 24 * 365;
+
+impl AppSettings {
+  fn max_image_dimension(&self) -> u32 {
+    self.max_image_dimension.unwrap_or(DEFAULT_MAX_IMAGE_DIMENSION)
+  }
+
+  fn fetch_timeout(&self) -> std::time::Duration {
+    std::time::Duration::from_millis(self.fetch_timeout_ms.unwrap_or(DEFAULT_FETCH_TIMEOUT_MS))
+  }
+
+  /// Base URL without a trailing slash, ready for `{base}/api/...`.
+  fn ollama_url(&self) -> String {
+    match self.ollama_url.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+      Some(url) => url.trim_end_matches('/').to_string(),
+      None => DEFAULT_OLLAMA_URL.to_string(),
+    }
+  }
+
+  fn max_concurrency(&self) -> usize {
+    self.max_concurrency.unwrap_or(DEFAULT_MAX_CONCURRENCY) as usize
+  }
+
+  fn use_trash(&self) -> bool {
+    self.use_trash.unwrap_or(true)
+  }
+
+  fn fetch_options(&self) -> FetchOptions {
+    FetchOptions {
+      max_image_dimension: self.max_image_dimension(),
+      asset_storage: self.asset_storage,
+      link_cache_ttl_ms: self.link_cache_ttl_hours.unwrap_or(DEFAULT_LINK_CACHE_TTL_HOURS) as i64
+        * 60
+        * 60
+        * 1000,
+    }
+  }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct ChatEntry {
+  id: String,
+  role: String,
+  content: String,
+  #[serde(rename = "createdAt")]
+  created_at: i64,
+  #[serde(skip_serializing_if = "Option::is_none", rename = "sessionId")]
+  session_id: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  model: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ChatSearchHit {
+  #[serde(rename = "boardId")]
+  board_id: String,
+  #[serde(rename = "boardName")]
+  board_name: String,
+  #[serde(rename = "messageId")]
+  message_id: String,
+  role: String,
+  #[serde(rename = "createdAt")]
+  created_at: i64,
+  snippet: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct ChatStore {
+  version: u32,
+  messages: Vec<ChatEntry>,
+  #[serde(default)]
+  summary: Option<String>,
+  #[serde(default, rename = "summaryUpTo")]
+  summary_up_to: usize,
+  #[serde(default, rename = "lastSessionId")]
+  last_session_id: Option<String>,
+}
+
+/// Per-board chat defaults, stored in `<board>/board_meta.json`.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct BoardChatConfig {
+  model: Option<String>,
+  #[serde(rename = "systemPrompt")]
+  system_prompt: Option<String>,
+}
+
+/// Per-board link fetch overrides; unset fields use the global settings.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct BoardFetchConfig {
+  #[serde(rename = "userAgent")]
+  user_agent: Option<UserAgentSetting>,
+  #[serde(rename = "timeoutMs")]
+  timeout_ms: Option<u64>,
+  #[serde(rename = "followRedirects")]
+  follow_redirects: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct BoardLocalMeta {
+  chat: BoardChatConfig,
+  fetch: BoardFetchConfig,
+  /// Most recently deleted cards, oldest first, for `restore_deleted_card`.
+  #[serde(rename = "deletedCards")]
+  deleted_cards: Vec<Card>,
+}
+
+const MAX_DELETED_CARDS: usize = 50;
+
+const MAX_BOARD_NAME_CHARS: usize = 200;
+
+/// Display-safe board name: control characters and newlines become spaces,
+/// whitespace is collapsed, and the result is capped at a char boundary.
+fn sanitize_board_name(name: &str) -> String {
+  let cleaned: String = name
+    .chars()
+    .map(|c| if c.is_control() { ' ' } else { c })
+    .collect();
+  let collapsed = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+  let truncated: String = collapsed.chars().take(MAX_BOARD_NAME_CHARS).collect();
+  let trimmed = truncated.trim_end();
+  if trimmed.is_empty() {
+    "Untitled".to_string()
+  } else {
+    trimmed.to_string()
+  }
+}
+
+/// Upgrade steps for `board.json`; entry `i` turns schema `i + 1` into `i + 2`.
+const BOARD_MIGRATIONS: &[fn(serde_json::Value) -> serde_json::Value] = &[];
+
+/// Current `board.json` schema.
+const BOARD_VERSION: u32 = BOARD_MIGRATIONS.len() as u32 + 1;
+
+fn default_board_version() -> u32 {
+  1
+}
+
+/// Upgrades raw board JSON from `from` to `BOARD_VERSION`, one step at a time.
+fn migrate_board_value(mut value: serde_json::Value, from: u32) -> serde_json::Value {
+  for step in BOARD_MIGRATIONS.iter().skip(from.saturating_sub(1) as usize) {
+    value = step(value);
+  }
+  value
+}
+
+/// serde_json writes NaN and infinities as `null`; read those back as NaN so
+/// the board still loads and `fix_board_geometry` can repair it.
+fn deserialize_geometry<'de, D: serde::Deserializer<'de>>(d: D) -> Result<f64, D::Error> {
+  Ok(<Option<f64> as serde::Deserialize>::deserialize(d)?.unwrap_or(f64::NAN))
+}
+
+/// Coordinates beyond this are treated as corrupt and pulled back in.
+const MAX_CARD_COORDINATE: f64 = 10_000_000.0;
+const FALLBACK_CARD_HEIGHT: f64 = DEFAULT_GRID_SIZE * 4.0;
+
+/// Replaces non-finite or out-of-range card positions and sizes with usable
+/// values. Returns how many cards were touched.
+fn fix_board_geometry(board: &mut Board) -> usize {
+  let default_width = board.settings.default_card_width;
+  let fix_coord = |v: f64| {
+    if v.is_finite() {
+      v.clamp(-MAX_CARD_COORDINATE, MAX_CARD_COORDINATE)
+    } else {
+      0.0
+    }
+  };
+  let fix_size = |v: f64, fallback: f64| {
+    if v.is_finite() && v > 0.0 {
+      v.min(MAX_CARD_COORDINATE)
+    } else {
+      fallback
+    }
+  };
+  let mut fixed = 0;
+  for card in board.cards.iter_mut() {
+    let next = (
+      fix_coord(card.x),
+      fix_coord(card.y),
+      fix_size(card.width, default_width),
+      fix_size(card.height, FALLBACK_CARD_HEIGHT),
+    );
+    if next != (card.x, card.y, card.width, card.height) {
+      (card.x, card.y, card.width, card.height) = next;
+      fixed += 1;
+    }
+  }
+  fixed
+}
+
+/// Parses `board.json`, migrating older schemas and refusing boards written by
+/// a newer app so they are never silently downgraded.
+fn parse_board(text: &str) -> Result<Board, AppError> {
+  let value: serde_json::Value = serde_json::from_str(text)
+    .map_err(|e| AppError::Serialize(format!("parse board failed: {e}")))?;
+  let version = value
+    .get("version")
+    .and_then(|v| v.as_u64())
+    .map_or(default_board_version(), |v| v.min(u32::MAX as u64) as u32);
+  if version > BOARD_VERSION {
+    return Err(newer_board_error(version));
+  }
+  let mut board: Board = serde_json::from_value(migrate_board_value(value, version))
+    .map_err(|e| AppError::Serialize(format!("parse board failed: {e}")))?;
+  board.version = BOARD_VERSION;
+  Ok(board)
+}
+
+/// The only `Conflict` `parse_board` returns; unreadable JSON is `Serialize`.
+fn newer_board_error(version: u32) -> AppError {
+  AppError::Conflict(format!(
+    "board was saved by a newer version of LANA (schema {version}, this app reads up to {})",
+    BOARD_VERSION
+  ))
 }
 
 fn empty_board(id: &str, name: &str) -> Board {
   Board {
+    version: BOARD_VERSION,
     id: id.to_string(),
     name: name.to_string(),
     cards: vec![],
     columns: vec![],
+    settings: BoardSettings::default(),
+  }
+}
+
+const MAX_TAG_LEN: usize = 40;
+
+/// Canonical tag form: lowercase, no leading `#`, single-spaced, bounded length.
+fn normalize_tag(tag: &str) -> Option<String> {
+  let tag = tag.trim().trim_start_matches('#');
+  let tag = tag.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+  if tag.is_empty() {
+    return None;
   }
+  Some(tag.chars().take(MAX_TAG_LEN).collect::<String>().trim_end().to_string())
 }
 
 fn default_column_name() -> String {
@@ -223,8 +1536,9 @@ fn now_millis() -> i64 {
     .unwrap_or(0)
 }
 
-fn ensure_root_dir(paths: &AppPaths) -> Result<(), String> {
-  std::fs::create_dir_all(&paths.root_dir).map_err(|e| format!("create boards dir failed: {e}"))?;
+fn ensure_root_dir(paths: &AppPaths) -> Result<(), AppError> {
+  std::fs::create_dir_all(&paths.root_dir)
+    .map_err(|e| AppError::Io(format!("create boards dir failed: {e}")))?;
   Ok(())
 }
 
@@ -233,11 +1547,170 @@ fn board_paths(root_dir: &std::path::Path, board_id: &str) -> BoardPaths {
   let file = dir.join("board.json");
   let tmp = dir.join("board.json.tmp");
   let assets_dir = dir.join("assets");
+  let thumbs_dir = dir.join("thumbs");
+  let history_dir = dir.join("history");
+  let uploads_dir = dir.join("uploads");
   BoardPaths {
     dir,
     file,
     tmp,
     assets_dir,
+    thumbs_dir,
+    history_dir,
+    uploads_dir,
+  }
+}
+
+/// Resolves a card asset reference such as `assets/photo.png` to a file in the
+/// board's assets dir, rejecting absolute paths and anything that would escape it.
+fn resolve_asset_ref(paths: &BoardPaths, asset_path: &str) -> Result<std::path::PathBuf, AppError> {
+  let rel = asset_path
+    .trim()
+    .strip_prefix("assets/")
+    .ok_or_else(|| AppError::Invalid("asset path must start with assets/".to_string()))?;
+  if rel.is_empty() || rel.contains('\\') {
+    return Err(AppError::Invalid("invalid asset path".to_string()));
+  }
+  let rel_path = std::path::Path::new(rel);
+  if !rel_path
+    .components()
+    .all(|c| matches!(c, std::path::Component::Normal(_)))
+  {
+    return Err(AppError::Invalid("invalid asset path".to_string()));
+  }
+  Ok(paths.assets_dir.join(rel_path))
+}
+
+/// Collapses an incoming filename to a single path component inside the assets dir.
+fn flatten_asset_name(name: &str) -> String {
+  name.replace('\\', "_").replace('/', "_").replace("..", "_")
+}
+
+/// Resolves an asset reference and canonicalizes it, failing unless the real
+/// file (after following any symlinks) lives inside the board's assets dir.
+fn canonical_asset_path(
+  paths: &BoardPaths,
+  asset_path: &str,
+) -> Result<std::path::PathBuf, AppError> {
+  let file = resolve_asset_ref(paths, asset_path)?;
+  let assets_dir = paths
+    .assets_dir
+    .canonicalize()
+    .map_err(|e| AppError::Io(format!("resolve assets dir failed: {e}")))?;
+  let file = file
+    .canonicalize()
+    .map_err(|_| AppError::NotFound("asset not found".to_string()))?;
+  if !file.starts_with(&assets_dir) || !file.is_file() {
+    return Err(AppError::Invalid(
+      "asset is outside the board's assets dir".to_string(),
+    ));
+  }
+  Ok(file)
+}
+
+/// True when a persisted card `src`/`image` value points inside the board's own
+/// assets dir: either `assets/<name>` or a bare filename the UI resolves there.
+fn is_board_asset_ref(paths: &BoardPaths, value: &str) -> bool {
+  if resolve_asset_ref(paths, value).is_ok() {
+    return true;
+  }
+  let mut components = std::path::Path::new(value).components();
+  !value.contains('\\')
+    && matches!(components.next(), Some(std::path::Component::Normal(_)))
+    && components.next().is_none()
+}
+
+/// Maps a card `src`/`image` value to its file in the assets dir, if it is a
+/// board-local reference.
+fn card_asset_file(paths: &BoardPaths, value: &str) -> Option<std::path::PathBuf> {
+  match resolve_asset_ref(paths, value) {
+    Ok(file) => Some(file),
+    Err(_) if is_board_asset_ref(paths, value) => Some(paths.assets_dir.join(value.trim())),
+    Err(_) => None,
+  }
+}
+
+const MAX_ASSET_STEM_LEN: usize = 100;
+
+/// Turns a user-supplied name into a safe file stem: any extension and path
+/// parts are dropped, and characters outside a conservative set become `_`.
+fn sanitize_asset_stem(name: &str) -> Option<String> {
+  let base = name.rsplit(['/', '\\']).next().unwrap_or("");
+  let stem = std::path::Path::new(base)
+    .file_stem()
+    .and_then(|s| s.to_str())
+    .unwrap_or(base);
+  let cleaned: String = stem
+    .chars()
+    .map(|c| {
+      if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' {
+        c
+      } else {
+        '_'
+      }
+    })
+    .take(MAX_ASSET_STEM_LEN)
+    .collect();
+  let cleaned = cleaned.trim().to_string();
+  if cleaned.is_empty() || cleaned.chars().all(|c| c == '_') {
+    None
+  } else {
+    Some(cleaned)
+  }
+}
+
+/// Blanks card asset references that would escape the board's assets dir.
+/// Returns how many references were removed.
+fn strip_foreign_asset_refs(paths: &BoardPaths, board: &mut Board) -> usize {
+  let mut blanked = 0;
+  for card in board.cards.iter_mut() {
+    for field in [&mut card.src, &mut card.image, &mut card.favicon] {
+      let foreign = match field.as_deref() {
+        Some(value) => {
+          !value.trim().is_empty()
+            && pool_ref_name(value).is_none()
+            && !is_board_asset_ref(paths, value)
+        }
+        None => false,
+      };
+      if foreign {
+        *field = None;
+        blanked += 1;
+      }
+    }
+  }
+  blanked
+}
+
+/// Matches JavaScript's `encodeURIComponent`, which Tauri's `convertFileSrc` uses.
+fn encode_uri_component(value: &str) -> String {
+  let mut out = String::with_capacity(value.len());
+  for byte in value.bytes() {
+    match byte {
+      b'A'..=b'Z'
+      | b'a'..=b'z'
+      | b'0'..=b'9'
+      | b'-'
+      | b'_'
+      | b'.'
+      | b'!'
+      | b'~'
+      | b'*'
+      | b'\''
+      | b'('
+      | b')' => out.push(byte as char),
+      _ => out.push_str(&format!("%{byte:02X}")),
+    }
+  }
+  out
+}
+
+fn asset_protocol_url(file: &std::path::Path) -> String {
+  let encoded = encode_uri_component(&file.to_string_lossy());
+  if cfg!(any(target_os = "windows", target_os = "android")) {
+    format!("http://asset.localhost/{encoded}")
+  } else {
+    format!("asset://localhost/{encoded}")
   }
 }
 
@@ -253,9 +1726,28 @@ fn is_valid_board_id(board_id: &str) -> bool {
     .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
 }
 
-fn ensure_board_file(paths: &BoardPaths, board_id: &str, board_name: &str) -> Result<(), String> {
-  std::fs::create_dir_all(&paths.dir).map_err(|e| format!("create dir failed: {e}"))?;
-  std::fs::create_dir_all(&paths.assets_dir).map_err(|e| format!("create assets dir failed: {e}"))?;
+/// True for a real directory entry. Symlinks are never treated as boards, even
+/// when they point at a directory, so writes can't land outside the LANA tree.
+fn is_plain_dir(entry: &std::fs::DirEntry) -> bool {
+  entry.file_type().map(|t| t.is_dir()).unwrap_or(false)
+}
+
+fn is_symlink(path: &std::path::Path) -> bool {
+  std::fs::symlink_metadata(path)
+    .map(|m| m.file_type().is_symlink())
+    .unwrap_or(false)
+}
+
+fn ensure_board_file(paths: &BoardPaths, board_id: &str, board_name: &str) -> Result<(), AppError> {
+  if is_symlink(&paths.dir) || is_symlink(&paths.assets_dir) {
+    return Err(AppError::Invalid(
+      "board directory is a symlink".to_string(),
+    ));
+  }
+  std::fs::create_dir_all(&paths.dir)
+    .map_err(|e| AppError::Io(format!("create dir failed: {e}")))?;
+  std::fs::create_dir_all(&paths.assets_dir)
+    .map_err(|e| AppError::Io(format!("create assets dir failed: {e}")))?;
 
   if !paths.file.exists() {
     write_board_atomic(paths, &empty_board(board_id, board_name))?;
@@ -264,60 +1756,349 @@ fn ensure_board_file(paths: &BoardPaths, board_id: &str, board_name: &str) -> Re
   Ok(())
 }
 
-fn write_board_atomic(paths: &BoardPaths, board: &Board) -> Result<(), String> {
-  let json = serde_json::to_string_pretty(board).map_err(|e| format!("serialize failed: {e}"))?;
+/// Per-session save counters; only sizes and timings are kept, never content.
+#[derive(Default)]
+struct SaveCounter {
+  count: std::sync::atomic::AtomicU64,
+  bytes: std::sync::atomic::AtomicU64,
+  max_micros: std::sync::atomic::AtomicU64,
+}
 
-  std::fs::write(&paths.tmp, json).map_err(|e| format!("write temp failed: {e}"))?;
+impl SaveCounter {
+  fn record(&self, bytes: usize, elapsed: std::time::Duration) {
+    use std::sync::atomic::Ordering::Relaxed;
+    self.count.fetch_add(1, Relaxed);
+    self.bytes.fetch_add(bytes as u64, Relaxed);
+    self.max_micros.fetch_max(elapsed.as_micros() as u64, Relaxed);
+  }
 
-  if paths.file.exists() {
-    let _ = std::fs::remove_file(&paths.file);
+  fn snapshot(&self) -> SaveStats {
+    use std::sync::atomic::Ordering::Relaxed;
+    SaveStats {
+      count: self.count.load(Relaxed),
+      total_bytes: self.bytes.load(Relaxed),
+      max_duration_ms: self.max_micros.load(Relaxed) as f64 / 1000.0,
+    }
   }
+}
 
-  std::fs::rename(&paths.tmp, &paths.file).map_err(|e| format!("rename failed: {e}"))?;
-  Ok(())
+/// Board and chat saves made through `save_board` and `save_chat`.
+#[derive(Default)]
+struct SaveCounters {
+  boards: SaveCounter,
+  chats: SaveCounter,
 }
 
-fn write_index_atomic(paths: &AppPaths, index: &BoardIndex) -> Result<(), String> {
-  let json = serde_json::to_string_pretty(index).map_err(|e| format!("serialize failed: {e}"))?;
-  let tmp = paths.index_file.with_extension("json.tmp");
-  std::fs::write(&tmp, json).map_err(|e| format!("write index temp failed: {e}"))?;
-  if paths.index_file.exists() {
-    let _ = std::fs::remove_file(&paths.index_file);
+#[derive(Debug, Clone, serde::Serialize)]
+struct SaveStats {
+  count: u64,
+  #[serde(rename = "totalBytes")]
+  total_bytes: u64,
+  #[serde(rename = "maxDurationMs")]
+  max_duration_ms: f64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct SaveMetrics {
+  boards: SaveStats,
+  chats: SaveStats,
+}
+
+/// Moves a fully written temp file over `dest`. `std::fs::rename` replaces an
+/// existing file on every platform (MoveFileEx with REPLACE_EXISTING on
+/// Windows), so `dest` is never removed first and a crash leaves either the
+/// old or the new file in place.
+fn replace_file(tmp: &std::path::Path, dest: &std::path::Path) -> std::io::Result<()> {
+  std::fs::rename(tmp, dest)
+}
+
+/// Returns the number of bytes written.
+fn write_board_atomic(paths: &BoardPaths, board: &Board) -> Result<usize, AppError> {
+  if board.version > BOARD_VERSION {
+    return Err(newer_board_error(board.version));
   }
-  std::fs::rename(&tmp, &paths.index_file).map_err(|e| format!("rename index failed: {e}"))?;
-  Ok(())
+  let started = std::time::Instant::now();
+  let json = serde_json::to_string_pretty(board)
+    .map_err(|e| AppError::Serialize(format!("serialize failed: {e}")))?;
+  let size = json.len();
+
+  std::fs::write(&paths.tmp, json).map_err(|e| AppError::Io(format!("write temp failed: {e}")))?;
+  replace_file(&paths.tmp, &paths.file).map_err(|e| AppError::Io(format!("rename failed: {e}")))?;
+  log::debug!(
+    "board save {}: {size} bytes in {:?}",
+    board.id,
+    started.elapsed()
+  );
+  Ok(size)
 }
 
-fn read_index(paths: &AppPaths) -> Result<BoardIndex, String> {
-  ensure_root_dir(paths)?;
-  if !paths.index_file.exists() {
-    return rebuild_index_from_fs(paths);
+/// Errors a sync client briefly holding the file can cause: permission denied
+/// and, on Windows, sharing or lock violations (OS errors 32 and 33).
+fn is_transient_read_error(e: &std::io::Error) -> bool {
+  matches!(
+    e.kind(),
+    std::io::ErrorKind::PermissionDenied
+      | std::io::ErrorKind::WouldBlock
+      | std::io::ErrorKind::Interrupted
+  ) || matches!(e.raw_os_error(), Some(32 | 33) if cfg!(windows))
+}
+
+/// Backoff between attempts; about half a second in all.
+const READ_RETRY_DELAYS_MS: [u64; 4] = [50, 100, 150, 200];
+
+/// `std::fs::read`, retried briefly when the file is momentarily locked.
+/// Missing files and other hard errors fail straight away.
+fn read_with_retry(file: &std::path::Path) -> std::io::Result<Vec<u8>> {
+  let mut delays = READ_RETRY_DELAYS_MS.iter();
+  loop {
+    match std::fs::read(file) {
+      Err(e) if is_transient_read_error(&e) => match delays.next() {
+        Some(ms) => std::thread::sleep(std::time::Duration::from_millis(*ms)),
+        None => return Err(e),
+      },
+      result => return result,
+    }
   }
-  let text = std::fs::read_to_string(&paths.index_file)
-    .map_err(|e| format!("read index failed: {e}"))?;
-  match serde_json::from_str::<BoardIndex>(&text) {
-    Ok(index) => {
-      if index.boards.is_empty() {
-        rebuild_index_from_fs(paths)
-      } else {
-        sync_index_with_fs(paths, index)
-      }
+}
+
+/// Reads a `board.json`, replacing invalid UTF-8 with U+FFFD instead of failing
+/// so a mangled name from a bad write can't hide the board.
+fn read_board_text(file: &std::path::Path) -> Result<String, AppError> {
+  let bytes = read_with_retry(file).map_err(|e| AppError::Io(format!("read failed: {e}")))?;
+  match String::from_utf8(bytes) {
+    Ok(text) => Ok(text),
+    Err(e) => {
+      log::warn!("{} is not valid UTF-8; replacing invalid sequences", file.display());
+      Ok(String::from_utf8_lossy(e.as_bytes()).into_owned())
     }
-    Err(_) => rebuild_index_from_fs(paths),
   }
 }
 
-fn ensure_board_index(paths: &AppPaths) -> Result<BoardIndex, String> {
-  ensure_root_dir(paths)?;
-  rebuild_index_from_fs(paths)
+fn read_board(paths: &BoardPaths) -> Result<Board, AppError> {
+  let text = read_board_text(&paths.file)?;
+  parse_board(&text)
 }
 
-fn ensure_board_index_contains(
+/// Loads an existing, non-trashed board for an in-place edit. Callers should
+/// hold the board's lock until the edit is committed.
+fn open_live_board(
   paths: &AppPaths,
-  mut index: BoardIndex,
   board_id: &str,
-  board_name: &str,
-) -> Result<BoardIndex, String> {
+) -> Result<(BoardPaths, Board, BoardIndex), AppError> {
+  if !is_valid_board_id(board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let index = read_index(paths)?;
+  match index.boards.iter().find(|b| b.id == board_id) {
+    Some(meta) if meta.deleted_at.is_some() => {
+      return Err(AppError::Deleted("board is deleted".to_string()))
+    }
+    Some(_) => {}
+    None => return Err(AppError::NotFound("board not found".to_string())),
+  }
+  let board_paths = board_paths(&paths.root_dir, board_id);
+  if !board_paths.file.exists() {
+    return Err(AppError::NotFound("board not found".to_string()));
+  }
+  let board = read_board(&board_paths)?;
+  Ok((board_paths, board, index))
+}
+
+fn commit_board(
+  paths: &AppPaths,
+  board_paths: &BoardPaths,
+  index: BoardIndex,
+  board: &Board,
+) -> Result<(), AppError> {
+  write_board_atomic(board_paths, board)?;
+  let _ = ensure_board_index_contains(paths, index, &board.id, &board.name)?;
+  Ok(())
+}
+
+fn write_index_atomic(paths: &AppPaths, index: &BoardIndex) -> Result<(), AppError> {
+  let json = serde_json::to_string_pretty(index)
+    .map_err(|e| AppError::Serialize(format!("serialize failed: {e}")))?;
+  let tmp = paths.index_file.with_extension("json.tmp");
+  std::fs::write(&tmp, json).map_err(|e| AppError::Io(format!("write index temp failed: {e}")))?;
+  replace_file(&tmp, &paths.index_file)
+    .map_err(|e| AppError::Io(format!("rename index failed: {e}")))?;
+  Ok(())
+}
+
+fn read_index(paths: &AppPaths) -> Result<BoardIndex, AppError> {
+  ensure_root_dir(paths)?;
+  if !paths.index_file.exists() {
+    return rebuild_index_from_fs(paths);
+  }
+  let bytes = read_with_retry(&paths.index_file)
+    .map_err(|e| AppError::Io(format!("read index failed: {e}")))?;
+  match serde_json::from_slice::<BoardIndex>(&bytes) {
+    Ok(index) => {
+      if index.boards.is_empty() {
+        rebuild_index_from_fs(paths)
+      } else {
+        sync_index_with_fs(paths, index)
+      }
+    }
+    Err(_) => rebuild_index_from_fs(paths),
+  }
+}
+
+struct SettingsState(std::sync::Mutex<AppSettings>);
+
+impl SettingsState {
+  fn current(&self) -> AppSettings {
+    self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+  }
+}
+
+/// Long-lived HTTP clients so repeated fetches and chats reuse pooled
+/// connections. `reqwest::Client` is an `Arc` inside, so cloning is cheap.
+#[derive(Clone)]
+struct HttpClients {
+  fetch: reqwest::Client,
+  chat: reqwest::Client,
+}
+
+impl HttpClients {
+  fn build(settings: &AppSettings) -> Result<Self, AppError> {
+    let fetch = reqwest::Client::builder()
+      .user_agent(settings.user_agent.value())
+      .timeout(settings.fetch_timeout())
+      .redirect(safe_redirect_policy())
+      .dns_resolver(std::sync::Arc::new(PublicDnsResolver))
+      .build()
+      .map_err(|e| AppError::Other(format!("http client failed: {e}")))?;
+    let chat = reqwest::Client::builder()
+      .user_agent(lana_user_agent())
+      .build()
+      .map_err(|e| AppError::Other(format!("http client failed: {e}")))?;
+    Ok(Self { fetch, chat })
+  }
+}
+
+struct HttpState(std::sync::Mutex<HttpClients>);
+
+impl HttpState {
+  fn current(&self) -> HttpClients {
+    self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+  }
+
+  fn rebuild(&self, settings: &AppSettings) -> Result<(), AppError> {
+    self.set(HttpClients::build(settings)?);
+    Ok(())
+  }
+
+  fn set(&self, clients: HttpClients) {
+    *self.0.lock().unwrap_or_else(|e| e.into_inner()) = clients;
+  }
+}
+
+fn validate_ollama_url(url: &str) -> Result<(), AppError> {
+  let parsed =
+    Url::parse(url.trim()).map_err(|e| AppError::Invalid(format!("invalid ollama url: {e}")))?;
+  if parsed.scheme() != "http" && parsed.scheme() != "https" {
+    return Err(AppError::Invalid(
+      "ollama url must be http or https".to_string(),
+    ));
+  }
+  if parsed.host_str().is_none() {
+    return Err(AppError::Invalid("ollama url has no host".to_string()));
+  }
+  Ok(())
+}
+
+fn validate_settings(settings: &AppSettings) -> Result<(), AppError> {
+  if let ChatBackend::OpenAICompatible { base_url, .. } = &settings.chat_backend {
+    let parsed = Url::parse(base_url.trim())
+      .map_err(|e| AppError::Invalid(format!("invalid backend url: {e}")))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+      return Err(AppError::Invalid(
+        "unsupported backend url scheme".to_string(),
+      ));
+    }
+  }
+  if let Some(url) = &settings.ollama_url {
+    validate_ollama_url(url)?;
+  }
+  if let UserAgentSetting::Custom(value) = &settings.user_agent {
+    if value.trim().is_empty() {
+      return Err(AppError::Invalid("custom user agent is empty".to_string()));
+    }
+    if value.chars().any(|c| c.is_control()) {
+      return Err(AppError::Invalid(
+        "user agent contains control characters".to_string(),
+      ));
+    }
+  }
+  if settings.max_image_dimension() < MIN_MAX_IMAGE_DIMENSION {
+    return Err(AppError::Invalid(format!(
+      "max image dimension must be at least {MIN_MAX_IMAGE_DIMENSION}"
+    )));
+  }
+  if !(1..=MAX_MAX_CONCURRENCY as usize).contains(&settings.max_concurrency()) {
+    return Err(AppError::Invalid(format!(
+      "max concurrency must be between 1 and {MAX_MAX_CONCURRENCY}"
+    )));
+  }
+  if let Some(ms) = settings.fetch_timeout_ms {
+    if !(MIN_FETCH_TIMEOUT_MS..=MAX_FETCH_TIMEOUT_MS).contains(&ms) {
+      return Err(AppError::Invalid(format!(
+        "fetch timeout must be between {MIN_FETCH_TIMEOUT_MS} and {MAX_FETCH_TIMEOUT_MS} ms"
+      )));
+    }
+  }
+  if settings
+    .link_cache_ttl_hours
+    .is_some_and(|h| h > MAX_LINK_CACHE_TTL_HOURS)
+  {
+    return Err(AppError::Invalid(format!(
+      "link cache ttl must be at most {MAX_LINK_CACHE_TTL_HOURS} hours"
+    )));
+  }
+  Ok(())
+}
+
+fn write_settings_atomic(paths: &AppPaths, settings: &AppSettings) -> Result<(), AppError> {
+  if let Some(dir) = paths.settings_file.parent() {
+    std::fs::create_dir_all(dir)
+      .map_err(|e| AppError::Io(format!("create settings dir failed: {e}")))?;
+  }
+  let json = serde_json::to_string_pretty(settings)
+    .map_err(|e| AppError::Serialize(format!("serialize failed: {e}")))?;
+  let tmp = paths.settings_file.with_extension("json.tmp");
+  std::fs::write(&tmp, json)
+    .map_err(|e| AppError::Io(format!("write settings temp failed: {e}")))?;
+  replace_file(&tmp, &paths.settings_file)
+    .map_err(|e| AppError::Io(format!("rename settings failed: {e}")))?;
+  Ok(())
+}
+
+fn read_settings(paths: &AppPaths) -> AppSettings {
+  let text = match std::fs::read_to_string(&paths.settings_file) {
+    Ok(text) => text,
+    Err(_) => return AppSettings::default(),
+  };
+  match serde_json::from_str::<AppSettings>(&text) {
+    Ok(settings) => settings,
+    Err(e) => {
+      log::warn!("settings parse failed, using defaults: {e}");
+      AppSettings::default()
+    }
+  }
+}
+
+fn ensure_board_index(paths: &AppPaths) -> Result<BoardIndex, AppError> {
+  ensure_root_dir(paths)?;
+  rebuild_index_from_fs(paths)
+}
+
+fn ensure_board_index_contains(
+  paths: &AppPaths,
+  mut index: BoardIndex,
+  board_id: &str,
+  board_name: &str,
+) -> Result<BoardIndex, AppError> {
   if let Some(meta) = index.boards.iter_mut().find(|b| b.id == board_id) {
     meta.name = board_name.to_string();
     meta.updated_at = now_millis();
@@ -328,12 +2109,61 @@ fn ensure_board_index_contains(
       name: board_name.to_string(),
       updated_at: now_millis(),
       deleted_at: None,
+      pinned: false,
     });
   }
   write_index_atomic(paths, &index)?;
   Ok(index)
 }
 
+/// Gives every card and column a fresh id, rewriting column membership to match.
+fn remap_board_ids(board: &mut Board) {
+  let stamp = now_millis();
+  let mut card_ids = std::collections::HashMap::new();
+  for (i, card) in board.cards.iter_mut().enumerate() {
+    let id = format!("card-{stamp}-{i}");
+    card_ids.insert(std::mem::replace(&mut card.id, id.clone()), id);
+  }
+  for (i, column) in board.columns.iter_mut().enumerate() {
+    column.id = format!("column-{stamp}-{i}");
+    column.card_ids = column
+      .card_ids
+      .iter()
+      .filter_map(|id| card_ids.get(id).cloned())
+      .collect();
+  }
+}
+
+/// Directory-safe template id derived from its display name.
+fn template_slug(name: &str) -> Option<String> {
+  let mut slug = String::new();
+  for c in name.trim().to_lowercase().chars() {
+    if c.is_alphanumeric() {
+      slug.push(c);
+    } else if !slug.is_empty() && !slug.ends_with('-') {
+      slug.push('-');
+    }
+  }
+  let slug: String = slug.trim_end_matches('-').chars().take(64).collect();
+  if slug.is_empty() {
+    None
+  } else {
+    Some(slug)
+  }
+}
+
+/// Copies a single asset file into `dest_dir`, keeping its file name.
+fn copy_asset_into(file: &std::path::Path, dest_dir: &std::path::Path) -> Result<(), AppError> {
+  if !file.is_file() || is_symlink(file) {
+    return Ok(());
+  }
+  if let Some(name) = file.file_name() {
+    std::fs::copy(file, dest_dir.join(name))
+      .map_err(|e| AppError::Io(format!("copy asset failed: {e}")))?;
+  }
+  Ok(())
+}
+
 fn generate_board_id(paths: &AppPaths, index: &BoardIndex) -> String {
   let base = format!("board-{}", now_millis());
   if !index.boards.iter().any(|b| b.id == base) && !paths.root_dir.join(&base).exists() {
@@ -351,6 +2181,21 @@ fn generate_board_id(paths: &AppPaths, index: &BoardIndex) -> String {
   }
 }
 
+const DEEPLINK_SCHEME: &str = "lana";
+
+/// Extracts the board id from a `lana://board/<id>` link.
+fn board_id_from_deeplink(url: &Url) -> Option<String> {
+  if url.scheme() != DEEPLINK_SCHEME || url.host_str() != Some("board") {
+    return None;
+  }
+  let mut segments = url.path_segments()?.filter(|s| !s.is_empty());
+  let board_id = segments.next()?;
+  if segments.next().is_some() || !is_valid_board_id(board_id) {
+    return None;
+  }
+  Some(board_id.to_string())
+}
+
 fn is_safe_url(url: &Url) -> bool {
   let host = match url.host_str() {
     Some(host) => host,
@@ -359,20 +2204,138 @@ fn is_safe_url(url: &Url) -> bool {
   if host.eq_ignore_ascii_case("localhost") || host.ends_with(".local") {
     return false;
   }
-  if let Ok(ip) = host.parse::<IpAddr>() {
-    return match ip {
-      IpAddr::V4(v4) => {
-        !(v4.is_private() || v4.is_loopback() || v4.is_link_local() || v4.is_unspecified())
-      }
-      IpAddr::V6(v6) => {
+  // IPv6 hosts come bracketed, e.g. `[::1]`.
+  let literal = host.strip_prefix('[').and_then(|h| h.strip_suffix(']')).unwrap_or(host);
+  match literal.parse::<IpAddr>() {
+    Ok(ip) => is_public_ip(ip),
+    Err(_) => true,
+  }
+}
+
+/// False for loopback, private, link-local, CGNAT and unspecified addresses,
+/// including IPv4 ones written as IPv4-mapped IPv6 (`::ffff:10.0.0.1`).
+fn is_public_ip(ip: IpAddr) -> bool {
+  match ip {
+    IpAddr::V4(v4) => {
+      let [a, b, ..] = v4.octets();
+      let cgnat = a == 100 && (b & 0xc0) == 64;
+      !(v4.is_private() || v4.is_loopback() || v4.is_link_local() || v4.is_unspecified() || cgnat)
+    }
+    IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+      Some(v4) => is_public_ip(IpAddr::V4(v4)),
+      None => {
         !(v6.is_loopback()
           || v6.is_unicast_link_local()
           || v6.is_unique_local()
           || v6.is_unspecified())
       }
-    };
+    },
   }
-  true
+}
+
+const BLOCKED_ADDRESS: &str = "blocked private address";
+
+/// Resolves with the system resolver, then drops non-public addresses, so a
+/// public-looking name that points at the local network can't be fetched.
+/// `is_safe_url` only sees the host name; this checks where it really leads.
+struct PublicDnsResolver;
+
+impl reqwest::dns::Resolve for PublicDnsResolver {
+  fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+    let host = name.as_str().to_string();
+    Box::pin(async move {
+      let addrs = tauri::async_runtime::spawn_blocking(move || {
+        std::net::ToSocketAddrs::to_socket_addrs(&(host.as_str(), 0))
+          .map(|addrs| addrs.collect::<Vec<_>>())
+      })
+      .await??;
+      let public: Vec<std::net::SocketAddr> = addrs
+        .into_iter()
+        .filter(|addr| is_public_ip(addr.ip()))
+        .collect();
+      if public.is_empty() {
+        return Err(BLOCKED_ADDRESS.into());
+      }
+      let addrs: reqwest::dns::Addrs = Box::new(public.into_iter());
+      Ok(addrs)
+    })
+  }
+}
+
+const MAX_FETCH_REDIRECTS: usize = 5;
+const BLOCKED_REDIRECT: &str = "blocked redirect target";
+
+/// Follows at most `MAX_FETCH_REDIRECTS` hops and re-checks every hop with
+/// `is_safe_url`, so a public page can't bounce the fetch onto the local network.
+fn safe_redirect_policy() -> reqwest::redirect::Policy {
+  reqwest::redirect::Policy::custom(|attempt| {
+    match redirect_refusal(attempt.url(), attempt.previous().len()) {
+      Some(reason) => attempt.error(reason),
+      None => attempt.follow(),
+    }
+  })
+}
+
+/// Why a redirect to `url`, after `hops` earlier requests, must not be followed.
+fn redirect_refusal(url: &Url, hops: usize) -> Option<&'static str> {
+  if (url.scheme() != "http" && url.scheme() != "https") || !is_safe_url(url) {
+    Some(BLOCKED_REDIRECT)
+  } else if hops > MAX_FETCH_REDIRECTS {
+    Some("too many redirects")
+  } else {
+    None
+  }
+}
+
+/// True when `message` is somewhere in the causes of a reqwest error, e.g.
+/// `BLOCKED_REDIRECT` from `safe_redirect_policy`.
+fn caused_by(e: &reqwest::Error, message: &str) -> bool {
+  let mut source = std::error::Error::source(e);
+  while let Some(err) = source {
+    if err.to_string() == message {
+      return true;
+    }
+    source = err.source();
+  }
+  false
+}
+
+const TRACKING_PARAMS: [&str; 7] =
+  ["fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "ref_src"];
+
+/// Normalizes a link URL into a comparison key: scheme, `www.`, fragments,
+/// tracking params and trailing slashes are ignored and the query is sorted.
+fn canonical_url_key(raw: &str) -> Option<String> {
+  let url = Url::parse(raw.trim()).ok()?;
+  if url.scheme() != "http" && url.scheme() != "https" {
+    return None;
+  }
+  let host = url.host_str()?.to_ascii_lowercase();
+  let host = host.strip_prefix("www.").unwrap_or(&host).to_string();
+
+  let mut params: Vec<(String, String)> = url
+    .query_pairs()
+    .filter(|(k, _)| {
+      let key = k.to_ascii_lowercase();
+      !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_str())
+    })
+    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+    .collect();
+  params.sort();
+
+  let path = url.path().trim_end_matches('/');
+  let mut key = format!("{host}{path}");
+  if let Some(port) = url.port() {
+    key = format!("{host}:{port}{path}");
+  }
+  if !params.is_empty() {
+    let query = url::form_urlencoded::Serializer::new(String::new())
+      .extend_pairs(params)
+      .finish();
+    key.push('?');
+    key.push_str(&query);
+  }
+  Some(key)
 }
 
 fn clean_text(value: &str) -> Option<String> {
@@ -469,489 +2432,7119 @@ fn meta_content(doc: &Html, selector: &str) -> Option<String> {
   clean_text(content)
 }
 
-fn title_text(doc: &Html) -> Option<String> {
-  let sel = Selector::parse("title").ok()?;
-  let el = doc.select(&sel).next()?;
-  clean_text(&el.inner_html())
+fn meta_contents(doc: &Html, selector: &str) -> Vec<String> {
+  let sel = match Selector::parse(selector) {
+    Ok(sel) => sel,
+    Err(_) => return vec![],
+  };
+  doc
+    .select(&sel)
+    .filter_map(|el| el.value().attr("content"))
+    .filter_map(clean_text)
+    .collect()
 }
 
-fn ext_from_content_type(content_type: &str) -> Option<&'static str> {
-  let ct = content_type.to_ascii_lowercase();
-  if ct.starts_with("image/jpeg") || ct.starts_with("image/jpg") {
-    Some(".jpg")
-  } else if ct.starts_with("image/png") {
-    Some(".png")
-  } else if ct.starts_with("image/webp") {
-    Some(".webp")
-  } else if ct.starts_with("image/gif") {
-    Some(".gif")
-  } else {
-    None
+/// Every object node in the page's JSON-LD blocks, with top-level arrays and
+/// `@graph` lists flattened. Blocks that don't parse are skipped.
+fn json_ld_nodes(doc: &Html) -> Vec<serde_json::Map<String, serde_json::Value>> {
+  fn collect(value: serde_json::Value, out: &mut Vec<serde_json::Map<String, serde_json::Value>>) {
+    match value {
+      serde_json::Value::Array(items) => items.into_iter().for_each(|v| collect(v, out)),
+      serde_json::Value::Object(mut map) => {
+        let graph = map.remove("@graph");
+        out.push(map);
+        if let Some(graph) = graph {
+          collect(graph, out);
+        }
+      }
+      _ => {}
+    }
   }
+  let sel = match Selector::parse("script[type='application/ld+json']") {
+    Ok(sel) => sel,
+    Err(_) => return vec![],
+  };
+  let mut out = Vec::new();
+  for el in doc.select(&sel) {
+    let text = el.text().collect::<String>();
+    if let Ok(value) = serde_json::from_str::<serde_json::Value>(&text) {
+      collect(value, &mut out);
+    }
+  }
+  out
 }
 
-fn save_asset_bytes(
-  paths: &AppPaths,
-  board_id: &str,
-  bytes: &[u8],
-  ext: &str,
-) -> Result<String, String> {
-  let index = read_index(paths)?;
-  let name = index
-    .boards
+/// Node types whose `name` is the publisher or site rather than the page.
+const JSON_LD_NON_PAGE_TYPES: [&str; 5] =
+  ["Organization", "Person", "WebSite", "BreadcrumbList", "ImageObject"];
+
+/// A JSON-LD `headline`, else the `name` of the first node describing the page.
+fn json_ld_title(doc: &Html) -> Option<String> {
+  let nodes = json_ld_nodes(doc);
+  let text = |node: &serde_json::Map<String, serde_json::Value>, key: &str| {
+    node.get(key).and_then(|v| v.as_str()).and_then(clean_text)
+  };
+  let is_page = |node: &serde_json::Map<String, serde_json::Value>| {
+    let kinds: Vec<&str> = match node.get("@type") {
+      Some(serde_json::Value::String(kind)) => vec![kind.as_str()],
+      Some(serde_json::Value::Array(kinds)) => kinds.iter().filter_map(|k| k.as_str()).collect(),
+      _ => vec![],
+    };
+    !kinds.iter().any(|k| JSON_LD_NON_PAGE_TYPES.contains(k))
+  };
+  nodes
     .iter()
-    .find(|b| b.id == board_id)
-    .map(|b| b.name.as_str())
-    .unwrap_or("Untitled");
-  let board_paths = board_paths(&paths.root_dir, board_id);
-  ensure_board_file(&board_paths, board_id, name)?;
+    .find_map(|node| text(node, "headline"))
+    .or_else(|| nodes.iter().filter(|node| is_page(node)).find_map(|node| text(node, "name")))
+}
+
+/// Collects `image` values from JSON-LD blocks: a URL string, an ImageObject,
+/// or a list of either.
+fn json_ld_images(doc: &Html) -> Vec<String> {
+  fn collect_image(value: &serde_json::Value, out: &mut Vec<String>) {
+    match value {
+      serde_json::Value::String(url) => out.push(url.trim().to_string()),
+      serde_json::Value::Array(items) => items.iter().for_each(|v| collect_image(v, out)),
+      serde_json::Value::Object(map) => {
+        if let Some(serde_json::Value::String(url)) = map.get("url").or(map.get("contentUrl")) {
+          out.push(url.trim().to_string());
+        }
+      }
+      _ => {}
+    }
+  }
+  let mut out = Vec::new();
+  for node in json_ld_nodes(doc) {
+    if let Some(image) = node.get("image") {
+      collect_image(image, &mut out);
+    }
+  }
+  out
+}
+
+/// The page's `<img>` elements with declared sizes, largest first.
+fn sized_img_sources(doc: &Html) -> Vec<String> {
+  let sel = match Selector::parse("img[src]") {
+    Ok(sel) => sel,
+    Err(_) => return vec![],
+  };
+  // u32 like `ProbedImage`, so the widened product can't overflow.
+  let dim = |el: &scraper::ElementRef, name: &str| -> Option<u64> {
+    let value: u32 = el
+      .value()
+      .attr(name)?
+      .trim()
+      .trim_end_matches("px")
+      .parse()
+      .ok()?;
+    Some(u64::from(value))
+  };
+  let mut imgs: Vec<(u64, String)> = doc
+    .select(&sel)
+    .filter_map(|el| {
+      let area = dim(&el, "width")? * dim(&el, "height")?;
+      Some((area, el.value().attr("src")?.trim().to_string()))
+    })
+    .collect();
+  imgs.sort_by_key(|(area, _)| std::cmp::Reverse(*area));
+  imgs.into_iter().map(|(_, src)| src).collect()
+}
+
+const MAX_IMAGE_CANDIDATES: usize = 6;
+const MAX_LINK_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+/// Images whose shorter side is below this are usually logos or icons.
+const MIN_PREVIEW_IMAGE_SIDE: u32 = 120;
+
+/// Preview image URLs in preference order: Open Graph, Twitter, JSON-LD, then
+/// the largest declared `<img>`. Resolved, deduplicated and safety-checked.
+fn image_candidates(doc: &Html, base: &Url) -> Vec<Url> {
+  let raw = meta_contents(doc, "meta[property='og:image']")
+    .into_iter()
+    .chain(meta_contents(doc, "meta[property='og:image:secure_url']"))
+    .chain(meta_contents(doc, "meta[name='twitter:image']"))
+    .chain(meta_contents(doc, "meta[name='twitter:image:src']"))
+    .chain(json_ld_images(doc))
+    .chain(sized_img_sources(doc).into_iter().take(2));
+  let mut seen = std::collections::HashSet::new();
+  raw
+    .filter_map(|value| base.join(&value).ok())
+    .filter(|url| matches!(url.scheme(), "http" | "https") && is_safe_url(url))
+    .filter(|url| seen.insert(url.to_string()))
+    .take(MAX_IMAGE_CANDIDATES)
+    .collect()
+}
 
-  let safe_ext = if ext.starts_with('.') { ext.to_string() } else { format!(".{ext}") };
-  let filename = format!("link-{}{}", now_millis(), safe_ext);
-  let safe_name = filename
-    .replace('\\', "_")
-    .replace('/', "_")
-    .replace("..", "_");
+struct ProbedImage {
+  bytes: Vec<u8>,
+  content_type: String,
+  width: u32,
+  height: u32,
+}
 
-  let out = board_paths.assets_dir.join(&safe_name);
-  let tmp = board_paths.assets_dir.join(format!("{safe_name}.tmp"));
-  std::fs::write(&tmp, bytes).map_err(|e| format!("write temp image failed: {e}"))?;
-  if out.exists() {
-    let _ = std::fs::remove_file(&out);
+impl ProbedImage {
+  /// Images that aren't icon-sized win, then the larger area.
+  fn rank(&self) -> (bool, u64) {
+    let suitable = self.width.min(self.height) >= MIN_PREVIEW_IMAGE_SIDE;
+    (suitable, self.width as u64 * self.height as u64)
   }
-  std::fs::rename(&tmp, &out).map_err(|e| format!("rename image failed: {e}"))?;
-  Ok(format!("assets/{safe_name}"))
 }
 
-fn read_board_name(file: &std::path::Path) -> Option<String> {
-  std::fs::read_to_string(file)
-    .ok()
-    .and_then(|text| serde_json::from_str::<Board>(&text).ok())
-    .map(|b| b.name)
+/// Reads a response body, refusing up front when Content-Length is over
+/// `limit` and stopping mid-stream once the bytes received pass it.
+async fn read_body_capped(mut resp: reqwest::Response, limit: usize) -> Result<Vec<u8>, AppError> {
+  if resp.content_length().is_some_and(|len| len > limit as u64) {
+    return Err(AppError::QuotaExceeded("response too large".to_string()));
+  }
+  let mut body: Vec<u8> = Vec::new();
+  while let Some(chunk) = resp.chunk().await.map_err(fetch_error)? {
+    if body.len() + chunk.len() > limit {
+      return Err(AppError::QuotaExceeded("response too large".to_string()));
+    }
+    body.extend_from_slice(&chunk);
+  }
+  Ok(body)
 }
 
-fn file_modified_millis(file: &std::path::Path) -> Option<i64> {
-  std::fs::metadata(file)
-    .and_then(|m| m.modified())
-    .ok()
-    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-    .map(|d| d.as_millis() as i64)
+/// Downloads a candidate image, checking its type, size and dimensions.
+async fn probe_image(client: &reqwest::Client, url: &Url) -> Option<ProbedImage> {
+  let resp = client.get(url.clone()).send().await.ok()?;
+  if !resp.status().is_success() {
+    return None;
+  }
+  let content_type = resp
+    .headers()
+    .get(CONTENT_TYPE)
+    .and_then(|v| v.to_str().ok())
+    .unwrap_or("")
+    .to_string();
+  if !content_type.starts_with("image/") {
+    return None;
+  }
+  let bytes = read_body_capped(resp, MAX_LINK_IMAGE_BYTES).await.ok()?;
+  let (width, height) = image::ImageReader::new(std::io::Cursor::new(&bytes[..]))
+    .with_guessed_format()
+    .ok()?
+    .into_dimensions()
+    .ok()?;
+  Some(ProbedImage {
+    bytes,
+    content_type,
+    width,
+    height,
+  })
 }
 
-fn rebuild_index_from_fs(paths: &AppPaths) -> Result<BoardIndex, String> {
-  ensure_root_dir(paths)?;
-  let mut boards = Vec::new();
-  let entries = std::fs::read_dir(&paths.root_dir)
-    .map_err(|e| format!("read boards dir failed: {e}"))?;
-  for entry in entries.flatten() {
-    let path = entry.path();
-    if !path.is_dir() {
+const MAX_KEYWORDS: usize = 20;
+const MAX_KEYWORD_LEN: usize = 64;
+
+fn extract_keywords(doc: &Html) -> Vec<String> {
+  let mut raw = Vec::new();
+  for value in meta_contents(doc, "meta[name='keywords']") {
+    raw.extend(value.split(',').map(|k| k.to_string()));
+  }
+  raw.extend(meta_contents(doc, "meta[property='article:tag']"));
+
+  let mut seen = std::collections::HashSet::new();
+  let mut keywords = Vec::new();
+  for keyword in raw {
+    let keyword = keyword.split_whitespace().collect::<Vec<_>>().join(" ");
+    if keyword.is_empty() || keyword.chars().count() > MAX_KEYWORD_LEN {
       continue;
     }
-    let board_id = match path.file_name().and_then(|n| n.to_str()) {
-      Some(name) => name.to_string(),
-      None => continue,
-    };
-    if !is_valid_board_id(&board_id) {
-      continue;
+    if seen.insert(keyword.to_lowercase()) {
+      keywords.push(keyword);
     }
-    let board_file = path.join("board.json");
-    if !board_file.exists() {
-      continue;
+    if keywords.len() >= MAX_KEYWORDS {
+      break;
     }
-    let name = read_board_name(&board_file).unwrap_or_else(|| board_id.clone());
-    let updated_at = file_modified_millis(&board_file).unwrap_or_else(now_millis);
-    boards.push(BoardMeta {
-      id: board_id,
-      name,
-      updated_at,
-      deleted_at: None,
-    });
   }
-  let trash_dir = paths.root_dir.join("trash");
-  if trash_dir.exists() {
-    let trash_entries = std::fs::read_dir(&trash_dir)
-      .map_err(|e| format!("read trash dir failed: {e}"))?;
-    for entry in trash_entries.flatten() {
-      let path = entry.path();
-      if !path.is_dir() {
-        continue;
-      }
-      let board_id = match path.file_name().and_then(|n| n.to_str()) {
-        Some(name) => name.to_string(),
-        None => continue,
-      };
-      if !is_valid_board_id(&board_id) {
-        continue;
-      }
-      let board_file = path.join("board.json");
-      if !board_file.exists() {
-        continue;
-      }
-      let name = read_board_name(&board_file).unwrap_or_else(|| board_id.clone());
-      let deleted_at = file_modified_millis(&board_file).unwrap_or_else(now_millis);
-      boards.push(BoardMeta {
-        id: board_id,
-        name,
-        updated_at: deleted_at,
-        deleted_at: Some(deleted_at),
-      });
+  keywords
+}
+
+fn title_text(doc: &Html) -> Option<String> {
+  let sel = Selector::parse("title").ok()?;
+  let el = doc.select(&sel).next()?;
+  clean_text(&el.inner_html())
+}
+
+async fn fetch_page(client: &reqwest::Client, url: Url) -> Result<(Url, String), AppError> {
+  let resp = client
+    .get(url)
+    .header(reqwest::header::ACCEPT, "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
+    .header(reqwest::header::ACCEPT_LANGUAGE, "en-US,en;q=0.9")
+    .header(reqwest::header::ACCEPT_ENCODING, "gzip, deflate, br")
+    .send()
+    .await
+    .map_err(fetch_error)?;
+
+  let final_url = resp.url().clone();
+  let content_type =
+    resp.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+  let bytes = resp.bytes().await.map_err(|e| {
+    if e.is_timeout() {
+      fetch_error(e)
+    } else {
+      AppError::Network(format!("read body failed: {e}"))
     }
+  })?;
+  Ok((final_url, decode_html(&bytes, content_type.as_deref())))
+}
+
+/// The `charset=` value from a header or the head of a page, if any.
+fn charset_param(text: &str) -> Option<String> {
+  let lower = text.to_ascii_lowercase();
+  let start = lower.find("charset=")? + "charset=".len();
+  let label: String = lower[start..]
+    .trim_start_matches(['"', '\''])
+    .chars()
+    .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+    .collect();
+  if label.is_empty() {
+    None
+  } else {
+    Some(label)
   }
-  let index = BoardIndex { version: 1, boards };
-  write_index_atomic(paths, &index)?;
-  Ok(index)
 }
 
-fn sync_index_with_fs(paths: &AppPaths, mut index: BoardIndex) -> Result<BoardIndex, String> {
-  ensure_root_dir(paths)?;
-  let mut changed = false;
-  let mut seen = std::collections::HashSet::new();
-  let mut seen_trash = std::collections::HashSet::new();
+/// How many leading bytes to scan for `<meta charset>`, as browsers do.
+const CHARSET_SNIFF_BYTES: usize = 1024;
 
-  let entries = std::fs::read_dir(&paths.root_dir)
-    .map_err(|e| format!("read boards dir failed: {e}"))?;
-  for entry in entries.flatten() {
-    let path = entry.path();
-    if !path.is_dir() {
+/// Decodes a page using, in order, its byte order mark, the Content-Type
+/// charset, and a `<meta charset>` near the top, falling back to UTF-8.
+/// Labels encoding_rs doesn't know are skipped.
+fn decode_html(bytes: &[u8], content_type: Option<&str>) -> String {
+  let encoding_for = |label: String| encoding_rs::Encoding::for_label(label.as_bytes());
+  let head = &bytes[..bytes.len().min(CHARSET_SNIFF_BYTES)];
+  let encoding = content_type
+    .and_then(charset_param)
+    .and_then(encoding_for)
+    .or_else(|| charset_param(&String::from_utf8_lossy(head)).and_then(encoding_for))
+    .unwrap_or(encoding_rs::UTF_8);
+  let (text, _, _) = encoding.decode(bytes);
+  text.into_owned()
+}
+
+/// Keeps a slow site apart from an unreachable one so the UI can say which.
+fn fetch_error(e: reqwest::Error) -> AppError {
+  if caused_by(&e, BLOCKED_REDIRECT) {
+    let target = e.url().map(|u| u.to_string()).unwrap_or_default();
+    AppError::Blocked(format!("{BLOCKED_REDIRECT}: {target}"))
+  } else if caused_by(&e, BLOCKED_ADDRESS) {
+    let host = e
+      .url()
+      .and_then(|u| u.host_str())
+      .unwrap_or_default()
+      .to_string();
+    AppError::Blocked(format!("{BLOCKED_ADDRESS}: {host}"))
+  } else if e.is_timeout() {
+    AppError::Timeout(format!("fetch timed out: {e}"))
+  } else if e.is_connect() {
+    AppError::Network(format!("fetch failed: could not connect: {e}"))
+  } else {
+    AppError::Network(format!("fetch failed: {e}"))
+  }
+}
+
+fn strip_quotes(value: &str) -> &str {
+  value.trim().trim_matches(|c| c == '\'' || c == '"').trim()
+}
+
+fn meta_refresh_target(doc: &Html) -> Option<String> {
+  let sel = Selector::parse("meta[http-equiv]").ok()?;
+  for el in doc.select(&sel) {
+    let equiv = el.value().attr("http-equiv").unwrap_or("");
+    if !equiv.eq_ignore_ascii_case("refresh") {
       continue;
     }
-    let board_id = match path.file_name().and_then(|n| n.to_str()) {
-      Some(name) => name.to_string(),
+    let content = match el.value().attr("content") {
+      Some(content) => content,
       None => continue,
     };
-    if !is_valid_board_id(&board_id) {
-      continue;
-    }
-    let board_file = path.join("board.json");
-    if !board_file.exists() {
-      continue;
+    // content="0;url=https://example.com/" (the url= part may be quoted or uppercase)
+    let rest = match content.split_once(';') {
+      Some((_, rest)) => rest.trim(),
+      None => continue,
+    };
+    let target = match rest.get(..3) {
+      Some(prefix) if prefix.eq_ignore_ascii_case("url") => {
+        rest[3..].trim_start().strip_prefix('=').unwrap_or("")
+      }
+      _ => rest,
+    };
+    let target = strip_quotes(target);
+    if !target.is_empty() {
+      return Some(target.to_string());
     }
-    seen.insert(board_id.clone());
-    let name = read_board_name(&board_file).unwrap_or_else(|| board_id.clone());
-    let updated_at = file_modified_millis(&board_file).unwrap_or_else(now_millis);
-    match index.boards.iter_mut().find(|b| b.id == board_id) {
-      Some(meta) => {
-        if meta.name != name || meta.updated_at != updated_at {
-          meta.name = name;
-          meta.updated_at = updated_at;
-          changed = true;
+  }
+  None
+}
+
+fn js_redirect_target(doc: &Html) -> Option<String> {
+  let sel = Selector::parse("script").ok()?;
+  let patterns = [
+    "window.location.href=",
+    "window.location=",
+    "location.href=",
+    "location.replace(",
+    "location.assign(",
+  ];
+  for el in doc.select(&sel) {
+    let script: String = el.text().collect::<String>().split_whitespace().collect();
+    for pattern in patterns {
+      let idx = match script.find(pattern) {
+        Some(idx) => idx,
+        None => continue,
+      };
+      let rest = &script[idx + pattern.len()..];
+      let quote = match rest.chars().next() {
+        Some(c @ ('\'' | '"')) => c,
+        _ => continue,
+      };
+      if let Some(end) = rest[1..].find(quote) {
+        let target = &rest[1..1 + end];
+        if !target.is_empty() {
+          return Some(target.to_string());
         }
       }
-      None => {
-        index.boards.push(BoardMeta {
-          id: board_id,
-          name,
-          updated_at,
-          deleted_at: None,
-        });
-        changed = true;
-      }
     }
   }
+  None
+}
 
-  let trash_dir = paths.root_dir.join("trash");
-  if trash_dir.exists() {
-    let trash_entries = std::fs::read_dir(&trash_dir)
-      .map_err(|e| format!("read trash dir failed: {e}"))?;
-    for entry in trash_entries.flatten() {
-      let path = entry.path();
-      if !path.is_dir() {
-        continue;
+/// The registrable part of a host name: its last two labels, or three under a
+/// country code whose second level is generic (`example.co.uk`). An
+/// approximation of the public suffix list, good enough to tell sites apart.
+fn registrable_domain(host: &str) -> String {
+  let host = host.trim_end_matches('.').to_ascii_lowercase();
+  let labels: Vec<&str> = host.split('.').collect();
+  let keep = match labels.as_slice() {
+    [.., second, tld]
+      if tld.len() == 2
+        && matches!(
+          *second,
+          "co" | "com" | "net" | "org" | "gov" | "edu" | "ac" | "ne" | "or"
+        ) =>
+    {
+      3
+    }
+    _ => 2,
+  };
+  labels[labels.len().saturating_sub(keep)..].join(".")
+}
+
+/// The page's `<link rel="canonical">`, resolved against `base`. Canonicals
+/// that point at another scheme, a private host, or a different site than
+/// `base` are ignored.
+fn canonical_link(doc: &Html, base: &Url) -> Option<Url> {
+  let sel = Selector::parse("link[rel~='canonical'][href]").ok()?;
+  let href = doc.select(&sel).next()?.value().attr("href")?;
+  let resolved = base.join(href.trim()).ok()?;
+  let same_site = match (resolved.host(), base.host()) {
+    (Some(url::Host::Domain(host)), Some(url::Host::Domain(base_host))) => {
+      registrable_domain(host) == registrable_domain(base_host)
+    }
+    (host, base_host) => host.is_some() && host == base_host,
+  };
+  match resolved.scheme() {
+    "http" | "https" if same_site && is_safe_url(&resolved) => Some(resolved),
+    _ => None,
+  }
+}
+
+const MAX_FAVICON_BYTES: usize = 256 * 1024;
+
+/// Icon URLs to try in order: the first declared icon, preferring touch icons
+/// (usually the largest), then `/favicon.ico` at the site root.
+fn favicon_candidates(doc: &Html, base: &Url) -> Vec<Url> {
+  let usable = |value: &str| {
+    let url = base.join(value).ok()?;
+    (matches!(url.scheme(), "http" | "https") && is_safe_url(&url)).then_some(url)
+  };
+  let declared = [
+    "link[rel~='apple-touch-icon'][href]",
+    "link[rel~='apple-touch-icon-precomposed'][href]",
+    "link[rel~='icon'][href]",
+  ]
+  .into_iter()
+  .filter_map(|selector| Selector::parse(selector).ok())
+  .find_map(|sel| {
+    doc
+      .select(&sel)
+      .filter_map(|el| el.value().attr("href"))
+      .filter_map(clean_text)
+      .find_map(|href| usable(&href))
+  });
+  let mut icons: Vec<Url> = declared.into_iter().collect();
+  if let Some(root) = usable("/favicon.ico").filter(|root| !icons.contains(root)) {
+    icons.push(root);
+  }
+  icons
+}
+
+fn favicon_ext(content_type: &str) -> Option<&'static str> {
+  let ct = content_type.to_ascii_lowercase();
+  if ct.starts_with("image/x-icon") || ct.starts_with("image/vnd.microsoft.icon") {
+    Some(".ico")
+  } else {
+    ext_from_content_type(&ct)
+  }
+}
+
+async fn download_favicon(client: &reqwest::Client, url: &Url) -> Option<(Vec<u8>, &'static str)> {
+  let resp = client.get(url.clone()).send().await.ok()?;
+  if !resp.status().is_success() {
+    return None;
+  }
+  let ext = resp.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).and_then(favicon_ext)?;
+  let bytes = read_body_capped(resp, MAX_FAVICON_BYTES).await.ok()?;
+  if bytes.is_empty() {
+    return None;
+  }
+  Some((bytes, ext))
+}
+
+fn redirect_hint(text: &str, base: &Url) -> Option<Url> {
+  let doc = Html::parse_document(text);
+  let target = meta_refresh_target(&doc).or_else(|| {
+    // Only trust scripted redirects on bare landing shells; real pages often
+    // mention location.href in unrelated code.
+    if meta_content(&doc, "meta[property='og:title']").is_some() {
+      None
+    } else {
+      js_redirect_target(&doc)
+    }
+  })?;
+  let resolved = base.join(&target).ok()?;
+  match resolved.scheme() {
+    "http" | "https" => Some(resolved),
+    _ => None,
+  }
+}
+
+fn ext_from_content_type(content_type: &str) -> Option<&'static str> {
+  let ct = content_type.to_ascii_lowercase();
+  if ct.starts_with("image/jpeg") || ct.starts_with("image/jpg") {
+    Some(".jpg")
+  } else if ct.starts_with("image/png") {
+    Some(".png")
+  } else if ct.starts_with("image/webp") {
+    Some(".webp")
+  } else if ct.starts_with("image/gif") {
+    Some(".gif")
+  } else {
+    None
+  }
+}
+
+fn mime_from_ext(ext: &str) -> Option<&'static str> {
+  match ext.to_ascii_lowercase().as_str() {
+    "jpg" | "jpeg" => Some("image/jpeg"),
+    "png" => Some("image/png"),
+    "webp" => Some("image/webp"),
+    "gif" => Some("image/gif"),
+    "svg" => Some("image/svg+xml"),
+    "bmp" => Some("image/bmp"),
+    "avif" => Some("image/avif"),
+    _ => None,
+  }
+}
+
+fn html_escape(value: &str) -> String {
+  let mut out = String::with_capacity(value.len());
+  for c in value.chars() {
+    match c {
+      '&' => out.push_str("&amp;"),
+      '<' => out.push_str("&lt;"),
+      '>' => out.push_str("&gt;"),
+      '"' => out.push_str("&quot;"),
+      '\'' => out.push_str("&#39;"),
+      _ => out.push(c),
+    }
+  }
+  out
+}
+
+/// Escapes card text, rendering the UI's `**bold**` markers as `<strong>`.
+fn card_text_html(text: &str) -> String {
+  text
+    .split("**")
+    .enumerate()
+    .map(|(i, part)| {
+      if i % 2 == 1 {
+        format!("<strong>{}</strong>", html_escape(part))
+      } else {
+        html_escape(part)
       }
-      let board_id = match path.file_name().and_then(|n| n.to_str()) {
-        Some(name) => name.to_string(),
-        None => continue,
-      };
-      if !is_valid_board_id(&board_id) {
-        continue;
+    })
+    .collect()
+}
+
+const MAX_INLINE_ASSET_BYTES: u64 = 20 * 1024 * 1024;
+
+fn asset_data_uri(paths: &AppPaths, board_paths: &BoardPaths, asset_path: &str) -> Option<String> {
+  let file = card_file(paths, board_paths, asset_path)?;
+  let mime = mime_from_ext(file.extension()?.to_str()?)?;
+  if std::fs::metadata(&file).ok()?.len() > MAX_INLINE_ASSET_BYTES {
+    return None;
+  }
+  let bytes = std::fs::read(&file).ok()?;
+  let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+  Some(format!("data:{mime};base64,{encoded}"))
+}
+
+/// Obsidian Canvas (`.canvas`) JSON for a board. Coordinates are kept as-is;
+/// image nodes reference `assets/<file>`, which `export_board_canvas` fills
+/// next to the canvas. LANA has no connectors, so `edges` is empty.
+fn render_board_canvas(
+  paths: &AppPaths,
+  board_paths: &BoardPaths,
+  board: &Board,
+) -> serde_json::Value {
+  let finite = |v: f64| if v.is_finite() { v } else { 0.0 };
+  let mut nodes = Vec::new();
+
+  for column in &board.columns {
+    let bottom = column
+      .card_ids
+      .iter()
+      .filter_map(|id| board.cards.iter().find(|c| &c.id == id))
+      .map(|c| finite(c.y) + finite(c.height))
+      .fold(finite(column.y), f64::max);
+    nodes.push(serde_json::json!({
+      "id": format!("column-{}", column.id),
+      "type": "group",
+      "label": column.name,
+      "x": finite(column.x),
+      "y": finite(column.y),
+      "width": finite(column.width),
+      "height": bottom - finite(column.y),
+    }));
+  }
+
+  for card in &board.cards {
+    let mut node = match card.r#type.as_str() {
+      "image" => {
+        let src = card.src.as_deref().unwrap_or("");
+        match card_file(paths, board_paths, src).and_then(|f| {
+          f.file_name().and_then(|n| n.to_str()).map(|n| n.to_string())
+        }) {
+          Some(name) => serde_json::json!({ "type": "file", "file": format!("assets/{name}") }),
+          None => serde_json::json!({ "type": "link", "url": src }),
+        }
       }
-      let board_file = path.join("board.json");
-      if !board_file.exists() {
-        continue;
+      "link" => serde_json::json!({ "type": "link", "url": card.url.as_deref().unwrap_or("") }),
+      _ => serde_json::json!({ "type": "text", "text": card.text }),
+    };
+    node["id"] = serde_json::json!(card.id);
+    node["x"] = serde_json::json!(finite(card.x));
+    node["y"] = serde_json::json!(finite(card.y));
+    node["width"] = serde_json::json!(finite(card.width));
+    node["height"] = serde_json::json!(finite(card.height));
+    nodes.push(node);
+  }
+
+  serde_json::json!({ "nodes": nodes, "edges": [] })
+}
+
+fn render_board_html(paths: &AppPaths, board_paths: &BoardPaths, board: &Board) -> String {
+  const PAD: f64 = 48.0;
+  const LABEL: f64 = 28.0;
+  let finite = |v: f64| if v.is_finite() { v } else { 0.0 };
+
+  let mut min_x = f64::MAX;
+  let mut min_y = f64::MAX;
+  let mut max_x = f64::MIN;
+  let mut max_y = f64::MIN;
+  for card in &board.cards {
+    min_x = min_x.min(finite(card.x));
+    min_y = min_y.min(finite(card.y));
+    max_x = max_x.max(finite(card.x) + finite(card.width));
+    max_y = max_y.max(finite(card.y) + finite(card.height));
+  }
+  for column in &board.columns {
+    min_x = min_x.min(finite(column.x));
+    min_y = min_y.min(finite(column.y) - LABEL);
+    max_x = max_x.max(finite(column.x) + finite(column.width));
+  }
+  if board.cards.is_empty() && board.columns.is_empty() {
+    (min_x, min_y, max_x, max_y) = (0.0, 0.0, 0.0, 0.0);
+  }
+  let ox = PAD - min_x;
+  let oy = PAD - min_y;
+
+  let mut body = String::new();
+  for column in &board.columns {
+    let members: Vec<&Card> = column
+      .card_ids
+      .iter()
+      .filter_map(|id| board.cards.iter().find(|c| &c.id == id))
+      .collect();
+    let bottom = members
+      .iter()
+      .map(|c| finite(c.y) + finite(c.height))
+      .fold(finite(column.y), f64::max);
+    body.push_str(&format!(
+      concat!(
+        "<section class=\"column\" style=\"left:{}px;top:{}px;width:{}px;height:{}px\">",
+        "<h2>{}</h2></section>\n"
+      ),
+      column.x + ox - 8.0,
+      column.y + oy - LABEL,
+      column.width + 16.0,
+      bottom - column.y + LABEL + 8.0,
+      html_escape(&column.name),
+    ));
+  }
+
+  for card in &board.cards {
+    let mut inner = String::new();
+    match card.r#type.as_str() {
+      "image" => {
+        if let Some(uri) = card
+          .src
+          .as_deref()
+          .and_then(|src| asset_data_uri(paths, board_paths, src))
+        {
+          inner.push_str(&format!("<img src=\"{uri}\" alt=\"\">"));
+        }
       }
-      seen_trash.insert(board_id.clone());
-      let name = read_board_name(&board_file).unwrap_or_else(|| board_id.clone());
-      let deleted_at = file_modified_millis(&board_file).unwrap_or_else(now_millis);
-      match index.boards.iter_mut().find(|b| b.id == board_id) {
-        Some(meta) => {
-          if meta.name != name
-            || meta.updated_at != deleted_at
-            || meta.deleted_at != Some(deleted_at)
-          {
-            meta.name = name;
-            meta.updated_at = deleted_at;
-            meta.deleted_at = Some(deleted_at);
-            changed = true;
-          }
+      "link" => {
+        let url = card.url.as_deref().unwrap_or("");
+        let href = Url::parse(url)
+          .ok()
+          .filter(|u| u.scheme() == "http" || u.scheme() == "https")
+          .map(|u| u.to_string());
+        if let Some(uri) = card
+          .image
+          .as_deref()
+          .and_then(|img| asset_data_uri(paths, board_paths, img))
+        {
+          inner.push_str(&format!("<img src=\"{uri}\" alt=\"\">"));
         }
-        None => {
-          index.boards.push(BoardMeta {
-            id: board_id,
-            name,
-            updated_at: deleted_at,
-            deleted_at: Some(deleted_at),
-          });
-          changed = true;
+        let title = html_escape(card.title.as_deref().filter(|t| !t.is_empty()).unwrap_or(url));
+        match href {
+          Some(href) => inner.push_str(&format!(
+            "<a href=\"{}\" target=\"_blank\" rel=\"noopener noreferrer\">{title}</a>",
+            html_escape(&href)
+          )),
+          None => inner.push_str(&format!("<span>{title}</span>")),
+        }
+        if let Some(site) = card.site_name.as_deref() {
+          inner.push_str(&format!("<small>{}</small>", html_escape(site)));
         }
       }
+      _ => inner.push_str(&format!("<p>{}</p>", card_text_html(&card.text))),
+    }
+    if let Some(note) = card.note.as_deref().filter(|n| !n.trim().is_empty()) {
+      inner.push_str(&format!("<p class=\"note\">{}</p>", card_text_html(note)));
+    }
+    body.push_str(&format!(
+      concat!(
+        "<article class=\"card {}\" style=\"left:{}px;top:{}px;width:{}px;min-height:{}px\">",
+        "{}</article>\n"
+      ),
+      html_escape(&card.r#type),
+      finite(card.x) + ox,
+      finite(card.y) + oy,
+      finite(card.width),
+      finite(card.height),
+      inner,
+    ));
+  }
+
+  format!(
+    r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ margin: 0; font: 14px/1.4 -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; background: #f4f4f2; }}
+.board {{ position: relative; width: {width}px; height: {height}px; }}
+.column {{ position: absolute; box-sizing: border-box; border: 1px dashed #b8b8b0; border-radius: 10px; }}
+.column h2 {{ margin: 4px 10px; font-size: 13px; color: #555; }}
+.card {{ position: absolute; box-sizing: border-box; padding: 10px; background: #fff; border-radius: 8px; box-shadow: 0 1px 3px rgba(0,0,0,.15); overflow: hidden; }}
+.card p {{ margin: 0; white-space: pre-wrap; }}
+.card img {{ display: block; width: 100%; height: auto; margin-bottom: 6px; border-radius: 4px; }}
+.card small {{ display: block; color: #777; margin-top: 4px; }}
+.card .note {{ margin-top: 8px; color: #555; }}
+</style>
+</head>
+<body>
+<div class="board">
+{body}</div>
+</body>
+</html>
+"#,
+    title = html_escape(&board.name),
+    width = max_x - min_x + PAD * 2.0,
+    height = max_y - min_y + PAD * 2.0,
+  )
+}
+
+fn is_image_asset(path: &std::path::Path) -> bool {
+  path
+    .extension()
+    .and_then(|e| e.to_str())
+    .and_then(mime_from_ext)
+    .is_some_and(|mime| mime != "image/svg+xml")
+}
+
+/// Difference hash: a 9x8 grayscale thumbnail compared pixel-to-neighbour, so
+/// re-encoded or resized copies of an image land within a few bits.
+fn dhash(path: &std::path::Path) -> Option<u64> {
+  let img = image::open(path).ok()?;
+  let small = img
+    .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+    .to_luma8();
+  let mut hash = 0u64;
+  for y in 0..8 {
+    for x in 0..8 {
+      hash <<= 1;
+      if small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0] {
+        hash |= 1;
+      }
     }
   }
+  Some(hash)
+}
+
+/// Downscales an encoded image so neither side exceeds `max_dim`, keeping its
+/// format. Images already within the limit (and GIFs, which may be animated)
+/// are returned untouched. Also returns the stored dimensions when readable.
+fn fit_image_bytes(bytes: Vec<u8>, max_dim: u32) -> (Vec<u8>, Option<(u32, u32)>) {
+  let format = match image::guess_format(&bytes) {
+    Ok(format) => format,
+    Err(_) => return (bytes, None),
+  };
+  let reader = image::ImageReader::with_format(std::io::Cursor::new(&bytes), format);
+  let (width, height) = match reader.into_dimensions() {
+    Ok(dims) => dims,
+    Err(_) => return (bytes, None),
+  };
+  if (width <= max_dim && height <= max_dim) || format == image::ImageFormat::Gif {
+    return (bytes, Some((width, height)));
+  }
+  let img = match image::load_from_memory_with_format(&bytes, format) {
+    Ok(img) => img,
+    Err(_) => return (bytes, Some((width, height))),
+  };
+  let mut small = img.resize(max_dim, max_dim, image::imageops::FilterType::Triangle);
+  if format == image::ImageFormat::Jpeg {
+    small = image::DynamicImage::ImageRgb8(small.to_rgb8());
+  }
+  let mut out = std::io::Cursor::new(Vec::new());
+  match small.write_to(&mut out, format) {
+    Ok(()) => (out.into_inner(), Some((small.width(), small.height()))),
+    Err(_) => (bytes, Some((width, height))),
+  }
+}
+
+const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+const MAX_THUMBNAIL_SIZE: u32 = 1024;
+/// Animations up to this size are served as-is rather than frozen to a frame.
+const MAX_ANIMATED_PASSTHROUGH_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Whether an image file has more than one frame (animated GIF, WebP or APNG).
+fn is_animated_image(path: &std::path::Path) -> bool {
+  let open = || std::fs::File::open(path).ok().map(std::io::BufReader::new);
+  match image::ImageFormat::from_path(path) {
+    Ok(image::ImageFormat::Gif) => {
+      use image::AnimationDecoder;
+      open()
+        .and_then(|r| image::codecs::gif::GifDecoder::new(r).ok())
+        .map(|d| d.into_frames().take(2).count() > 1)
+        .unwrap_or(false)
+    }
+    Ok(image::ImageFormat::WebP) => open()
+      .and_then(|r| image::codecs::webp::WebPDecoder::new(r).ok())
+      .is_some_and(|d| d.has_animation()),
+    Ok(image::ImageFormat::Png) => open()
+      .and_then(|r| image::codecs::png::PngDecoder::new(r).ok())
+      .and_then(|d| d.is_apng().ok())
+      .unwrap_or(false),
+    _ => false,
+  }
+}
+
+/// Thumbnails live in `<board>/thumbs/<asset name>.<size>.jpg`.
+fn thumbnail_file(
+  paths: &BoardPaths,
+  asset: &std::path::Path,
+  size: u32,
+) -> Option<std::path::PathBuf> {
+  let name = asset.file_name()?.to_str()?;
+  Some(paths.thumbs_dir.join(format!("{name}.{size}.jpg")))
+}
+
+/// Renders a JPEG thumbnail (first frame for animations), flattening any
+/// transparency onto white.
+fn write_thumbnail(
+  source: &std::path::Path,
+  dest: &std::path::Path,
+  size: u32,
+) -> Result<(u32, u32), AppError> {
+  let img =
+    image::open(source).map_err(|e| AppError::Serialize(format!("decode image failed: {e}")))?;
+  let small = img.thumbnail(size, size).to_rgba8();
+  let flat = image::RgbImage::from_fn(small.width(), small.height(), |x, y| {
+    let [r, g, b, a] = small.get_pixel(x, y).0;
+    let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+    image::Rgb([blend(r), blend(g), blend(b)])
+  });
+  if let Some(parent) = dest.parent() {
+    std::fs::create_dir_all(parent)
+      .map_err(|e| AppError::Io(format!("create thumbs dir failed: {e}")))?;
+  }
+  let tmp = dest.with_extension("jpg.tmp");
+  flat
+    .save_with_format(&tmp, image::ImageFormat::Jpeg)
+    .map_err(|e| AppError::Io(format!("write thumbnail failed: {e}")))?;
+  replace_file(&tmp, dest).map_err(|e| AppError::Io(format!("rename thumbnail failed: {e}")))?;
+  Ok((flat.width(), flat.height()))
+}
+
+/// Removes thumbnails whose source asset, on the board or in the pool, no
+/// longer exists.
+fn prune_orphan_thumbs(app_paths: &AppPaths, paths: &BoardPaths) -> u32 {
+  let entries = match std::fs::read_dir(&paths.thumbs_dir) {
+    Ok(entries) => entries,
+    Err(_) => return 0,
+  };
+  let mut removed = 0;
+  for entry in entries.flatten() {
+    let name = entry.file_name().to_string_lossy().to_string();
+    let source = name.rsplitn(3, '.').nth(2).unwrap_or("");
+    let exists = |dir: &std::path::Path| dir.join(source).is_file();
+    if source.is_empty() || !(exists(&paths.assets_dir) || exists(&app_paths.pool_dir)) {
+      if std::fs::remove_file(entry.path()).is_ok() {
+        removed += 1;
+      }
+    }
+  }
+  removed
+}
+
+const DEFAULT_PREVIEW_SIZE: u32 = 256;
+const MAX_PREVIEW_SIZE: u32 = 1024;
+const MAX_PREVIEW_TILES: usize = 4;
+const PREVIEW_PALETTE: [[u8; 3]; 6] = [
+  [0x5b, 0x6c, 0x8f],
+  [0x6f, 0x8f, 0x5b],
+  [0x8f, 0x5b, 0x6c],
+  [0x8f, 0x7a, 0x5b],
+  [0x5b, 0x8a, 0x8f],
+  [0x7a, 0x5b, 0x8f],
+];
+
+/// 5x7 bitmap glyphs for the fallback preview's initial, one row per byte.
+const PREVIEW_GLYPH_CHARS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+const PREVIEW_GLYPHS: [[u8; 7]; 36] = [
+  [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+  [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e],
+  [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e],
+  [0x1e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1e],
+  [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f],
+  [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10],
+  [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f],
+  [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+  [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e],
+  [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c],
+  [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+  [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f],
+  [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11],
+  [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+  [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+  [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10],
+  [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d],
+  [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11],
+  [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e],
+  [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+  [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+  [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04],
+  [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a],
+  [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11],
+  [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04],
+  [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f],
+  [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e],
+  [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e],
+  [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f],
+  [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e],
+  [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02],
+  [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e],
+  [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e],
+  [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+  [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e],
+  [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c],
+];
+
+/// The board's `#rrggbb` background, or a palette colour picked from its id.
+fn preview_color(board: &Board) -> image::Rgba<u8> {
+  let parsed = board
+    .settings
+    .background
+    .as_deref()
+    .and_then(|bg| bg.trim().strip_prefix('#'))
+    .filter(|hex| hex.len() == 6)
+    .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+  let [r, g, b] = match parsed {
+    Some(rgb) => [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8],
+    None => {
+      let sum = board.id.bytes().map(|b| b as usize).sum::<usize>();
+      PREVIEW_PALETTE[sum % PREVIEW_PALETTE.len()]
+    }
+  };
+  image::Rgba([r, g, b, 255])
+}
+
+/// Draws the board name's first letter (if the bitmap font has it) centred in white.
+fn draw_preview_initial(canvas: &mut image::RgbaImage, name: &str) {
+  let letter = match name.trim().chars().next() {
+    Some(c) => c.to_ascii_uppercase(),
+    None => return,
+  };
+  let glyph = match PREVIEW_GLYPH_CHARS.find(letter) {
+    Some(i) => PREVIEW_GLYPHS[i],
+    None => return,
+  };
+  let scale = (canvas.width().min(canvas.height()) / 14).max(1);
+  let left = (canvas.width().saturating_sub(5 * scale)) / 2;
+  let top = (canvas.height().saturating_sub(7 * scale)) / 2;
+  for (row, bits) in glyph.iter().enumerate() {
+    for col in 0..5u32 {
+      if bits & (0x10 >> col) == 0 {
+        continue;
+      }
+      for dy in 0..scale {
+        for dx in 0..scale {
+          let (x, y) = (left + col * scale + dx, top + row as u32 * scale + dy);
+          if x < canvas.width() && y < canvas.height() {
+            canvas.put_pixel(x, y, image::Rgba([255, 255, 255, 255]));
+          }
+        }
+      }
+    }
+  }
+}
+
+/// Tiles up to four of the board's images into a square, falling back to a
+/// solid colour with the board's initial when none of them decode.
+fn render_board_preview(
+  images: &[std::path::PathBuf],
+  board: &Board,
+  size: u32,
+) -> image::RgbaImage {
+  let mut canvas = image::RgbaImage::from_pixel(size, size, preview_color(board));
+  let decoded: Vec<image::DynamicImage> = images
+    .iter()
+    .filter_map(|file| image::open(file).ok())
+    .take(MAX_PREVIEW_TILES)
+    .collect();
+  if decoded.is_empty() {
+    draw_preview_initial(&mut canvas, &board.name);
+    return canvas;
+  }
+  let (cols, rows) = match decoded.len() {
+    1 => (1, 1),
+    2 => (2, 1),
+    _ => (2, 2),
+  };
+  let (cell_w, cell_h) = (size / cols, size / rows);
+  for (i, img) in decoded.iter().enumerate() {
+    let (col, row) = (i as u32 % cols, i as u32 / cols);
+    let tile = img
+      .resize_to_fill(cell_w.max(1), cell_h.max(1), image::imageops::FilterType::Triangle)
+      .to_rgba8();
+    image::imageops::overlay(&mut canvas, &tile, (col * cell_w) as i64, (row * cell_h) as i64);
+  }
+  canvas
+}
+
+const POOL_PREFIX: &str = "pool://";
+
+/// The file name behind a well-formed `pool://sha256-<hex>[.ext]` reference.
+fn pool_ref_name(value: &str) -> Option<&str> {
+  let name = value.trim().strip_prefix(POOL_PREFIX)?;
+  let (stem, ext) = name.split_once('.').unwrap_or((name, ""));
+  let hash = stem.strip_prefix("sha256-")?;
+  let valid = hash.len() == 64
+    && hash.bytes().all(|b| b.is_ascii_hexdigit() && !b.is_ascii_uppercase())
+    && ext.len() <= 10
+    && ext.bytes().all(|b| b.is_ascii_alphanumeric());
+  if valid {
+    Some(name)
+  } else {
+    None
+  }
+}
+
+fn pool_file(paths: &AppPaths, value: &str) -> Option<std::path::PathBuf> {
+  pool_ref_name(value).map(|name| paths.pool_dir.join(name))
+}
+
+/// The file behind a card reference, whether it lives in the pool or the board.
+fn card_file(
+  paths: &AppPaths,
+  board_paths: &BoardPaths,
+  value: &str,
+) -> Option<std::path::PathBuf> {
+  pool_file(paths, value).or_else(|| card_asset_file(board_paths, value))
+}
+
+fn pool_ext(ext: &str) -> String {
+  let ext: String = ext
+    .trim_start_matches('.')
+    .chars()
+    .filter(|c| c.is_ascii_alphanumeric())
+    .take(10)
+    .collect::<String>()
+    .to_ascii_lowercase();
+  if ext.is_empty() {
+    String::new()
+  } else {
+    format!(".{ext}")
+  }
+}
+
+/// A temp file in `dir` that no concurrent save in this process shares.
+fn unique_tmp(dir: &std::path::Path, name: &str) -> std::path::PathBuf {
+  static NEXT: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+  let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+  dir.join(format!(".{name}.{}-{n}.tmp", std::process::id()))
+}
+
+/// Moves a finished temp file to `out`. If `out` already holds the same
+/// content (say, from a concurrent save) the temp file is just dropped.
+fn place_content_addressed(tmp: &std::path::Path, out: &std::path::Path) -> Result<(), AppError> {
+  if out.is_file() {
+    let _ = std::fs::remove_file(tmp);
+    return Ok(());
+  }
+  match std::fs::rename(tmp, out) {
+    Ok(()) => Ok(()),
+    Err(_) if out.is_file() => {
+      let _ = std::fs::remove_file(tmp);
+      Ok(())
+    }
+    Err(e) => Err(AppError::Io(format!("rename image failed: {e}"))),
+  }
+}
+
+/// Where a board asset came from; it leads the asset's file name.
+#[derive(Debug, Clone, Copy)]
+enum AssetRole {
+  /// Added by the user: pasted, dropped or uploaded.
+  Image,
+  /// A link card's preview image.
+  Link,
+  Favicon,
+}
+
+impl AssetRole {
+  fn prefix(self) -> &'static str {
+    match self {
+      AssetRole::Image => "image-",
+      AssetRole::Link => "link-",
+      AssetRole::Favicon => "favicon-",
+    }
+  }
+}
+
+/// Writes bytes into `dir` as `<prefix>sha256-<hex><ext>` and returns that
+/// file name. Identical content is only written once.
+fn write_content_addressed(
+  dir: &std::path::Path,
+  prefix: &str,
+  bytes: &[u8],
+  ext: &str,
+) -> Result<String, AppError> {
+  use sha2::Digest;
+  let hash = sha2::Sha256::digest(bytes);
+  let name = format!("{prefix}sha256-{hash:x}{}", pool_ext(ext));
+  let out = dir.join(&name);
+  if !out.is_file() {
+    let tmp = unique_tmp(dir, &name);
+    std::fs::write(&tmp, bytes)
+      .map_err(|e| AppError::Io(format!("write temp image failed: {e}")))?;
+    place_content_addressed(&tmp, &out)?;
+  }
+  Ok(name)
+}
+
+/// Like `write_content_addressed` for content already on disk in `tmp`, which
+/// is moved into `dir` (or removed, if the content is already there).
+fn adopt_content_addressed(
+  dir: &std::path::Path,
+  prefix: &str,
+  tmp: &std::path::Path,
+  ext: &str,
+) -> Result<String, AppError> {
+  use sha2::Digest;
+  let mut hasher = sha2::Sha256::new();
+  let mut file =
+    std::fs::File::open(tmp).map_err(|e| AppError::Io(format!("read upload failed: {e}")))?;
+  std::io::copy(&mut file, &mut hasher)
+    .map_err(|e| AppError::Io(format!("read upload failed: {e}")))?;
+  drop(file);
+  let name = format!("{prefix}sha256-{:x}{}", hasher.finalize(), pool_ext(ext));
+  place_content_addressed(tmp, &dir.join(&name))?;
+  Ok(name)
+}
+
+/// Stores bytes in the shared pool under their SHA-256 and returns the
+/// `pool://` reference.
+fn save_pool_bytes(paths: &AppPaths, bytes: &[u8], ext: &str) -> Result<String, AppError> {
+  std::fs::create_dir_all(&paths.pool_dir)
+    .map_err(|e| AppError::Io(format!("create asset pool failed: {e}")))?;
+  let name = write_content_addressed(&paths.pool_dir, "", bytes, ext)?;
+  Ok(format!("{POOL_PREFIX}{name}"))
+}
+
+/// Stores bytes in the board's assets as `<role>-sha256-<hex>`, so saving the
+/// same image twice reuses the first file while `delete_assets_matching` can
+/// still pick out e.g. every `favicon-` file.
+fn save_board_bytes(
+  paths: &BoardPaths,
+  role: AssetRole,
+  bytes: &[u8],
+  ext: &str,
+) -> Result<String, AppError> {
+  std::fs::create_dir_all(&paths.assets_dir)
+    .map_err(|e| AppError::Io(format!("create assets dir failed: {e}")))?;
+  let name = write_content_addressed(&paths.assets_dir, role.prefix(), bytes, ext)?;
+  Ok(format!("assets/{name}"))
+}
+
+/// `save_pool_bytes` for a temp file, e.g. a finished upload.
+fn save_pool_file(paths: &AppPaths, tmp: &std::path::Path, ext: &str) -> Result<String, AppError> {
+  std::fs::create_dir_all(&paths.pool_dir)
+    .map_err(|e| AppError::Io(format!("create asset pool failed: {e}")))?;
+  let name = adopt_content_addressed(&paths.pool_dir, "", tmp, ext)?;
+  Ok(format!("{POOL_PREFIX}{name}"))
+}
+
+/// `save_board_bytes` for a temp file, e.g. a finished upload.
+fn save_board_file(
+  paths: &BoardPaths,
+  role: AssetRole,
+  tmp: &std::path::Path,
+  ext: &str,
+) -> Result<String, AppError> {
+  std::fs::create_dir_all(&paths.assets_dir)
+    .map_err(|e| AppError::Io(format!("create assets dir failed: {e}")))?;
+  let name = adopt_content_addressed(&paths.assets_dir, role.prefix(), tmp, ext)?;
+  Ok(format!("assets/{name}"))
+}
+
+fn save_asset_bytes(
+  paths: &AppPaths,
+  board_id: &str,
+  role: AssetRole,
+  bytes: &[u8],
+  ext: &str,
+  storage: AssetStorage,
+) -> Result<String, AppError> {
+  if storage == AssetStorage::Pool {
+    return save_pool_bytes(paths, bytes, ext);
+  }
+  let index = read_index(paths)?;
+  let name = index
+    .boards
+    .iter()
+    .find(|b| b.id == board_id)
+    .map(|b| b.name.as_str())
+    .unwrap_or("Untitled");
+  let board_paths = board_paths(&paths.root_dir, board_id);
+  ensure_board_file(&board_paths, board_id, name)?;
+  save_board_bytes(&board_paths, role, bytes, ext)
+}
+
+/// Runs blocking filesystem work on the blocking pool so async commands never
+/// stall the runtime's reactor threads.
+async fn run_blocking<T, F>(work: F) -> Result<T, AppError>
+where
+  F: FnOnce() -> Result<T, AppError> + Send + 'static,
+  T: Send + 'static,
+{
+  tauri::async_runtime::spawn_blocking(work)
+    .await
+    .map_err(|e| AppError::Other(format!("background task failed: {e}")))?
+}
+
+/// Session-only passphrase for encrypted chat files; never written to disk.
+#[derive(Default)]
+struct ChatKey(std::sync::Mutex<Option<String>>);
+
+impl ChatKey {
+  fn current(&self) -> Option<String> {
+    self.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+  }
+}
+
+/// Encrypted chat files are `LANAENC1 | salt | nonce | ciphertext`.
+const CHAT_MAGIC: &[u8] = b"LANAENC1";
+const CHAT_SALT_LEN: usize = 16;
+const CHAT_NONCE_LEN: usize = 24;
+
+fn chat_cipher(
+  passphrase: &str,
+  salt: &[u8],
+) -> Result<chacha20poly1305::XChaCha20Poly1305, AppError> {
+  use chacha20poly1305::KeyInit;
+  let mut key = [0u8; 32];
+  argon2::Argon2::default()
+    .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+    .map_err(|e| AppError::Other(format!("derive chat key failed: {e}")))?;
+  Ok(chacha20poly1305::XChaCha20Poly1305::new(&key.into()))
+}
+
+fn encrypt_chat(plain: &[u8], passphrase: &str) -> Result<Vec<u8>, AppError> {
+  use chacha20poly1305::aead::{rand_core::RngCore, Aead, AeadCore, OsRng};
+  let mut salt = [0u8; CHAT_SALT_LEN];
+  OsRng.fill_bytes(&mut salt);
+  let nonce = chacha20poly1305::XChaCha20Poly1305::generate_nonce(&mut OsRng);
+  let sealed = chat_cipher(passphrase, &salt)?
+    .encrypt(&nonce, plain)
+    .map_err(|_| AppError::Other("encrypt chat failed".to_string()))?;
+  let mut out = Vec::with_capacity(CHAT_MAGIC.len() + salt.len() + nonce.len() + sealed.len());
+  out.extend_from_slice(CHAT_MAGIC);
+  out.extend_from_slice(&salt);
+  out.extend_from_slice(&nonce);
+  out.extend_from_slice(&sealed);
+  Ok(out)
+}
+
+fn decrypt_chat(data: &[u8], passphrase: &str) -> Result<Vec<u8>, AppError> {
+  use chacha20poly1305::aead::Aead;
+  let body = &data[CHAT_MAGIC.len()..];
+  if body.len() < CHAT_SALT_LEN + CHAT_NONCE_LEN {
+    return Err(AppError::Serialize(
+      "encrypted chat is truncated".to_string(),
+    ));
+  }
+  let (salt, rest) = body.split_at(CHAT_SALT_LEN);
+  let (nonce, sealed) = rest.split_at(CHAT_NONCE_LEN);
+  chat_cipher(passphrase, salt)?
+    .decrypt(chacha20poly1305::XNonce::from_slice(nonce), sealed)
+    .map_err(|_| {
+      AppError::Invalid("decrypt chat failed: wrong passphrase or corrupted file".to_string())
+    })
+}
+
+/// Reads chat.json, decrypting it when it carries the encrypted header.
+fn read_chat(paths: &BoardPaths, passphrase: Option<&str>) -> Result<ChatStore, AppError> {
+  let chat_path = paths.dir.join("chat.json");
+  if !chat_path.exists() {
+    return Ok(ChatStore {
+      version: 1,
+      messages: vec![],
+      summary: None,
+      summary_up_to: 0,
+      last_session_id: None,
+    });
+  }
+
+  let mut raw =
+    std::fs::read(&chat_path).map_err(|e| AppError::Io(format!("read chat failed: {e}")))?;
+  if raw.starts_with(CHAT_MAGIC) {
+    let passphrase = passphrase
+      .ok_or_else(|| AppError::Invalid("chat is encrypted; passphrase required".to_string()))?;
+    raw = decrypt_chat(&raw, passphrase)?;
+  }
+  let chat: ChatStore = serde_json::from_slice(&raw)
+    .map_err(|e| AppError::Serialize(format!("parse chat failed: {e}")))?;
+  Ok(chat)
+}
+
+/// Writes chat.json, encrypted when the setting is on. Returns the bytes written.
+fn write_chat(
+  paths: &BoardPaths,
+  chat: &ChatStore,
+  settings: &AppSettings,
+  passphrase: Option<&str>,
+) -> Result<usize, AppError> {
+  let chat_path = paths.dir.join("chat.json");
+  let tmp_path = paths.dir.join("chat.json.tmp");
+  let mut serialized = serde_json::to_vec_pretty(chat)
+    .map_err(|e| AppError::Serialize(format!("serialize chat failed: {e}")))?;
+  if settings.encrypt_chats {
+    let passphrase =
+      passphrase.ok_or_else(|| AppError::Invalid("chat passphrase not set".to_string()))?;
+    serialized = encrypt_chat(&serialized, passphrase)?;
+  }
+  let size = serialized.len();
+  std::fs::write(&tmp_path, serialized)
+    .map_err(|e| AppError::Io(format!("write chat failed: {e}")))?;
+  replace_file(&tmp_path, &chat_path)
+    .map_err(|e| AppError::Io(format!("write chat failed: {e}")))?;
+  Ok(size)
+}
+
+/// Keeps the summary bookkeeping consistent with `messages`: a summary that
+/// covers more messages than exist is dropped, as is a cursor with no summary.
+/// Returns true if anything changed.
+fn normalize_chat(chat: &mut ChatStore) -> bool {
+  let stale = chat.summary_up_to > chat.messages.len()
+    || (chat.summary.is_none() && chat.summary_up_to > 0);
+  if stale {
+    chat.summary = None;
+    chat.summary_up_to = 0;
+  }
+  stale
+}
+
+/// Case-insensitive match returning a short excerpt around the first hit.
+fn match_snippet(content: &str, query: &str, context: usize) -> Option<String> {
+  let lower = |c: char| c.to_lowercase().next().unwrap_or(c);
+  let hay: Vec<char> = content.chars().collect();
+  let needle: Vec<char> = query.chars().map(lower).collect();
+  if needle.is_empty() || needle.len() > hay.len() {
+    return None;
+  }
+  let start = (0..=hay.len() - needle.len())
+    .find(|&i| hay[i..i + needle.len()].iter().map(|&c| lower(c)).eq(needle.iter().copied()))?;
+  let from = start.saturating_sub(context);
+  let to = (start + needle.len() + context).min(hay.len());
+  let mut snippet: String = hay[from..to].iter().collect();
+  snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+  if from > 0 {
+    snippet.insert(0, '…');
+  }
+  if to < hay.len() {
+    snippet.push('…');
+  }
+  Some(snippet)
+}
+
+fn read_board_name(file: &std::path::Path) -> Option<String> {
+  read_board_text(file)
+    .ok()
+    .and_then(|text| parse_board(&text).ok())
+    .map(|b| b.name)
+}
+
+fn file_modified_millis(file: &std::path::Path) -> Option<i64> {
+  std::fs::metadata(file)
+    .and_then(|m| m.modified())
+    .ok()
+    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+    .map(|d| d.as_millis() as i64)
+}
+
+/// Rebuilds the index from the board folders. Pins only live in the index, so
+/// they are carried over from the current file when it still parses.
+fn rebuild_index_from_fs(paths: &AppPaths) -> Result<BoardIndex, AppError> {
+  ensure_root_dir(paths)?;
+  let pinned: std::collections::HashSet<String> = std::fs::read_to_string(&paths.index_file)
+    .ok()
+    .and_then(|text| serde_json::from_str::<BoardIndex>(&text).ok())
+    .map(|index| {
+      index
+        .boards
+        .into_iter()
+        .filter(|b| b.pinned)
+        .map(|b| b.id)
+        .collect()
+    })
+    .unwrap_or_default();
+  let mut boards = Vec::new();
+  let entries = std::fs::read_dir(&paths.root_dir)
+    .map_err(|e| AppError::Io(format!("read boards dir failed: {e}")))?;
+  for entry in entries.flatten() {
+    let path = entry.path();
+    if !is_plain_dir(&entry) {
+      continue;
+    }
+    let board_id = match path.file_name().and_then(|n| n.to_str()) {
+      Some(name) => name.to_string(),
+      None => continue,
+    };
+    if !is_valid_board_id(&board_id) {
+      continue;
+    }
+    let board_file = path.join("board.json");
+    if !board_file.exists() {
+      continue;
+    }
+    let name = read_board_name(&board_file).unwrap_or_else(|| board_id.clone());
+    let updated_at = file_modified_millis(&board_file).unwrap_or_else(now_millis);
+    boards.push(BoardMeta {
+      pinned: pinned.contains(&board_id),
+      id: board_id,
+      name,
+      updated_at,
+      deleted_at: None,
+    });
+  }
+  let trash_dir = paths.root_dir.join("trash");
+  if trash_dir.exists() {
+    let trash_entries = std::fs::read_dir(&trash_dir)
+      .map_err(|e| AppError::Io(format!("read trash dir failed: {e}")))?;
+    for entry in trash_entries.flatten() {
+      let path = entry.path();
+      if !is_plain_dir(&entry) {
+        continue;
+      }
+      let board_id = match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name.to_string(),
+        None => continue,
+      };
+      if !is_valid_board_id(&board_id) {
+        continue;
+      }
+      let board_file = path.join("board.json");
+      if !board_file.exists() {
+        continue;
+      }
+      let name = read_board_name(&board_file).unwrap_or_else(|| board_id.clone());
+      let deleted_at = file_modified_millis(&board_file).unwrap_or_else(now_millis);
+      boards.push(BoardMeta {
+        pinned: pinned.contains(&board_id),
+        id: board_id,
+        name,
+        updated_at: deleted_at,
+        deleted_at: Some(deleted_at),
+      });
+    }
+  }
+  let index = BoardIndex { version: 1, boards };
+  write_index_atomic(paths, &index)?;
+  Ok(index)
+}
+
+/// Reads every non-trashed board, skipping any whose board.json can't be parsed.
+fn live_boards(paths: &AppPaths) -> Result<Vec<(BoardMeta, Board)>, AppError> {
+  let index = read_index(paths)?;
+  let mut boards = Vec::new();
+  for meta in index.boards.into_iter().filter(|b| b.deleted_at.is_none()) {
+    let board_paths = board_paths(&paths.root_dir, &meta.id);
+    if let Ok(board) = read_board(&board_paths) {
+      boards.push((meta, board));
+    }
+  }
+  Ok(boards)
+}
+
+fn sync_index_with_fs(paths: &AppPaths, mut index: BoardIndex) -> Result<BoardIndex, AppError> {
+  ensure_root_dir(paths)?;
+  let mut changed = false;
+  let mut seen = std::collections::HashSet::new();
+  let mut seen_trash = std::collections::HashSet::new();
+
+  let entries = std::fs::read_dir(&paths.root_dir)
+    .map_err(|e| AppError::Io(format!("read boards dir failed: {e}")))?;
+  for entry in entries.flatten() {
+    let path = entry.path();
+    if !is_plain_dir(&entry) {
+      continue;
+    }
+    let board_id = match path.file_name().and_then(|n| n.to_str()) {
+      Some(name) => name.to_string(),
+      None => continue,
+    };
+    if !is_valid_board_id(&board_id) {
+      continue;
+    }
+    let board_file = path.join("board.json");
+    if !board_file.exists() {
+      continue;
+    }
+    seen.insert(board_id.clone());
+    let name = read_board_name(&board_file).unwrap_or_else(|| board_id.clone());
+    let updated_at = file_modified_millis(&board_file).unwrap_or_else(now_millis);
+    match index.boards.iter_mut().find(|b| b.id == board_id) {
+      Some(meta) => {
+        if meta.name != name || meta.updated_at != updated_at {
+          meta.name = name;
+          meta.updated_at = updated_at;
+          changed = true;
+        }
+      }
+      None => {
+        index.boards.push(BoardMeta {
+          id: board_id,
+          name,
+          updated_at,
+          deleted_at: None,
+          pinned: false,
+        });
+        changed = true;
+      }
+    }
+  }
+
+  let trash_dir = paths.root_dir.join("trash");
+  if trash_dir.exists() {
+    let trash_entries = std::fs::read_dir(&trash_dir)
+      .map_err(|e| AppError::Io(format!("read trash dir failed: {e}")))?;
+    for entry in trash_entries.flatten() {
+      let path = entry.path();
+      if !is_plain_dir(&entry) {
+        continue;
+      }
+      let board_id = match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name.to_string(),
+        None => continue,
+      };
+      if !is_valid_board_id(&board_id) {
+        continue;
+      }
+      let board_file = path.join("board.json");
+      if !board_file.exists() {
+        continue;
+      }
+      seen_trash.insert(board_id.clone());
+      let name = read_board_name(&board_file).unwrap_or_else(|| board_id.clone());
+      let deleted_at = file_modified_millis(&board_file).unwrap_or_else(now_millis);
+      match index.boards.iter_mut().find(|b| b.id == board_id) {
+        Some(meta) => {
+          if meta.name != name
+            || meta.updated_at != deleted_at
+            || meta.deleted_at != Some(deleted_at)
+          {
+            meta.name = name;
+            meta.updated_at = deleted_at;
+            meta.deleted_at = Some(deleted_at);
+            changed = true;
+          }
+        }
+        None => {
+          index.boards.push(BoardMeta {
+            id: board_id,
+            name,
+            updated_at: deleted_at,
+            deleted_at: Some(deleted_at),
+            pinned: false,
+          });
+          changed = true;
+        }
+      }
+    }
+  }
+
+  let before_len = index.boards.len();
+  index.boards.retain(|b| {
+    if b.deleted_at.is_some() {
+      seen_trash.contains(&b.id)
+    } else {
+      seen.contains(&b.id)
+    }
+  });
+  if index.boards.len() != before_len {
+    changed = true;
+  }
+
+  if changed {
+    write_index_atomic(paths, &index)?;
+  }
+  Ok(index)
+}
+
+#[tauri::command]
+fn open_external_url(url: String) -> Result<(), AppError> {
+  let parsed = Url::parse(&url).map_err(|e| AppError::Invalid(format!("invalid url: {e}")))?;
+  let scheme = parsed.scheme();
+  if scheme != "http" && scheme != "https" {
+    return Err(AppError::Invalid("unsupported url scheme".into()));
+  }
+
+  #[cfg(target_os = "macos")]
+  {
+    std::process::Command::new("open")
+      .arg(parsed.as_str())
+      .status()
+      .map_err(|e| AppError::Io(format!("open failed: {e}")))?;
+  }
+
+  #[cfg(target_os = "windows")]
+  {
+    std::process::Command::new("cmd")
+      .arg("/C")
+      .arg("start")
+      .arg("")
+      .arg(parsed.as_str())
+      .status()
+      .map_err(|e| AppError::Io(format!("open failed: {e}")))?;
+  }
+
+  #[cfg(target_os = "linux")]
+  {
+    std::process::Command::new("xdg-open")
+      .arg(parsed.as_str())
+      .status()
+      .map_err(|e| AppError::Io(format!("open failed: {e}")))?;
+  }
+
+  Ok(())
+}
+
+#[tauri::command]
+fn get_settings(settings: tauri::State<'_, SettingsState>) -> AppSettings {
+  settings.current()
+}
+
+#[tauri::command]
+fn set_settings(
+  paths: tauri::State<'_, AppPaths>,
+  state: tauri::State<'_, SettingsState>,
+  http: tauri::State<'_, HttpState>,
+  limiter: tauri::State<'_, WorkLimiter>,
+  caps: tauri::State<'_, ChatCaps>,
+  settings: AppSettings,
+) -> Result<AppSettings, AppError> {
+  validate_settings(&settings)?;
+  // Build the clients first: if that fails, nothing has been saved or applied.
+  let clients = HttpClients::build(&settings)?;
+  let mut current = state.0.lock().unwrap_or_else(|e| e.into_inner());
+  write_settings_atomic(&paths, &settings)?;
+  *current = settings.clone();
+  http.set(clients);
+  limiter.set_limit(settings.max_concurrency());
+  caps.clear();
+  Ok(settings)
+}
+
+#[tauri::command]
+fn get_ollama_url(settings: tauri::State<'_, SettingsState>) -> String {
+  settings.current().ollama_url()
+}
+
+/// Points chat at another Ollama server; `None` or blank restores the default.
+#[tauri::command]
+fn set_ollama_url(
+  paths: tauri::State<'_, AppPaths>,
+  state: tauri::State<'_, SettingsState>,
+  caps: tauri::State<'_, ChatCaps>,
+  url: Option<String>,
+) -> Result<String, AppError> {
+  let url = url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+  if let Some(url) = &url {
+    validate_ollama_url(url)?;
+  }
+  let mut current = state.0.lock().unwrap_or_else(|e| e.into_inner());
+  let mut next = current.clone();
+  next.ollama_url = url;
+  write_settings_atomic(&paths, &next)?;
+  *current = next;
+  caps.clear();
+  Ok(current.ollama_url())
+}
+
+#[tauri::command]
+fn reload_http_client(
+  settings: tauri::State<'_, SettingsState>,
+  http: tauri::State<'_, HttpState>,
+) -> Result<(), AppError> {
+  http.rebuild(&settings.current())
+}
+
+#[tauri::command]
+fn get_board_deeplink(board_id: String) -> Result<String, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  Ok(format!("{DEEPLINK_SCHEME}://board/{board_id}"))
+}
+
+#[tauri::command]
+fn take_pending_board_link(
+  pending: tauri::State<'_, PendingBoardLink>,
+) -> Result<Option<String>, AppError> {
+  Ok(pending.0.lock().unwrap_or_else(|e| e.into_inner()).take())
+}
+
+#[tauri::command]
+fn list_boards(paths: tauri::State<'_, AppPaths>) -> Result<Vec<BoardMeta>, AppError> {
+  let index = read_index(&paths)?;
+  let mut boards: Vec<BoardMeta> = index
+    .boards
+    .into_iter()
+    .filter(|b| b.deleted_at.is_none())
+    .collect();
+  // Stable, so pinned and unpinned boards each keep their index order.
+  boards.sort_by_key(|b| !b.pinned);
+  Ok(boards)
+}
+
+fn set_board_pinned(paths: &AppPaths, board_id: &str, pinned: bool) -> Result<BoardMeta, AppError> {
+  if !is_valid_board_id(board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let mut index = read_index(paths)?;
+  let meta = index
+    .boards
+    .iter_mut()
+    .find(|b| b.id == board_id && b.deleted_at.is_none())
+    .ok_or_else(|| AppError::NotFound("board not found".to_string()))?;
+  meta.pinned = pinned;
+  let meta = meta.clone();
+  write_index_atomic(paths, &index)?;
+  Ok(meta)
+}
+
+#[tauri::command]
+fn pin_board(paths: tauri::State<'_, AppPaths>, board_id: String) -> Result<BoardMeta, AppError> {
+  set_board_pinned(&paths, &board_id, true)
+}
+
+#[tauri::command]
+fn unpin_board(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+) -> Result<BoardMeta, AppError> {
+  set_board_pinned(&paths, &board_id, false)
+}
+
+/// Rebuilds `boards.json` from the board folders on disk, dropping entries
+/// whose folder is gone. Pins survive; everything else comes from the files.
+#[tauri::command]
+fn force_reindex(paths: tauri::State<'_, AppPaths>) -> Result<Vec<BoardMeta>, AppError> {
+  Ok(rebuild_index_from_fs(&paths)?.boards)
+}
+
+/// Re-reads one board's name from its board.json into the index, a cheap
+/// alternative to a full `sync_index_with_fs` after an external edit.
+#[tauri::command]
+fn refresh_board_meta(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+) -> Result<BoardMeta, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let mut index = read_index(&paths)?;
+  let trashed = index
+    .boards
+    .iter()
+    .any(|b| b.id == board_id && b.deleted_at.is_some());
+  if trashed {
+    // Keep it in the trash; only the name follows the file.
+    let file = paths.root_dir.join("trash").join(&board_id).join("board.json");
+    let name = read_board_name(&file).ok_or_else(|| AppError::NotFound("board not found".into()))?;
+    let meta = index
+      .boards
+      .iter_mut()
+      .find(|b| b.id == board_id)
+      .ok_or_else(|| AppError::NotFound("board not found".into()))?;
+    meta.name = sanitize_board_name(&name);
+    let meta = meta.clone();
+    write_index_atomic(&paths, &index)?;
+    return Ok(meta);
+  }
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  let name = read_board_name(&board_paths.file)
+    .ok_or_else(|| AppError::NotFound("board not found".into()))?;
+  let index = ensure_board_index_contains(&paths, index, &board_id, &sanitize_board_name(&name))?;
+  index
+    .boards
+    .into_iter()
+    .find(|b| b.id == board_id)
+    .ok_or_else(|| AppError::NotFound("board not found".into()))
+}
+
+#[tauri::command]
+fn list_trashed_boards(
+  paths: tauri::State<'_, AppPaths>,
+  settings: tauri::State<'_, SettingsState>,
+) -> Result<Vec<BoardMeta>, AppError> {
+  if !settings.current().use_trash() {
+    return Ok(vec![]);
+  }
+  let mut boards: Vec<BoardMeta> = read_index(&paths)?
+    .boards
+    .into_iter()
+    .filter(|b| b.deleted_at.is_some())
+    .collect();
+  // Newest first; the id breaks ties between boards trashed in the same millisecond.
+  boards.sort_by(|a, b| {
+    b.deleted_at
+      .unwrap_or(0)
+      .cmp(&a.deleted_at.unwrap_or(0))
+      .then_with(|| a.id.cmp(&b.id))
+  });
+  Ok(boards)
+}
+
+const MAX_DUPLICATE_GROUPS: usize = 200;
+
+#[tauri::command]
+fn find_duplicate_links_global(
+  paths: tauri::State<'_, AppPaths>,
+) -> Result<Vec<DuplicateLinkGroup>, AppError> {
+  let mut groups: std::collections::HashMap<String, Vec<LinkCardRef>> =
+    std::collections::HashMap::new();
+  for (meta, board) in live_boards(&paths)? {
+    for card in board.cards.iter().filter(|c| c.r#type == "link") {
+      let key = match card.url.as_deref().and_then(canonical_url_key) {
+        Some(key) => key,
+        None => continue,
+      };
+      groups.entry(key).or_default().push(LinkCardRef {
+        board_id: meta.id.clone(),
+        board_name: meta.name.clone(),
+        card_id: card.id.clone(),
+      });
+    }
+  }
+
+  let mut duplicates: Vec<DuplicateLinkGroup> = groups
+    .into_iter()
+    .filter(|(_, cards)| {
+      let boards: std::collections::HashSet<&str> =
+        cards.iter().map(|c| c.board_id.as_str()).collect();
+      boards.len() > 1
+    })
+    .map(|(url, cards)| DuplicateLinkGroup { url, cards })
+    .collect();
+  duplicates.sort_by(|a, b| b.cards.len().cmp(&a.cards.len()).then_with(|| a.url.cmp(&b.url)));
+  duplicates.truncate(MAX_DUPLICATE_GROUPS);
+  Ok(duplicates)
+}
+
+/// Link cards on live boards whose URL matches `url` after canonicalization.
+#[tauri::command]
+fn boards_containing_url(
+  paths: tauri::State<'_, AppPaths>,
+  url: String,
+) -> Result<Vec<LinkCardRef>, AppError> {
+  let key = canonical_url_key(&url).ok_or_else(|| AppError::Invalid("invalid url".to_string()))?;
+  let mut refs = Vec::new();
+  for (meta, board) in live_boards(&paths)? {
+    for card in board.cards.iter().filter(|c| c.r#type == "link") {
+      if card.url.as_deref().and_then(canonical_url_key).as_deref() == Some(key.as_str()) {
+        refs.push(LinkCardRef {
+          board_id: meta.id.clone(),
+          board_name: meta.name.clone(),
+          card_id: card.id.clone(),
+        });
+      }
+    }
+  }
+  Ok(refs)
+}
+
+const MAX_BOARDS_COMPARED: usize = 300;
+const MAX_DUPLICATE_BOARD_PAIRS: usize = 50;
+const DEFAULT_DUPLICATE_BOARD_SCORE: f64 = 0.6;
+
+/// Comparable keys for a board's cards: canonical URLs, normalized text, and
+/// image file names.
+fn board_content_keys(board: &Board) -> std::collections::HashSet<String> {
+  let mut keys = std::collections::HashSet::new();
+  for card in &board.cards {
+    let key = match card.r#type.as_str() {
+      "link" => card.url.as_deref().and_then(canonical_url_key).map(|k| format!("u:{k}")),
+      "image" => card
+        .src
+        .as_deref()
+        .and_then(|src| src.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .map(|name| format!("i:{name}")),
+      _ => {
+        let text = card.text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        if text.is_empty() {
+          None
+        } else {
+          Some(format!("t:{text}"))
+        }
+      }
+    };
+    if let Some(key) = key {
+      keys.insert(key);
+    }
+  }
+  keys
+}
+
+/// Board name reduced to its words, ignoring a trailing copy counter like
+/// `(2)` or `-1` left by sync conflicts.
+fn board_name_words(name: &str) -> std::collections::HashSet<String> {
+  name
+    .to_lowercase()
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|w| !w.is_empty() && !w.chars().all(|c| c.is_ascii_digit()) && *w != "copy")
+    .map(|w| w.to_string())
+    .collect()
+}
+
+fn jaccard(a: &std::collections::HashSet<String>, b: &std::collections::HashSet<String>) -> f64 {
+  let union = a.union(b).count();
+  if union == 0 {
+    return 0.0;
+  }
+  a.intersection(b).count() as f64 / union as f64
+}
+
+/// Pairs of live boards that look like accidental duplicates. Read-only; the
+/// most recently updated boards are compared first and the scan is capped.
+#[tauri::command]
+fn find_duplicate_boards(
+  paths: tauri::State<'_, AppPaths>,
+  min_score: Option<f64>,
+) -> Result<Vec<DuplicateBoardPair>, AppError> {
+  let min_score = min_score.unwrap_or(DEFAULT_DUPLICATE_BOARD_SCORE).clamp(0.0, 1.0);
+  let mut boards = live_boards(&paths)?;
+  boards.sort_by_key(|(meta, _)| std::cmp::Reverse(meta.updated_at));
+  boards.truncate(MAX_BOARDS_COMPARED);
+  let prepared: Vec<_> = boards
+    .iter()
+    .map(|(meta, board)| (meta, board_content_keys(board), board_name_words(&meta.name)))
+    .collect();
+
+  let mut pairs = Vec::new();
+  for (i, (meta, keys, words)) in prepared.iter().enumerate() {
+    for (other, other_keys, other_words) in &prepared[i + 1..] {
+      if keys.is_empty() && other_keys.is_empty() {
+        continue;
+      }
+      let score = 0.8 * jaccard(keys, other_keys) + 0.2 * jaccard(words, other_words);
+      if score >= min_score {
+        pairs.push(DuplicateBoardPair {
+          board_id: meta.id.clone(),
+          board_name: meta.name.clone(),
+          other_board_id: other.id.clone(),
+          other_board_name: other.name.clone(),
+          score,
+          shared_items: keys.intersection(other_keys).count(),
+        });
+      }
+    }
+  }
+  pairs.sort_by(|a, b| b.score.total_cmp(&a.score));
+  pairs.truncate(MAX_DUPLICATE_BOARD_PAIRS);
+  Ok(pairs)
+}
+
+const MAX_SUGGEST_URLS: usize = 500;
+const MAX_SUGGESTED_BOARDS: usize = 10;
+const DEFAULT_MIN_OVERLAP: usize = 2;
+
+/// Boards whose link cards already cover at least `min_overlap` of `urls`,
+/// most overlap first. Read-only.
+#[tauri::command]
+fn suggest_existing_board(
+  paths: tauri::State<'_, AppPaths>,
+  urls: Vec<String>,
+  min_overlap: Option<usize>,
+) -> Result<Vec<BoardOverlap>, AppError> {
+  let wanted: std::collections::HashSet<String> = urls
+    .iter()
+    .take(MAX_SUGGEST_URLS)
+    .filter_map(|u| canonical_url_key(u))
+    .collect();
+  if wanted.is_empty() {
+    return Ok(vec![]);
+  }
+  let min_overlap = min_overlap.unwrap_or(DEFAULT_MIN_OVERLAP).max(1);
+
+  let mut matches = Vec::new();
+  for (meta, board) in live_boards(&paths)? {
+    let shared: std::collections::HashSet<String> = board
+      .cards
+      .iter()
+      .filter(|c| c.r#type == "link")
+      .filter_map(|c| c.url.as_deref().and_then(canonical_url_key))
+      .filter(|key| wanted.contains(key))
+      .collect();
+    if shared.len() >= min_overlap {
+      matches.push(BoardOverlap {
+        board_id: meta.id,
+        board_name: meta.name,
+        overlap: shared.len(),
+      });
+    }
+  }
+  matches.sort_by(|a, b| b.overlap.cmp(&a.overlap).then_with(|| a.board_name.cmp(&b.board_name)));
+  matches.truncate(MAX_SUGGESTED_BOARDS);
+  Ok(matches)
+}
+
+/// Deletes unreferenced assets and thumbnails across every live board, by the
+/// same rules as `gc_board_assets`. Returns how many files went.
+#[tauri::command]
+fn cleanup_assets(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  chat_key: tauri::State<'_, ChatKey>,
+  uploads: tauri::State<'_, ImageUploads>,
+) -> Result<u32, AppError> {
+  ensure_root_dir(&paths)?;
+  let mut removed = 0u32;
+  let entries = std::fs::read_dir(&paths.root_dir)
+    .map_err(|e| AppError::Io(format!("read boards dir failed: {e}")))?;
+  let passphrase = chat_key.current();
+  for entry in entries.flatten() {
+    if !is_plain_dir(&entry) {
+      continue;
+    }
+    let board_id = match entry.file_name().to_str() {
+      Some(name) => name.to_string(),
+      None => continue,
+    };
+    if board_id == "trash" || !is_valid_board_id(&board_id) {
+      continue;
+    }
+    let lock = locks.for_board(&board_id);
+    let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+    let board_paths = board_paths(&paths.root_dir, &board_id);
+    let board = match std::fs::read_to_string(&board_paths.file)
+      .ok()
+      .and_then(|text| parse_board(&text).ok())
+    {
+      Some(board) => board,
+      None => continue,
+    };
+    removed += remove_orphan_assets(&board_paths, &board, passphrase.as_deref(), &board_id).removed;
+    removed += prune_orphan_thumbs(&paths, &board_paths);
+    removed += prune_abandoned_uploads(&board_paths, &uploads);
+  }
+  Ok(removed)
+}
+
+/// Removes files in the board's `uploads/` that no pending upload owns, i.e.
+/// left behind when the app quit mid-upload.
+fn prune_abandoned_uploads(board_paths: &BoardPaths, uploads: &ImageUploads) -> u32 {
+  let pending = uploads.pending.lock().unwrap_or_else(|e| e.into_inner());
+  let owned = |path: &std::path::Path| pending.values().any(|upload| upload.tmp == path);
+  let mut removed = 0;
+  for entry in std::fs::read_dir(&board_paths.uploads_dir)
+    .into_iter()
+    .flatten()
+    .flatten()
+  {
+    let path = entry.path();
+    if path.is_file() && !owned(&path) && std::fs::remove_file(&path).is_ok() {
+      removed += 1;
+    }
+  }
+  removed
+}
+
+/// Removes one board's assets whose filename starts with `prefix` (letters,
+/// digits, `-` and `_` only), e.g. `favicon-` or `link-` for every fetched
+/// icon or preview image. Card references to them are cleared and the
+/// board saved first, so no card is left pointing at a missing file.
+#[tauri::command]
+fn delete_assets_matching(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+  prefix: String,
+  confirm: bool,
+) -> Result<Vec<String>, AppError> {
+  if !confirm {
+    return Err(AppError::Invalid("confirm is required to delete assets".to_string()));
+  }
+  let safe = prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+  if prefix.is_empty() || !safe {
+    return Err(AppError::Invalid(format!("unsafe asset prefix: {prefix}")));
+  }
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, index) = open_live_board(&paths, &board_id)?;
+  let mut matched: Vec<String> = std::fs::read_dir(&board_paths.assets_dir)
+    .into_iter()
+    .flatten()
+    .flatten()
+    .filter(|e| e.path().is_file())
+    .filter_map(|e| e.file_name().to_str().map(str::to_string))
+    .filter(|name| name.starts_with(&prefix))
+    .collect();
+  matched.sort();
+  if matched.is_empty() {
+    return Ok(matched);
+  }
+  let doomed: std::collections::HashSet<String> =
+    matched.iter().map(|name| format!("assets/{name}")).collect();
+  let blank = |card: &mut Card| -> bool {
+    let mut changed = false;
+    for field in [&mut card.src, &mut card.image, &mut card.favicon] {
+      if field.as_ref().is_some_and(|v| doomed.contains(v)) {
+        *field = None;
+        changed = true;
+      }
+    }
+    changed
+  };
+  let mut board_changed = false;
+  for card in board.cards.iter_mut() {
+    board_changed |= blank(card);
+  }
+  if board_changed {
+    commit_board(&paths, &board_paths, index, &board)?;
+  }
+  let mut meta = read_board_local_meta(&board_paths);
+  let mut meta_changed = false;
+  for card in meta.deleted_cards.iter_mut() {
+    meta_changed |= blank(card);
+  }
+  if meta_changed {
+    write_board_local_meta(&board_paths, &meta)?;
+  }
+  let mut removed = Vec::new();
+  for name in matched {
+    match std::fs::remove_file(board_paths.assets_dir.join(&name)) {
+      Ok(()) => removed.push(name),
+      Err(e) => log::warn!("delete_assets_matching {board_id}: {name}: {e}"),
+    }
+  }
+  prune_orphan_thumbs(&paths, &board_paths);
+  Ok(removed)
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct MissingAssetRef {
+  #[serde(rename = "cardId")]
+  card_id: String,
+  /// `src` or `image`.
+  field: &'static str,
+  value: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct AssetReconcileReport {
+  missing: Vec<MissingAssetRef>,
+  /// Files in `assets/` no card (live or recently deleted) refers to.
+  orphans: Vec<String>,
+  /// Cards changed in fix mode.
+  fixed: usize,
+}
+
+/// Cross-checks one board's card image references against the files on disk.
+/// With `fix`, missing link images are cleared and image cards whose file is
+/// gone become text placeholders naming it. Orphans are only reported; see
+/// `cleanup_assets` to remove them.
+#[tauri::command]
+fn reconcile_assets(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+  fix: Option<bool>,
+) -> Result<AssetReconcileReport, AppError> {
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, board_index) = open_live_board(&paths, &board_id)?;
+
+  let mut missing = Vec::new();
+  let mut fixed = 0;
+  for card in board.cards.iter_mut() {
+    let mut changed = false;
+    let slots =
+      [("src", &mut card.src), ("image", &mut card.image), ("favicon", &mut card.favicon)];
+    for (field, slot) in slots {
+      let value = match slot.clone() {
+        Some(value) => value,
+        None => continue,
+      };
+      if !is_missing_asset(&paths, &board_paths, &value) {
+        continue;
+      }
+      missing.push(MissingAssetRef {
+        card_id: card.id.clone(),
+        field,
+        value,
+      });
+      if fix.unwrap_or(false) {
+        *slot = None;
+        changed = true;
+      }
+    }
+    if changed {
+      if card.r#type == "image" && card.src.is_none() {
+        let name = missing.last().map(|m| m.value.as_str()).unwrap_or_default();
+        let name = name.rsplit('/').next().unwrap_or(name);
+        card.r#type = "text".to_string();
+        card.text = format!("Missing image: {name}");
+      }
+      fixed += 1;
+    }
+  }
+  if fixed > 0 {
+    commit_board(&paths, &board_paths, board_index, &board)?;
+  }
+
+  let mut orphans: Vec<String> = orphan_asset_files(&board_paths, &board)
+    .iter()
+    .filter_map(|p| p.file_name().and_then(|n| n.to_str()).map(str::to_string))
+    .collect();
+  orphans.sort();
+  Ok(AssetReconcileReport {
+    missing,
+    orphans,
+    fixed,
+  })
+}
+
+/// Asset references held by cards in the board's `history/` snapshots. Read
+/// as plain JSON so snapshots from other schema versions still count.
+fn snapshot_asset_refs(board_paths: &BoardPaths) -> Vec<String> {
+  let mut refs = Vec::new();
+  for ts in board_snapshots(board_paths) {
+    let file = board_paths.history_dir.join(format!("{ts}.json"));
+    let value: serde_json::Value = match std::fs::read(&file)
+      .ok()
+      .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+    {
+      Some(value) => value,
+      None => continue,
+    };
+    for card in value["cards"].as_array().into_iter().flatten() {
+      for field in ["src", "image", "favicon"] {
+        if let Some(value) = card[field].as_str() {
+          refs.push(value.to_string());
+        }
+      }
+    }
+  }
+  refs
+}
+
+/// Files in the board's `assets/` that no card refers to, whether live,
+/// recently deleted, or in a history snapshot. Half-written `.tmp` files are
+/// left out.
+fn orphan_asset_files(board_paths: &BoardPaths, board: &Board) -> Vec<std::path::PathBuf> {
+  let recycled = read_board_local_meta(board_paths).deleted_cards;
+  let snapshots = snapshot_asset_refs(board_paths);
+  let referenced: std::collections::HashSet<std::path::PathBuf> = board
+    .cards
+    .iter()
+    .chain(recycled.iter())
+    .flat_map(|c| [c.src.as_deref(), c.image.as_deref(), c.favicon.as_deref()])
+    .flatten()
+    .chain(snapshots.iter().map(String::as_str))
+    .filter_map(|value| card_asset_file(board_paths, value))
+    .collect();
+  std::fs::read_dir(&board_paths.assets_dir)
+    .into_iter()
+    .flatten()
+    .flatten()
+    .map(|e| e.path())
+    .filter(|p| p.is_file() && !referenced.contains(p))
+    .filter(|p| p.extension().map_or(true, |ext| ext != "tmp"))
+    .collect()
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct AssetGcSummary {
+  removed: u32,
+  #[serde(rename = "bytesFreed")]
+  bytes_freed: u64,
+}
+
+/// Deletes one board's unreferenced assets and their thumbnails. A file whose
+/// name appears in the board's chat is kept, in case a message links to it;
+/// if the chat can't be read (say, it is encrypted and locked) every asset is
+/// kept.
+#[tauri::command]
+fn gc_board_assets(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  chat_key: tauri::State<'_, ChatKey>,
+  board_id: String,
+) -> Result<AssetGcSummary, AppError> {
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, board, _) = open_live_board(&paths, &board_id)?;
+  let passphrase = chat_key.current();
+  let summary = remove_orphan_assets(&board_paths, &board, passphrase.as_deref(), &board_id);
+  prune_orphan_thumbs(&paths, &board_paths);
+  Ok(summary)
+}
+
+/// Deletes the board's `orphan_asset_files`, except those the chat mentions.
+/// The caller holds the board lock.
+fn remove_orphan_assets(
+  board_paths: &BoardPaths,
+  board: &Board,
+  passphrase: Option<&str>,
+  board_id: &str,
+) -> AssetGcSummary {
+  let chat = match read_chat(board_paths, passphrase) {
+    Ok(chat) => Some(chat),
+    Err(e) => {
+      log::warn!("gc assets {board_id}: keeping all assets, chat unreadable: {e}");
+      None
+    }
+  };
+  let mentioned = |name: &str| match &chat {
+    Some(chat) => {
+      chat.summary.as_deref().is_some_and(|s| s.contains(name))
+        || chat.messages.iter().any(|m| m.content.contains(name))
+    }
+    None => true,
+  };
+
+  let mut summary = AssetGcSummary { removed: 0, bytes_freed: 0 };
+  for file in orphan_asset_files(board_paths, board) {
+    let name = match file.file_name().and_then(|n| n.to_str()) {
+      Some(name) => name,
+      None => continue,
+    };
+    if mentioned(name) {
+      continue;
+    }
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    if std::fs::remove_file(&file).is_ok() {
+      summary.removed += 1;
+      summary.bytes_freed += size;
+    }
+  }
+  summary
+}
+
+const MAX_STORAGE_SCAN_FILES: usize = 500_000;
+const MAX_STORAGE_TOP_BOARDS: usize = 10;
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+struct BoardStorage {
+  #[serde(rename = "boardId")]
+  board_id: String,
+  name: String,
+  bytes: u64,
+  #[serde(rename = "assetBytes")]
+  asset_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+struct StorageSummary {
+  /// Everything under live board folders, assets and thumbnails included.
+  #[serde(rename = "boardBytes")]
+  board_bytes: u64,
+  #[serde(rename = "assetBytes")]
+  asset_bytes: u64,
+  #[serde(rename = "thumbnailBytes")]
+  thumbnail_bytes: u64,
+  #[serde(rename = "poolBytes")]
+  pool_bytes: u64,
+  #[serde(rename = "trashBytes")]
+  trash_bytes: u64,
+  /// The largest boards, biggest first.
+  largest: Vec<BoardStorage>,
+  /// True when the scan stopped early (cancelled or too many files).
+  truncated: bool,
+}
+
+/// Visits each file under `dir` (not following symlinks) with its size.
+/// Returns false if the scan was cancelled or ran out of budget.
+fn walk_file_sizes(
+  dir: &std::path::Path,
+  budget: &mut usize,
+  cancel: &std::sync::atomic::AtomicBool,
+  mut visit: impl FnMut(&std::path::Path, u64),
+) -> bool {
+  for entry in WalkDir::new(dir).into_iter().filter_map(Result::ok) {
+    if !entry.file_type().is_file() {
+      continue;
+    }
+    if *budget == 0 || cancel.load(std::sync::atomic::Ordering::Relaxed) {
+      return false;
+    }
+    *budget -= 1;
+    visit(entry.path(), entry.metadata().map(|m| m.len()).unwrap_or(0));
+  }
+  true
+}
+
+/// Reports disk usage by live boards, their asset and thumbnail caches, the
+/// shared asset pool and the trash. Emits `storage-progress` per board and
+/// stops early on `cancel_storage_summary` or after a bounded number of files.
+#[tauri::command]
+async fn storage_summary(
+  app: tauri::AppHandle,
+  paths: tauri::State<'_, AppPaths>,
+  scan: tauri::State<'_, StorageScan>,
+) -> Result<StorageSummary, AppError> {
+  let index = read_index(&paths)?;
+  let paths = paths.inner().clone();
+  let cancel = scan.0.clone();
+  cancel.store(false, std::sync::atomic::Ordering::Relaxed);
+
+  run_blocking(move || {
+    let mut summary = StorageSummary::default();
+    let mut budget = MAX_STORAGE_SCAN_FILES;
+    let live: Vec<&BoardMeta> = index.boards.iter().filter(|b| b.deleted_at.is_none()).collect();
+    let mut boards = Vec::new();
+    for (done, meta) in live.iter().enumerate() {
+      let board_paths = board_paths(&paths.root_dir, &meta.id);
+      let mut board = BoardStorage {
+        board_id: meta.id.clone(),
+        name: meta.name.clone(),
+        ..BoardStorage::default()
+      };
+      let preview = board_paths.dir.join("preview.png");
+      let complete = walk_file_sizes(&board_paths.dir, &mut budget, &cancel, |file, len| {
+        board.bytes += len;
+        if file.starts_with(&board_paths.assets_dir) {
+          board.asset_bytes += len;
+        } else if file.starts_with(&board_paths.thumbs_dir) || file == preview {
+          summary.thumbnail_bytes += len;
+        }
+      });
+      summary.board_bytes += board.bytes;
+      summary.asset_bytes += board.asset_bytes;
+      boards.push(board);
+      let progress = ScanProgress {
+        board_id: meta.id.clone(),
+        done: done + 1,
+        total: live.len(),
+      };
+      let _ = app.emit("storage-progress", progress);
+      if !complete {
+        summary.truncated = true;
+        break;
+      }
+    }
+    if !summary.truncated {
+      let (mut pool, mut trash) = (0, 0);
+      summary.truncated = !walk_file_sizes(&paths.pool_dir, &mut budget, &cancel, |_, len| {
+        pool += len
+      }) || !walk_file_sizes(&paths.root_dir.join("trash"), &mut budget, &cancel, |_, len| {
+        trash += len
+      });
+      summary.pool_bytes = pool;
+      summary.trash_bytes = trash;
+    }
+    boards.sort_by_key(|b| std::cmp::Reverse(b.bytes));
+    boards.truncate(MAX_STORAGE_TOP_BOARDS);
+    summary.largest = boards;
+    Ok(summary)
+  })
+  .await
+}
+
+#[tauri::command]
+fn cancel_storage_summary(scan: tauri::State<'_, StorageScan>) {
+  scan.0.store(true, std::sync::atomic::Ordering::Relaxed);
+}
+
+#[tauri::command]
+fn create_backup(paths: tauri::State<'_, AppPaths>, dest_dir: String) -> Result<String, AppError> {
+  let dest = std::path::PathBuf::from(dest_dir.trim());
+  if dest.as_os_str().is_empty() {
+    return Err(AppError::Invalid("backup folder not set".into()));
+  }
+  if !dest.exists() {
+    return Err(AppError::NotFound("backup folder does not exist".into()));
+  }
+  if !dest.is_dir() {
+    return Err(AppError::Invalid("backup folder is not a directory".into()));
+  }
+  if dest.starts_with(&paths.root_dir) {
+    return Err(AppError::Invalid(
+      "backup folder must be outside boards directory".into(),
+    ));
+  }
+
+  let timestamp = Utc::now().format("%Y%m%d-%H%M%S").to_string();
+  let filename = format!("LANA-backup-{timestamp}.zip");
+  let out_path = dest.join(&filename);
+
+  let file = std::fs::File::create(&out_path)
+    .map_err(|e| AppError::Io(format!("create backup failed: {e}")))?;
+  let mut zip = zip::ZipWriter::new(file);
+  let options = zip::write::FileOptions::default()
+    .compression_method(zip::CompressionMethod::Deflated)
+    .unix_permissions(0o644);
+
+  // Boards keep their layout at the zip root; pooled images sit beside them.
+  for (dir, prefix) in [(&paths.root_dir, ""), (&paths.pool_dir, "assets-pool/")] {
+    for entry in WalkDir::new(dir)
+      .into_iter()
+      .filter_map(Result::ok)
+      .filter(|e| e.file_type().is_file())
+    {
+      let path = entry.path();
+      let rel = match path.strip_prefix(dir) {
+        Ok(rel) => rel,
+        Err(_) => continue,
+      };
+      if rel.as_os_str().is_empty() {
+        continue;
+      }
+      let name = format!("{prefix}{}", rel.to_string_lossy().replace('\\', "/"));
+      let mut input =
+        std::fs::File::open(path).map_err(|e| AppError::Io(format!("zip open failed: {e}")))?;
+      zip
+        .start_file(name, options)
+        .map_err(|e| AppError::Io(format!("zip start failed: {e}")))?;
+      std::io::copy(&mut input, &mut zip)
+        .map_err(|e| AppError::Io(format!("zip write failed: {e}")))?;
+    }
+  }
+  zip
+    .finish()
+    .map_err(|e| AppError::Io(format!("zip finish failed: {e}")))?;
+
+  let mut backups: Vec<(String, std::path::PathBuf)> = Vec::new();
+  if let Ok(entries) = std::fs::read_dir(&dest) {
+    for entry in entries.flatten() {
+      let path = entry.path();
+      if !path.is_file() {
+        continue;
+      }
+      let name = match path.file_name().and_then(|n| n.to_str()) {
+        Some(name) => name.to_string(),
+        None => continue,
+      };
+      if is_backup_filename(&name) {
+        backups.push((name, path));
+      }
+    }
+  }
+  backups.sort_by(|a, b| a.0.cmp(&b.0));
+  if backups.len() > 3 {
+    for (_, path) in backups.iter().take(backups.len() - 3) {
+      let _ = std::fs::remove_file(path);
+    }
+  }
+
+  Ok(filename)
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct DataDirProgress {
+  done: usize,
+  total: usize,
+  bytes: u64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct DataDirMove {
+  #[serde(rename = "dataDir")]
+  data_dir: String,
+  files: usize,
+  bytes: u64,
+}
+
+/// Indexes the copied data at `target` and records it as the data dir,
+/// remembering `source` so the next launch can remove the originals.
+fn switch_data_dir(
+  source: &std::path::Path,
+  target: &std::path::Path,
+  pointer: &std::path::Path,
+) -> Result<(), AppError> {
+  let new_paths = AppPaths::at(target.to_path_buf());
+  let index = read_index(&new_paths)?;
+  write_index_atomic(&new_paths, &index)?;
+  let record = DataDirPointer {
+    storage_root: target.to_path_buf(),
+    previous_root: Some(source.to_path_buf()),
+  };
+  write_data_dir_pointer(pointer, &record)
+}
+
+/// Removes a partial `move_data_dir` copy. A folder the move created goes
+/// entirely; one that already existed only loses the files copied into it.
+fn discard_data_dir_copy(target: &std::path::Path, created: bool, copied: &[std::path::PathBuf]) {
+  if created {
+    let _ = std::fs::remove_dir_all(target);
+    return;
+  }
+  for file in copied {
+    let _ = std::fs::remove_file(file);
+  }
+  for entry in std::fs::read_dir(target).into_iter().flatten().flatten() {
+    if is_plain_dir(&entry) {
+      prune_empty_dirs(&entry.path());
+    }
+  }
+}
+
+/// Copies every file under the data dir to `new_root`, checking each copy's
+/// size, then points the app at the new location. Boards stay locked for the
+/// copy. Any failure before the switch removes the partial copy and leaves the
+/// original untouched. After the switch the app relaunches on the new location
+/// without releasing the board locks, and that launch deletes the originals.
+#[tauri::command]
+async fn move_data_dir(
+  app: tauri::AppHandle,
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  new_root: String,
+) -> Result<DataDirMove, AppError> {
+  let target = std::path::PathBuf::from(new_root.trim());
+  if !target.is_absolute() {
+    return Err(AppError::Invalid(
+      "new data folder must be an absolute path".into(),
+    ));
+  }
+  let source = paths.data_dir.clone();
+  if target.starts_with(&source) || source.starts_with(&target) {
+    return Err(AppError::Invalid(
+      "new data folder must not overlap the current one".into(),
+    ));
+  }
+  let created = !target.exists();
+  if !created {
+    let mut entries = std::fs::read_dir(&target)
+      .map_err(|e| AppError::Io(format!("read new data folder failed: {e}")))?;
+    if entries.next().is_some() {
+      return Err(AppError::Invalid("new data folder must be empty".into()));
+    }
+  }
+  std::fs::create_dir_all(&target)
+    .map_err(|e| AppError::Io(format!("create new data folder failed: {e}")))?;
+  let probe = target.join(".lana-write-test");
+  if let Err(e) = std::fs::write(&probe, b"") {
+    discard_data_dir_copy(&target, created, &[]);
+    return Err(AppError::Io(format!(
+      "new data folder is not writable: {e}"
+    )));
+  }
+  let _ = std::fs::remove_file(&probe);
+  let pointer = data_dir_pointer(&app)?;
+  // Sorted so two multi-board operations can never wait on each other.
+  let mut board_ids: Vec<String> = read_index(&paths)?
+    .boards
+    .into_iter()
+    .map(|b| b.id)
+    .collect();
+  board_ids.sort();
+  let board_locks: Vec<_> = board_ids.iter().map(|id| locks.for_board(id)).collect();
+
+  run_blocking(move || {
+    let _guards: Vec<_> = board_locks
+      .iter()
+      .map(|lock| lock.lock().unwrap_or_else(|e| e.into_inner()))
+      .collect();
+    let files: Vec<(std::path::PathBuf, u64)> = WalkDir::new(&source)
+      .into_iter()
+      .filter_map(Result::ok)
+      .filter(|e| e.file_type().is_file())
+      .filter_map(|e| Some((e.path().to_path_buf(), e.metadata().ok()?.len())))
+      .collect();
+    let total = files.len();
+    let mut bytes = 0u64;
+    let mut copied: Vec<std::path::PathBuf> = Vec::with_capacity(total);
+    let mut copy = |file: &std::path::Path, len: u64| -> Result<(), AppError> {
+      let rel = file
+        .strip_prefix(&source)
+        .map_err(|_| AppError::Invalid("invalid data path".to_string()))?;
+      let dest = target.join(rel);
+      if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)
+          .map_err(|e| AppError::Io(format!("create folder failed: {e}")))?;
+      }
+      let written =
+        std::fs::copy(file, &dest).map_err(|e| AppError::Io(format!("copy failed: {e}")))?;
+      copied.push(dest);
+      if written != len {
+        return Err(AppError::Io(format!(
+          "copy of {} is incomplete",
+          rel.display()
+        )));
+      }
+      Ok(())
+    };
+    for (done, (file, len)) in files.iter().enumerate() {
+      if let Err(e) = copy(file, *len) {
+        discard_data_dir_copy(&target, created, &copied);
+        return Err(e);
+      }
+      bytes += len;
+      let progress = DataDirProgress {
+        done: done + 1,
+        total,
+        bytes,
+      };
+      let _ = app.emit("data-dir-progress", progress);
+    }
+
+    if let Err(e) = switch_data_dir(&source, &target, &pointer) {
+      discard_data_dir_copy(&target, created, &copied);
+      return Err(e);
+    }
+    let moved = DataDirMove {
+      data_dir: target.to_string_lossy().to_string(),
+      files: total,
+      bytes,
+    };
+    let _ = app.emit("data-dir-moved", moved);
+    // This process still writes through paths into the old data dir, which the
+    // next launch deletes; nothing may be saved there after the switch.
+    app.restart()
+  })
+  .await
+}
+
+/// Removes empty directories below `dir`, deepest first, then `dir` itself if
+/// that leaves it empty. Symlinks are never followed. Returns how many went.
+fn prune_empty_dirs(dir: &std::path::Path) -> u32 {
+  let mut removed = 0;
+  if let Ok(entries) = std::fs::read_dir(dir) {
+    for entry in entries.flatten() {
+      if is_plain_dir(&entry) {
+        removed += prune_empty_dirs(&entry.path());
+      }
+    }
+  }
+  // `remove_dir` refuses a directory that still has anything in it.
+  if std::fs::remove_dir(dir).is_ok() {
+    removed += 1;
+  }
+  removed
+}
+
+/// Clears empty leftovers from `trash/`, including the folder itself.
+#[tauri::command]
+fn tidy_trash(paths: tauri::State<'_, AppPaths>) -> u32 {
+  prune_empty_dirs(&paths.root_dir.join("trash"))
+}
+
+#[tauri::command]
+fn empty_trash(paths: tauri::State<'_, AppPaths>) -> Result<(), AppError> {
+  let mut index = read_index(&paths)?;
+  index.boards.retain(|b| b.deleted_at.is_none());
+  write_index_atomic(&paths, &index)?;
+
+  let trash_dir = paths.root_dir.join("trash");
+  if trash_dir.exists() {
+    if let Err(e) = std::fs::remove_dir_all(&trash_dir) {
+      // Whatever could be deleted is gone; drop any directories it emptied.
+      prune_empty_dirs(&trash_dir);
+      return Err(AppError::Io(format!("empty trash failed: {e}")));
+    }
+  }
+  Ok(())
+}
+
+#[tauri::command]
+fn create_board(paths: tauri::State<'_, AppPaths>, name: String) -> Result<BoardMeta, AppError> {
+  let index = read_index(&paths)?;
+  let board_id = generate_board_id(&paths, &index);
+  let safe_name = sanitize_board_name(&name);
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  ensure_board_file(&board_paths, &board_id, &safe_name)?;
+  let meta = BoardMeta {
+    id: board_id,
+    name: safe_name,
+    updated_at: now_millis(),
+    deleted_at: None,
+    pinned: false,
+  };
+  let mut next = index;
+  next.boards.push(meta.clone());
+  write_index_atomic(&paths, &next)?;
+  Ok(meta)
+}
+
+/// Renames a live board in its board.json and the index, without the caller
+/// having to send the whole board.
+#[tauri::command]
+fn rename_board(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+  new_name: String,
+) -> Result<BoardMeta, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, index) = open_live_board(&paths, &board_id)?;
+  board.name = sanitize_board_name(&new_name);
+  write_board_atomic(&board_paths, &board)?;
+  let index = ensure_board_index_contains(&paths, index, &board_id, &board.name)?;
+  index
+    .boards
+    .into_iter()
+    .find(|b| b.id == board_id)
+    .ok_or_else(|| AppError::NotFound("board not found".to_string()))
+}
+
+/// Stores a copy of a board (and the assets its cards use) under
+/// `LANA/templates/<slug>`. The board id is cleared; card and column ids are
+/// regenerated whenever the template is instantiated.
+#[tauri::command]
+fn save_board_as_template(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+  template_name: String,
+) -> Result<TemplateMeta, AppError> {
+  let name = sanitize_board_name(&template_name);
+  let slug =
+    template_slug(&name).ok_or_else(|| AppError::Invalid("invalid template name".to_string()))?;
+
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let template_paths = board_paths(&paths.templates_dir, &slug);
+  let (board_paths, mut board, _) = open_live_board(&paths, &board_id)?;
+  if is_symlink(&template_paths.dir) {
+    return Err(AppError::Invalid("template directory is a symlink".into()));
+  }
+  if template_paths.dir.exists() {
+    std::fs::remove_dir_all(&template_paths.dir)
+      .map_err(|e| AppError::Io(format!("replace template failed: {e}")))?;
+  }
+  std::fs::create_dir_all(&template_paths.assets_dir)
+    .map_err(|e| AppError::Io(format!("create template dir failed: {e}")))?;
+
+  for card in &board.cards {
+    let refs = [card.src.as_deref(), card.image.as_deref(), card.favicon.as_deref()];
+    for value in refs.into_iter().flatten() {
+      if let Some(file) = card_asset_file(&board_paths, value) {
+        copy_asset_into(&file, &template_paths.assets_dir)?;
+      }
+    }
+  }
+
+  board.id = String::new();
+  board.name = name.clone();
+  write_board_atomic(&template_paths, &board)?;
+  Ok(TemplateMeta {
+    id: slug,
+    name,
+    card_count: board.cards.len(),
+  })
+}
+
+#[tauri::command]
+fn list_templates(paths: tauri::State<'_, AppPaths>) -> Result<Vec<TemplateMeta>, AppError> {
+  let entries = match std::fs::read_dir(&paths.templates_dir) {
+    Ok(entries) => entries,
+    Err(_) => return Ok(vec![]),
+  };
+  let mut templates = Vec::new();
+  for entry in entries.flatten() {
+    if !is_plain_dir(&entry) {
+      continue;
+    }
+    let id = match entry.file_name().to_str() {
+      Some(id) => id.to_string(),
+      None => continue,
+    };
+    if let Ok(board) = read_board(&board_paths(&paths.templates_dir, &id)) {
+      templates.push(TemplateMeta {
+        id,
+        name: board.name,
+        card_count: board.cards.len(),
+      });
+    }
+  }
+  templates.sort_by_key(|t| t.name.to_lowercase());
+  Ok(templates)
+}
+
+#[tauri::command]
+fn create_board_from_template(
+  paths: tauri::State<'_, AppPaths>,
+  name: String,
+  board_name: String,
+) -> Result<BoardMeta, AppError> {
+  let slug =
+    template_slug(&name).ok_or_else(|| AppError::Invalid("invalid template name".to_string()))?;
+  let template_paths = board_paths(&paths.templates_dir, &slug);
+  if !template_paths.file.exists() || is_symlink(&template_paths.dir) {
+    return Err(AppError::NotFound("template not found".into()));
+  }
+  let mut board = read_board(&template_paths)?;
+
+  let index = read_index(&paths)?;
+  let board_id = generate_board_id(&paths, &index);
+  let safe_name = sanitize_board_name(&board_name);
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  ensure_board_file(&board_paths, &board_id, &safe_name)?;
+
+  if let Ok(entries) = std::fs::read_dir(&template_paths.assets_dir) {
+    for entry in entries.flatten() {
+      copy_asset_into(&entry.path(), &board_paths.assets_dir)?;
+    }
+  }
+
+  remap_board_ids(&mut board);
+  board.id = board_id.clone();
+  board.name = safe_name.clone();
+  write_board_atomic(&board_paths, &board)?;
+
+  let meta = BoardMeta {
+    id: board_id,
+    name: safe_name,
+    updated_at: now_millis(),
+    deleted_at: None,
+    pinned: false,
+  };
+  let mut next = index;
+  next.boards.push(meta.clone());
+  write_index_atomic(&paths, &next)?;
+  Ok(meta)
+}
+
+/// Copies every file under `src` to the same relative path under `dest`.
+/// Symlinks are skipped so a copy can't pull in files from outside the board.
+fn copy_dir_files(src: &std::path::Path, dest: &std::path::Path) -> Result<usize, AppError> {
+  let mut copied = 0;
+  for entry in WalkDir::new(src).into_iter().filter_map(Result::ok) {
+    if !entry.file_type().is_file() {
+      continue;
+    }
+    let rel = entry
+      .path()
+      .strip_prefix(src)
+      .map_err(|_| AppError::Invalid("invalid asset path".to_string()))?;
+    let target = dest.join(rel);
+    if let Some(parent) = target.parent() {
+      std::fs::create_dir_all(parent)
+        .map_err(|e| AppError::Io(format!("create assets dir failed: {e}")))?;
+    }
+    std::fs::copy(entry.path(), &target)
+      .map_err(|e| AppError::Io(format!("copy asset failed: {e}")))?;
+    copied += 1;
+  }
+  Ok(copied)
+}
+
+/// Forks a live board: a new id, fresh card and column ids, and its own copy
+/// of the assets folder (paths kept, so `src` references still resolve).
+#[tauri::command]
+fn duplicate_board(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+  new_name: Option<String>,
+) -> Result<BoardMeta, AppError> {
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (source_paths, mut board, index) = open_live_board(&paths, &board_id)?;
+
+  let copy_id = generate_board_id(&paths, &index);
+  let default_name = format!("{} (copy)", board.name);
+  let safe_name = sanitize_board_name(new_name.as_deref().unwrap_or(&default_name));
+  let copy_paths = board_paths(&paths.root_dir, &copy_id);
+  ensure_board_file(&copy_paths, &copy_id, &safe_name)?;
+
+  remap_board_ids(&mut board);
+  board.id = copy_id.clone();
+  board.name = safe_name.clone();
+  let write = || -> Result<(), AppError> {
+    if source_paths.assets_dir.is_dir() && !is_symlink(&source_paths.assets_dir) {
+      copy_dir_files(&source_paths.assets_dir, &copy_paths.assets_dir)?;
+    }
+    write_board_atomic(&copy_paths, &board)?;
+    Ok(())
+  };
+  if let Err(e) = write() {
+    let _ = std::fs::remove_dir_all(&copy_paths.dir);
+    return Err(e);
+  }
+
+  let meta = BoardMeta {
+    id: copy_id,
+    name: safe_name,
+    updated_at: now_millis(),
+    deleted_at: None,
+    pinned: false,
+  };
+  let mut next = index;
+  next.boards.push(meta.clone());
+  write_index_atomic(&paths, &next)?;
+  Ok(meta)
+}
+
+const MAX_READING_LIST_ROWS: usize = 5000;
+/// Matches the frontend's `BOARD_START_X/Y` and link card height without an image.
+const IMPORT_ORIGIN: f64 = 5000.0;
+const IMPORT_CARD_HEIGHT: f64 = 110.0;
+const IMPORT_CARDS_PER_ROW: usize = 6;
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ReadingListImport {
+  board: BoardMeta,
+  imported: usize,
+  skipped: usize,
+}
+
+/// Splits a reading-list tag cell: Pocket uses `a|b`, Instapaper a JSON array.
+fn reading_list_tags(cell: &str) -> Vec<String> {
+  let cell = cell.trim();
+  let raw: Vec<String> = if cell.starts_with('[') {
+    serde_json::from_str(cell).unwrap_or_default()
+  } else {
+    cell.split(['|', ',']).map(str::to_string).collect()
+  };
+  let mut tags: Vec<String> = Vec::new();
+  for tag in raw.iter().filter_map(|t| normalize_tag(t)) {
+    if !tags.contains(&tag) {
+      tags.push(tag);
+    }
+  }
+  tags
+}
+
+/// Creates a board with one link card per row of a Pocket or Instapaper CSV
+/// export, oldest first. Metadata is left for `backfill_link_metadata`.
+#[tauri::command]
+fn import_reading_list(
+  paths: tauri::State<'_, AppPaths>,
+  csv: String,
+  name: Option<String>,
+) -> Result<ReadingListImport, AppError> {
+  let mut reader = csv::ReaderBuilder::new()
+    .flexible(true)
+    .from_reader(csv.as_bytes());
+  let headers: Vec<String> = reader
+    .headers()
+    .map_err(|e| AppError::Serialize(format!("read csv header failed: {e}")))?
+    .iter()
+    .map(|h| h.trim().to_lowercase())
+    .collect();
+  let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+  // Pocket: title,url,time_added,tags,status.
+  // Instapaper: URL,Title,Selection,Folder,Timestamp and, in newer exports, Tags.
+  let url_col =
+    column(&["url"]).ok_or_else(|| AppError::Invalid("csv has no url column".to_string()))?;
+  let title_col = column(&["title"]);
+  let time_col = column(&["time_added", "timestamp"]);
+  let tags_col = column(&["tags"]);
+  let folder_col = column(&["folder"]);
+
+  let mut rows: Vec<(i64, Card)> = Vec::new();
+  let mut skipped = 0;
+  for (i, record) in reader.records().enumerate() {
+    if rows.len() >= MAX_READING_LIST_ROWS {
+      skipped += 1;
+      continue;
+    }
+    let record = match record {
+      Ok(record) => record,
+      Err(_) => {
+        skipped += 1;
+        continue;
+      }
+    };
+    let cell = |col: Option<usize>| col.and_then(|c| record.get(c)).unwrap_or("").trim();
+    let url = match Url::parse(cell(Some(url_col))) {
+      Ok(url) if matches!(url.scheme(), "http" | "https") => url.to_string(),
+      _ => {
+        skipped += 1;
+        continue;
+      }
+    };
+    let mut tags = reading_list_tags(cell(tags_col));
+    let folder = cell(folder_col);
+    if !matches!(folder.to_lowercase().as_str(), "" | "unread" | "archive") {
+      tags.extend(normalize_tag(folder).filter(|f| !tags.contains(f)));
+    }
+    let added = cell(time_col).parse::<i64>().unwrap_or(0);
+    rows.push((
+      added,
+      Card {
+        id: format!("card-{}-{i}", now_millis()),
+        r#type: "link".to_string(),
+        x: 0.0,
+        y: 0.0,
+        width: 0.0,
+        height: IMPORT_CARD_HEIGHT,
+        text: String::new(),
+        src: None,
+        natural_width: None,
+        natural_height: None,
+        url: Some(url),
+        title: Some(cell(title_col).to_string()).filter(|t| !t.is_empty()),
+        description: None,
+        image: None,
+        favicon: None,
+        site_name: None,
+        fetched_at: None,
+        note: None,
+        note_expanded: None,
+        tags,
+      },
+    ));
+  }
+  if rows.is_empty() {
+    return Err(AppError::Invalid("csv has no importable links".into()));
+  }
+  rows.sort_by_key(|(added, _)| *added);
+
+  let index = read_index(&paths)?;
+  let board_id = generate_board_id(&paths, &index);
+  let safe_name = sanitize_board_name(name.as_deref().unwrap_or("Reading list"));
+  let mut board = empty_board(&board_id, &safe_name);
+  let (width, gap) = (board.settings.default_card_width, board.settings.grid());
+  board.cards = rows
+    .into_iter()
+    .enumerate()
+    .map(|(i, (_, mut card))| {
+      let (col, row) = (i % IMPORT_CARDS_PER_ROW, i / IMPORT_CARDS_PER_ROW);
+      card.x = IMPORT_ORIGIN + col as f64 * (width + gap);
+      card.y = IMPORT_ORIGIN + row as f64 * (IMPORT_CARD_HEIGHT + gap);
+      card.width = width;
+      card
+    })
+    .collect();
+  let imported = board.cards.len();
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  ensure_board_file(&board_paths, &board_id, &safe_name)?;
+  write_board_atomic(&board_paths, &board)?;
+
+  let meta = BoardMeta {
+    id: board_id,
+    name: safe_name,
+    updated_at: now_millis(),
+    deleted_at: None,
+    pinned: false,
+  };
+  let mut next = index;
+  next.boards.push(meta.clone());
+  write_index_atomic(&paths, &next)?;
+  Ok(ReadingListImport {
+    board: meta,
+    imported,
+    skipped,
+  })
+}
+
+#[tauri::command]
+fn delete_board(
+  paths: tauri::State<'_, AppPaths>,
+  settings: tauri::State<'_, SettingsState>,
+  board_id: String,
+) -> Result<(), AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let mut index = read_index(&paths)?;
+  if !settings.current().use_trash() {
+    let before = index.boards.len();
+    index.boards.retain(|b| b.id != board_id);
+    if index.boards.len() == before {
+      return Err(AppError::NotFound("board not found".into()));
+    }
+    let board_paths = board_paths(&paths.root_dir, &board_id);
+    if is_symlink(&board_paths.dir) {
+      // Drop the link itself; never follow it into someone else's files.
+      let _ = std::fs::remove_file(&board_paths.dir);
+    } else if board_paths.dir.exists() {
+      std::fs::remove_dir_all(&board_paths.dir)
+        .map_err(|e| AppError::Io(format!("delete board failed: {e}")))?;
+    }
+    write_index_atomic(&paths, &index)?;
+    return Ok(());
+  }
+  let mut found = false;
+  for meta in index.boards.iter_mut() {
+    if meta.id == board_id {
+      meta.deleted_at = Some(now_millis());
+      found = true;
+      break;
+    }
+  }
+  if !found {
+    return Err(AppError::NotFound("board not found".into()));
+  }
+  write_index_atomic(&paths, &index)?;
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  if board_paths.dir.exists() {
+    let trash_dir = paths.root_dir.join("trash");
+    std::fs::create_dir_all(&trash_dir)
+      .map_err(|e| AppError::Io(format!("create trash dir failed: {e}")))?;
+    let dest = trash_dir.join(&board_id);
+    if dest.exists() {
+      let _ = std::fs::remove_dir_all(&dest);
+    }
+    std::fs::rename(&board_paths.dir, &dest)
+      .map_err(|e| AppError::Io(format!("move board to trash failed: {e}")))?;
+  }
+  Ok(())
+}
+
+#[tauri::command]
+fn restore_board(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+  force_copy: Option<bool>,
+) -> Result<String, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let trash_dir = paths.root_dir.join("trash");
+  let src = trash_dir.join(&board_id);
+  if !src.exists() {
+    return Err(AppError::NotFound("board not found in trash".into()));
+  }
+  let mut index = read_index(&paths)?;
+  let mut restored_id = board_id.clone();
+  if paths.root_dir.join(&board_id).exists() {
+    if !force_copy.unwrap_or(false) {
+      return Err(AppError::Conflict("board already exists".into()));
+    }
+    // Restore alongside the live board under a fresh id.
+    restored_id = generate_board_id(&paths, &index);
+    index.boards.retain(|b| !(b.id == board_id && b.deleted_at.is_some()));
+  }
+  let dest = paths.root_dir.join(&restored_id);
+  std::fs::rename(&src, &dest).map_err(|e| AppError::Io(format!("restore board failed: {e}")))?;
+
+  let restored_paths = board_paths(&paths.root_dir, &restored_id);
+  let name = read_board_name(&restored_paths.file).unwrap_or_else(|| board_id.clone());
+  if restored_id != board_id {
+    let mut board = read_board(&restored_paths)?;
+    board.id = restored_id.clone();
+    write_board_atomic(&restored_paths, &board)?;
+  }
+  index = ensure_board_index_contains(&paths, index, &restored_id, &name)?;
+  write_index_atomic(&paths, &index)?;
+  prune_empty_dirs(&trash_dir);
+  Ok(restored_id)
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct RestorePreview {
+  #[serde(rename = "boardId")]
+  board_id: String,
+  name: String,
+  #[serde(rename = "sizeBytes")]
+  size_bytes: u64,
+  #[serde(rename = "cardCount")]
+  card_count: usize,
+  /// A live board already uses this id, so restoring needs `force_copy`.
+  conflict: bool,
+}
+
+#[tauri::command]
+fn preview_restore(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+) -> Result<RestorePreview, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let trashed = board_paths(&paths.root_dir.join("trash"), &board_id);
+  if !trashed.dir.exists() {
+    return Err(AppError::NotFound("board not found in trash".into()));
+  }
+  let board = read_board(&trashed).ok();
+  let size_bytes = WalkDir::new(&trashed.dir)
+    .into_iter()
+    .filter_map(Result::ok)
+    .filter(|e| e.file_type().is_file())
+    .filter_map(|e| e.metadata().ok())
+    .map(|m| m.len())
+    .sum();
+  Ok(RestorePreview {
+    name: board.as_ref().map(|b| b.name.clone()).unwrap_or_else(|| board_id.clone()),
+    card_count: board.as_ref().map_or(0, |b| b.cards.len()),
+    size_bytes,
+    conflict: paths.root_dir.join(&board_id).exists(),
+    board_id,
+  })
+}
+
+#[tauri::command]
+async fn fetch_link_metadata(
+  paths: tauri::State<'_, AppPaths>,
+  settings: tauri::State<'_, SettingsState>,
+  http: tauri::State<'_, HttpState>,
+  link_cache: tauri::State<'_, LinkCache>,
+  board_id: String,
+  url: String,
+) -> Result<LinkMetadata, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let settings = settings.current();
+  let client = board_fetch_client(&paths, &board_id, &settings, http.current().fetch)?;
+  let opts = settings.fetch_options();
+  fetch_metadata(&paths, &link_cache, &client, &opts, &board_id, &url).await
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ImageUrlCheck {
+  #[serde(rename = "isImage")]
+  is_image: bool,
+  #[serde(rename = "contentType")]
+  content_type: Option<String>,
+  /// Format sniffed from the first bytes, e.g. `png` or `webp`.
+  format: Option<String>,
+  width: Option<u32>,
+  height: Option<u32>,
+}
+
+/// Enough for the header of any format `image` can size without decoding.
+const IMAGE_SNIFF_BYTES: usize = 64 * 1024;
+
+/// Tells an image URL from a page without downloading it: asks for the first
+/// bytes only and sniffs format and dimensions from them.
+#[tauri::command]
+async fn is_image_url(
+  http: tauri::State<'_, HttpState>,
+  url: String,
+) -> Result<ImageUrlCheck, AppError> {
+  let parsed =
+    Url::parse(url.trim()).map_err(|e| AppError::Invalid(format!("invalid url: {e}")))?;
+  if parsed.scheme() != "http" && parsed.scheme() != "https" {
+    return Err(AppError::Invalid("unsupported url scheme".into()));
+  }
+  if !is_safe_url(&parsed) {
+    return Err(AppError::Blocked("blocked url host".into()));
+  }
+  let client = http.current().fetch;
+  let mut resp = client
+    .get(parsed)
+    .header(reqwest::header::RANGE, format!("bytes=0-{}", IMAGE_SNIFF_BYTES - 1))
+    .send()
+    .await
+    .map_err(fetch_error)?;
+  if !resp.status().is_success() {
+    return Err(AppError::Network(format!(
+      "request failed ({})",
+      resp.status()
+    )));
+  }
+  let content_type = resp
+    .headers()
+    .get(CONTENT_TYPE)
+    .and_then(|v| v.to_str().ok())
+    .map(|v| v.split(';').next().unwrap_or(v).trim().to_ascii_lowercase());
+
+  // Servers that ignore Range send the whole body; stop reading early.
+  let mut head: Vec<u8> = Vec::new();
+  while head.len() < IMAGE_SNIFF_BYTES {
+    match resp.chunk().await {
+      Ok(Some(chunk)) => head.extend_from_slice(&chunk),
+      _ => break,
+    }
+  }
+  head.truncate(IMAGE_SNIFF_BYTES);
+  let format = image::guess_format(&head).ok();
+  let dimensions = format.and_then(|f| {
+    image::ImageReader::with_format(std::io::Cursor::new(&head[..]), f)
+      .into_dimensions()
+      .ok()
+  });
+  let is_image =
+    format.is_some() || content_type.as_deref().is_some_and(|t| t.starts_with("image/"));
+  Ok(ImageUrlCheck {
+    is_image,
+    content_type,
+    format: format.and_then(|f| f.extensions_str().first()).map(|e| e.to_string()),
+    width: dimensions.map(|(w, _)| w),
+    height: dimensions.map(|(_, h)| h),
+  })
+}
+
+const MAX_LINK_CACHE_ENTRIES: usize = 1000;
+
+/// `link-cache.json`, read on first use and kept in memory. The mutex also
+/// serializes stores, each of which writes the file back.
+#[derive(Clone, Default)]
+struct LinkCache(
+  std::sync::Arc<std::sync::Mutex<Option<std::collections::HashMap<String, LinkCacheEntry>>>>,
+);
+
+impl LinkCache {
+  fn with<T>(
+    &self,
+    paths: &AppPaths,
+    f: impl FnOnce(&mut std::collections::HashMap<String, LinkCacheEntry>) -> T,
+  ) -> T {
+    let mut cache = self.0.lock().unwrap_or_else(|e| e.into_inner());
+    f(cache.get_or_insert_with(|| read_link_cache(paths)))
+  }
+}
+
+/// A file under `link-cache/` and the extension it is saved into boards with.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct CachedAsset {
+  file: String,
+  ext: String,
+}
+
+/// A fetched page, shared by every board that links to it. The preview image
+/// is kept at full size under `link-cache/` and copied into each board.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct LinkCacheEntry {
+  #[serde(rename = "fetchedAt")]
+  fetched_at: i64,
+  metadata: LinkMetadata,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  image: Option<CachedAsset>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  favicon: Option<CachedAsset>,
+}
+
+/// Downloaded bytes and the extension to save them with.
+type AssetBytes = (Vec<u8>, String);
+
+/// Cached metadata plus the source image and favicon, if any.
+type CachedLink = (LinkMetadata, Option<AssetBytes>, Option<AssetBytes>);
+
+fn read_link_cache(paths: &AppPaths) -> std::collections::HashMap<String, LinkCacheEntry> {
+  std::fs::read_to_string(&paths.link_cache_file)
+    .ok()
+    .and_then(|text| serde_json::from_str(&text).ok())
+    .unwrap_or_default()
+}
+
+fn write_link_cache(
+  paths: &AppPaths,
+  cache: &std::collections::HashMap<String, LinkCacheEntry>,
+) -> Result<(), AppError> {
+  let json = serde_json::to_string(cache)
+    .map_err(|e| AppError::Serialize(format!("serialize failed: {e}")))?;
+  let tmp = paths.data_dir.join("link-cache.json.tmp");
+  std::fs::write(&tmp, json).map_err(|e| AppError::Io(format!("write temp failed: {e}")))?;
+  replace_file(&tmp, &paths.link_cache_file)
+    .map_err(|e| AppError::Io(format!("rename failed: {e}")))
+}
+
+/// The cached entry for `key` if it is younger than `ttl_ms`. An entry whose
+/// files went missing counts as a miss so they get refetched.
+fn cached_link(paths: &AppPaths, cache: &LinkCache, key: &str, ttl_ms: i64) -> Option<CachedLink> {
+  let entry = cache.with(paths, |cache| cache.get(key).cloned())?;
+  if now_millis() - entry.fetched_at > ttl_ms {
+    return None;
+  }
+  let load = |asset: Option<CachedAsset>| -> Result<Option<AssetBytes>, ()> {
+    match asset {
+      Some(asset) => match std::fs::read(paths.link_cache_dir.join(&asset.file)) {
+        Ok(bytes) => Ok(Some((bytes, asset.ext))),
+        Err(_) => Err(()),
+      },
+      None => Ok(None),
+    }
+  };
+  let image = load(entry.image).ok()?;
+  let favicon = load(entry.favicon).ok()?;
+  Some((entry.metadata, image, favicon))
+}
+
+fn write_cached_asset(
+  paths: &AppPaths,
+  file: String,
+  bytes: &[u8],
+  ext: &str,
+) -> Result<CachedAsset, AppError> {
+  std::fs::create_dir_all(&paths.link_cache_dir)
+    .map_err(|e| AppError::Io(format!("create link cache failed: {e}")))?;
+  let tmp = paths.link_cache_dir.join(format!("{file}.tmp"));
+  std::fs::write(&tmp, bytes).map_err(|e| AppError::Io(format!("write temp image failed: {e}")))?;
+  replace_file(&tmp, &paths.link_cache_dir.join(&file))
+    .map_err(|e| AppError::Io(format!("rename image failed: {e}")))?;
+  Ok(CachedAsset { file, ext: ext.to_string() })
+}
+
+fn store_cached_link(
+  paths: &AppPaths,
+  cache: &LinkCache,
+  key: &str,
+  metadata: LinkMetadata,
+  image: Option<(&[u8], &str)>,
+  favicon: Option<(&[u8], &str)>,
+) -> Result<(), AppError> {
+  use sha2::Digest;
+  cache.with(paths, |cache| {
+    let stem = format!("{:x}", sha2::Sha256::digest(key.as_bytes()));
+    let mut entry = LinkCacheEntry {
+      fetched_at: now_millis(),
+      metadata,
+      image: None,
+      favicon: None,
+    };
+    if let Some((bytes, ext)) = image {
+      entry.image = Some(write_cached_asset(
+        paths,
+        format!("{stem}{ext}"),
+        bytes,
+        ext,
+      )?);
+    }
+    if let Some((bytes, ext)) = favicon {
+      entry.favicon = Some(write_cached_asset(
+        paths,
+        format!("{stem}-favicon{ext}"),
+        bytes,
+        ext,
+      )?);
+    }
+    if let Some(old) = cache.insert(key.to_string(), entry.clone()) {
+      for (stale, current) in [(old.image, &entry.image), (old.favicon, &entry.favicon)] {
+        if stale.is_some() && stale.as_ref() != current.as_ref() {
+          remove_cached_asset(paths, stale.as_ref());
+        }
+      }
+    }
+    if cache.len() > MAX_LINK_CACHE_ENTRIES {
+      let mut by_age: Vec<(i64, String)> = cache
+        .iter()
+        .map(|(k, e)| (e.fetched_at, k.clone()))
+        .collect();
+      by_age.sort();
+      for (_, stale) in by_age
+        .into_iter()
+        .take(cache.len() - MAX_LINK_CACHE_ENTRIES)
+      {
+        if let Some(old) = cache.remove(&stale) {
+          remove_cached_asset(paths, old.image.as_ref());
+          remove_cached_asset(paths, old.favicon.as_ref());
+        }
+      }
+    }
+    write_link_cache(paths, cache)
+  })
+}
+
+fn remove_cached_asset(paths: &AppPaths, asset: Option<&CachedAsset>) {
+  if let Some(asset) = asset {
+    let _ = std::fs::remove_file(paths.link_cache_dir.join(&asset.file));
+  }
+}
+
+/// Fits a preview image and stores it in the board's assets (or the pool).
+async fn save_preview_image(
+  paths: &AppPaths,
+  opts: &FetchOptions,
+  board_id: &str,
+  bytes: Vec<u8>,
+  ext: String,
+) -> Result<(String, Option<(u32, u32)>), AppError> {
+  let app_paths = paths.clone();
+  let target_board = board_id.to_string();
+  let (max_dim, storage) = (opts.max_image_dimension, opts.asset_storage);
+  run_blocking(move || {
+    let (data, dims) = fit_image_bytes(bytes, max_dim);
+    save_asset_bytes(
+      &app_paths,
+      &target_board,
+      AssetRole::Link,
+      &data,
+      &ext,
+      storage,
+    )
+    .map(|s| (s, dims))
+  })
+  .await
+}
+
+/// Stores a favicon as downloaded; icons are small and often `.ico`, which
+/// `fit_image_bytes` can't decode.
+async fn save_favicon(
+  paths: &AppPaths,
+  opts: &FetchOptions,
+  board_id: &str,
+  bytes: Vec<u8>,
+  ext: String,
+) -> Result<String, AppError> {
+  let app_paths = paths.clone();
+  let target_board = board_id.to_string();
+  let storage = opts.asset_storage;
+  run_blocking(move || {
+    save_asset_bytes(
+      &app_paths,
+      &target_board,
+      AssetRole::Favicon,
+      &bytes,
+      &ext,
+      storage,
+    )
+  })
+  .await
+}
+
+/// Fetches a page's title/site/keywords and stores its preview image and
+/// favicon in the board's assets. Pages fetched within the cache TTL are
+/// served from `link-cache.json` without touching the network.
+async fn fetch_metadata(
+  paths: &AppPaths,
+  cache: &LinkCache,
+  client: &reqwest::Client,
+  opts: &FetchOptions,
+  board_id: &str,
+  url: &str,
+) -> Result<LinkMetadata, AppError> {
+  let parsed = Url::parse(url).map_err(|e| AppError::Invalid(format!("invalid url: {e}")))?;
+  let scheme = parsed.scheme();
+  if scheme != "http" && scheme != "https" {
+    return Err(AppError::Invalid("unsupported url scheme".to_string()));
+  }
+  if !is_safe_url(&parsed) {
+    return Err(AppError::Blocked("blocked url host".to_string()));
+  }
+
+  let cache_key = canonical_url_key(url).filter(|_| opts.link_cache_ttl_ms > 0);
+  if let Some(key) = cache_key.clone() {
+    let (app_paths, link_cache, ttl_ms) = (paths.clone(), cache.clone(), opts.link_cache_ttl_ms);
+    let hit = run_blocking(move || Ok(cached_link(&app_paths, &link_cache, &key, ttl_ms))).await?;
+    if let Some((mut meta, image, favicon)) = hit {
+      if let Some((bytes, ext)) = image {
+        if let Ok((saved, dims)) = save_preview_image(paths, opts, board_id, bytes, ext).await {
+          meta.image = Some(saved);
+          meta.image_width = dims.map(|(w, _)| w);
+          meta.image_height = dims.map(|(_, h)| h);
+        }
+      }
+      if let Some((bytes, ext)) = favicon {
+        meta.favicon = save_favicon(paths, opts, board_id, bytes, ext).await.ok();
+      }
+      return Ok(meta);
+    }
+  }
+
+  let (mut final_url, mut text) = fetch_page(client, parsed.clone()).await?;
+
+  // Interstitial pages bounce through a meta refresh or a scripted redirect;
+  // follow at most one such hop so a refresh loop can't keep us fetching.
+  if let Some(next) = redirect_hint(&text, &final_url) {
+    if next != final_url && is_safe_url(&next) {
+      if let Ok((next_url, next_text)) = fetch_page(client, next).await {
+        final_url = next_url;
+        text = next_text;
+      }
+    }
+  }
+
+  let (canonical, title, description, site_name, candidates, keywords, icons) = {
+    let doc = Html::parse_document(&text);
+    let mut title = meta_content(&doc, "meta[property='og:title']")
+      .or_else(|| meta_content(&doc, "meta[name='twitter:title']"))
+      .or_else(|| meta_content(&doc, "meta[name='title']"))
+      .or_else(|| meta_content(&doc, "meta[itemprop='name']"))
+      .or_else(|| json_ld_title(&doc))
+      .or_else(|| title_text(&doc))
+      .or_else(|| final_url.host_str().map(|h| h.to_string()))
+      .unwrap_or_else(|| "Link".to_string());
+    if title == "Google Maps" || title == "Link" {
+      if let Some(map_title) = google_maps_title(&final_url) {
+        title = map_title;
+      }
+    }
+
+    let description = meta_content(&doc, "meta[property='og:description']")
+      .or_else(|| meta_content(&doc, "meta[name='twitter:description']"))
+      .or_else(|| meta_content(&doc, "meta[name='description']"))
+      .or_else(|| meta_content(&doc, "meta[itemprop='description']"));
+
+    let site_name = meta_content(&doc, "meta[property='og:site_name']")
+      .or_else(|| final_url.host_str().map(|h| h.to_string()));
+
+    let (candidates, keywords) = (image_candidates(&doc, &final_url), extract_keywords(&doc));
+    let icons = favicon_candidates(&doc, &final_url);
+    (canonical_link(&doc, &final_url), title, description, site_name, candidates, keywords, icons)
+  };
+
+  // Probe every candidate and keep the largest that isn't icon-sized, falling
+  // back to the largest overall.
+  let mut best: Option<(usize, ProbedImage)> = None;
+  let mut usable: Vec<usize> = Vec::new();
+  for (i, url) in candidates.iter().enumerate() {
+    let probe = match probe_image(client, url).await {
+      Some(probe) => probe,
+      None => continue,
+    };
+    usable.push(i);
+    if best.as_ref().map_or(true, |(_, b)| probe.rank() > b.rank()) {
+      best = Some((i, probe));
+    }
+  }
+
+  let mut image: Option<String> = None;
+  let mut image_dims: Option<(u32, u32)> = None;
+  let mut chosen: Option<usize> = None;
+  let mut source_image: Option<(Vec<u8>, &str)> = None;
+  if let Some((i, probe)) = best {
+    let ext = ext_from_content_type(&probe.content_type).unwrap_or(".img");
+    if cache_key.is_some() {
+      source_image = Some((probe.bytes.clone(), ext));
+    }
+    let saved = save_preview_image(paths, opts, board_id, probe.bytes, ext.to_string()).await;
+    if let Ok((saved, dims)) = saved {
+      image = Some(saved);
+      image_dims = dims;
+      chosen = Some(i);
+    }
+  }
+  let image_candidates = usable
+    .into_iter()
+    .filter(|i| Some(*i) != chosen)
+    .map(|i| candidates[i].to_string())
+    .collect();
+
+  let mut favicon: Option<String> = None;
+  let mut source_favicon: Option<(Vec<u8>, &str)> = None;
+  for icon in &icons {
+    let (bytes, ext) = match download_favicon(client, icon).await {
+      Some(found) => found,
+      None => continue,
+    };
+    if cache_key.is_some() {
+      source_favicon = Some((bytes.clone(), ext));
+    }
+    favicon = save_favicon(paths, opts, board_id, bytes, ext.to_string()).await.ok();
+    break;
+  }
+
+  let meta = LinkMetadata {
+    url: canonical.unwrap_or(final_url).to_string(),
+    title,
+    description,
+    image,
+    favicon: favicon.clone(),
+    site_name,
+    keywords,
+    image_width: image_dims.map(|(w, _)| w),
+    image_height: image_dims.map(|(_, h)| h),
+    image_candidates,
+  };
+  if let Some(key) = cache_key {
+    let (app_paths, link_cache) = (paths.clone(), cache.clone());
+    let mut shared = meta.clone();
+    shared.image = None;
+    shared.favicon = None;
+    let source = if chosen.is_some() { source_image } else { None };
+    let icon = if favicon.is_some() { source_favicon } else { None };
+    let stored = run_blocking(move || {
+      let image = source.as_ref().map(|(bytes, ext)| (bytes.as_slice(), *ext));
+      let favicon = icon.as_ref().map(|(bytes, ext)| (bytes.as_slice(), *ext));
+      store_cached_link(&app_paths, &link_cache, &key, shared, image, favicon)
+    })
+    .await;
+    if let Err(e) = stored {
+      log::warn!("link cache write failed: {e}");
+    }
+  }
+  Ok(meta)
+}
+
+fn backfill_file(paths: &BoardPaths) -> std::path::PathBuf {
+  paths.dir.join("backfill.json")
+}
+
+fn read_backfill_progress(paths: &BoardPaths) -> BackfillProgress {
+  std::fs::read_to_string(backfill_file(paths))
+    .ok()
+    .and_then(|text| serde_json::from_str(&text).ok())
+    .unwrap_or_default()
+}
+
+fn write_backfill_progress(
+  paths: &BoardPaths,
+  progress: &BackfillProgress,
+) -> Result<(), AppError> {
+  let json = serde_json::to_string(progress)
+    .map_err(|e| AppError::Serialize(format!("serialize failed: {e}")))?;
+  let file = backfill_file(paths);
+  let tmp = paths.dir.join("backfill.json.tmp");
+  std::fs::write(&tmp, json).map_err(|e| AppError::Io(format!("write temp failed: {e}")))?;
+  replace_file(&tmp, &file).map_err(|e| AppError::Io(format!("rename failed: {e}")))
+}
+
+fn board_meta_file(paths: &BoardPaths) -> std::path::PathBuf {
+  paths.dir.join("board_meta.json")
+}
+
+fn read_board_local_meta(paths: &BoardPaths) -> BoardLocalMeta {
+  std::fs::read_to_string(board_meta_file(paths))
+    .ok()
+    .and_then(|text| serde_json::from_str(&text).ok())
+    .unwrap_or_default()
+}
+
+fn write_board_local_meta(paths: &BoardPaths, meta: &BoardLocalMeta) -> Result<(), AppError> {
+  let json = serde_json::to_string_pretty(meta)
+    .map_err(|e| AppError::Serialize(format!("serialize failed: {e}")))?;
+  let file = board_meta_file(paths);
+  let tmp = paths.dir.join("board_meta.json.tmp");
+  std::fs::write(&tmp, json).map_err(|e| AppError::Io(format!("write temp failed: {e}")))?;
+  replace_file(&tmp, &file).map_err(|e| AppError::Io(format!("rename failed: {e}")))
+}
+
+fn needs_backfill(card: &Card) -> bool {
+  card.r#type == "link"
+    && card.url.as_deref().is_some_and(|u| !u.trim().is_empty())
+    && (card.title.as_deref().map_or(true, |t| t.trim().is_empty()) || card.site_name.is_none())
+}
+
+/// Fetches metadata for link cards that are missing it, saving each card as it
+/// completes. Progress is checkpointed to `backfill.json`, so a run that was
+/// cancelled or cut short by quitting resumes where it stopped.
+#[tauri::command]
+async fn backfill_link_metadata(
+  app: tauri::AppHandle,
+  paths: tauri::State<'_, AppPaths>,
+  settings: tauri::State<'_, SettingsState>,
+  http: tauri::State<'_, HttpState>,
+  locks: tauri::State<'_, BoardLocks>,
+  backfills: tauri::State<'_, Backfills>,
+  board_id: String,
+) -> Result<BackfillSummary, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let cancel = backfills.start(&board_id)?;
+  let result = run_backfill(&app, &paths, &settings, &http, &locks, &cancel, &board_id).await;
+  backfills.finish(&board_id);
+  result
+}
+
+async fn run_backfill(
+  app: &tauri::AppHandle,
+  paths: &AppPaths,
+  settings: &SettingsState,
+  http: &HttpState,
+  locks: &BoardLocks,
+  cancel: &std::sync::atomic::AtomicBool,
+  board_id: &str,
+) -> Result<BackfillSummary, AppError> {
+  let settings = settings.current();
+  let shared = http.current().fetch;
+  let (app_paths, id, client_settings) = (paths.clone(), board_id.to_string(), settings.clone());
+  let (board_paths, board, mut progress, client) = run_blocking(move || {
+    let (board_paths, board, _) = open_live_board(&app_paths, &id)?;
+    let progress = read_backfill_progress(&board_paths);
+    let client = board_fetch_client(&app_paths, &id, &client_settings, shared)?;
+    Ok((board_paths, board, progress, client))
+  })
+  .await?;
+  let done: std::collections::HashSet<String> = progress.done.iter().cloned().collect();
+  let pending: Vec<(String, String)> = board
+    .cards
+    .iter()
+    .filter(|c| needs_backfill(c) && !done.contains(&c.id))
+    .filter_map(|c| c.url.clone().map(|url| (c.id.clone(), url)))
+    .collect();
+
+  let total = pending.len() + progress.done.len();
+  let mut summary = BackfillSummary {
+    board_id: board_id.to_string(),
+    updated: 0,
+    failed: 0,
+    skipped: progress.done.len(),
+    cancelled: false,
+  };
+  if !progress.done.is_empty() {
+    let resumed = ScanProgress {
+      board_id: board_id.to_string(),
+      done: progress.done.len(),
+      total,
+    };
+    emit_to_board(app, board_id, "backfill-resumed", resumed);
+  }
+
+  let opts = settings.fetch_options();
+  let limiter = app.state::<WorkLimiter>().inner().clone();
+  let link_cache = app.state::<LinkCache>().inner().clone();
+  for (i, (card_id, url)) in pending.into_iter().enumerate() {
+    if cancel.load(std::sync::atomic::Ordering::Relaxed) {
+      summary.cancelled = true;
+      break;
+    }
+    let permit = limiter.acquire().await?;
+    let fetched = fetch_metadata(paths, &link_cache, &client, &opts, board_id, &url).await;
+    drop(permit);
+    match fetched {
+      Ok(meta) => {
+        progress.done.push(card_id.clone());
+        let (app_paths, id, lock) = (
+          paths.clone(),
+          board_id.to_string(),
+          locks.for_board(board_id),
+        );
+        let (checkpoint, step_paths) = (progress, board_paths.clone());
+        let (updated, checkpoint) = run_blocking(move || {
+          let updated = {
+            let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+            apply_backfilled_metadata(&app_paths, &id, &card_id, meta)?
+          };
+          write_backfill_progress(&step_paths, &checkpoint)?;
+          Ok((updated, checkpoint))
+        })
+        .await?;
+        progress = checkpoint;
+        if updated {
+          summary.updated += 1;
+        }
+      }
+      Err(e) => {
+        log::warn!("backfill {board_id}: {url}: {e}");
+        summary.failed += 1;
+      }
+    }
+    let step = ScanProgress {
+      board_id: board_id.to_string(),
+      done: summary.skipped + i + 1,
+      total,
+    };
+    emit_to_board(app, board_id, "backfill-progress", step);
+  }
+
+  if !summary.cancelled {
+    let file = backfill_file(&board_paths);
+    run_blocking(move || Ok(std::fs::remove_file(file).ok())).await?;
+    emit_to_board(app, board_id, "backfill-complete", summary.clone());
+  }
+  Ok(summary)
+}
+
+/// Saves fetched metadata onto one card of a live board. The caller holds the
+/// board lock. Returns false if the card has since been removed.
+fn apply_backfilled_metadata(
+  paths: &AppPaths,
+  board_id: &str,
+  card_id: &str,
+  meta: LinkMetadata,
+) -> Result<bool, AppError> {
+  let (board_paths, mut board, board_index) = open_live_board(paths, board_id)?;
+  let card = match board.cards.iter_mut().find(|c| c.id == card_id) {
+    Some(card) => card,
+    None => return Ok(false),
+  };
+  refresh_link_card(card, meta);
+  commit_board(paths, &board_paths, board_index, &board)?;
+  Ok(true)
+}
+
+/// Whether a card `image` points at a local asset (board or pool) whose file
+/// is gone.
+fn is_missing_asset(paths: &AppPaths, board_paths: &BoardPaths, value: &str) -> bool {
+  let file = if pool_ref_name(value).is_some() {
+    pool_file(paths, value)
+  } else {
+    card_asset_file(board_paths, value)
+  };
+  file.is_some_and(|f| !f.is_file())
+}
+
+/// Re-fetches the preview image for a link card whose image file is missing,
+/// leaving its title and other fields alone. Returns true if it was restored.
+#[tauri::command]
+async fn repair_card_image(
+  paths: tauri::State<'_, AppPaths>,
+  settings: tauri::State<'_, SettingsState>,
+  http: tauri::State<'_, HttpState>,
+  locks: tauri::State<'_, BoardLocks>,
+  link_cache: tauri::State<'_, LinkCache>,
+  board_id: String,
+  card_id: String,
+) -> Result<bool, AppError> {
+  let settings = settings.current();
+  let shared = http.current().fetch;
+  let (app_paths, id, cid, client_settings) = (
+    paths.inner().clone(),
+    board_id.clone(),
+    card_id.clone(),
+    settings.clone(),
+  );
+  let target = run_blocking(move || {
+    let (board_paths, board, _) = open_live_board(&app_paths, &id)?;
+    let card = board
+      .cards
+      .iter()
+      .find(|c| c.id == cid)
+      .ok_or_else(|| AppError::NotFound("card not found".to_string()))?;
+    if card.r#type != "link" {
+      return Err(AppError::Invalid("not a link card".into()));
+    }
+    let url = card
+      .url
+      .clone()
+      .ok_or_else(|| AppError::Invalid("card has no url".to_string()))?;
+    match card.image.as_deref() {
+      Some(value) if is_missing_asset(&app_paths, &board_paths, value) => {}
+      _ => return Ok(None),
+    }
+    let client = board_fetch_client(&app_paths, &id, &client_settings, shared)?;
+    Ok(Some((url, client)))
+  })
+  .await?;
+  let (url, client) = match target {
+    Some(target) => target,
+    None => return Ok(false),
+  };
+
+  let opts = settings.fetch_options();
+  let meta = fetch_metadata(&paths, &link_cache, &client, &opts, &board_id, &url).await?;
+  let image = match meta.image {
+    Some(image) => image,
+    None => return Ok(false),
+  };
+
+  let (app_paths, lock) = (paths.inner().clone(), locks.for_board(&board_id));
+  run_blocking(move || {
+    let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+    let (board_paths, mut board, board_index) = open_live_board(&app_paths, &board_id)?;
+    let card = board
+      .cards
+      .iter_mut()
+      .find(|c| c.id == card_id)
+      .ok_or_else(|| AppError::NotFound("card not found".to_string()))?;
+    card.image = Some(image);
+    commit_board(&app_paths, &board_paths, board_index, &board)?;
+    Ok(true)
+  })
+  .await
+}
+
+const PREFETCH_CONCURRENCY: usize = 3;
+const DEFAULT_PREFETCH_MAX_AGE_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct LinkCardUpdate {
+  #[serde(rename = "boardId")]
+  board_id: String,
+  card: Card,
+}
+
+/// Refreshes link cards whose metadata is older than `max_age` (millis), or
+/// was never stamped, in the background. Cards sharing a URL are fetched once.
+/// Each refreshed card is saved and emitted as `link-card-updated`. Returns
+/// how many URLs were queued.
+#[tauri::command]
+fn prefetch_stale_links(
+  app: tauri::AppHandle,
+  paths: tauri::State<'_, AppPaths>,
+  settings: tauri::State<'_, SettingsState>,
+  http: tauri::State<'_, HttpState>,
+  board_id: String,
+  max_age: Option<i64>,
+) -> Result<usize, AppError> {
+  let (_, board, _) = open_live_board(&paths, &board_id)?;
+  let cutoff = now_millis() - max_age.unwrap_or(DEFAULT_PREFETCH_MAX_AGE_MS).max(0);
+  let mut stale: Vec<(String, Vec<String>)> = Vec::new();
+  let mut seen: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+  for card in board.cards {
+    if card.r#type != "link" || card.fetched_at.is_some_and(|t| t >= cutoff) {
+      continue;
+    }
+    let url = match card.url.filter(|u| !u.trim().is_empty()) {
+      Some(url) => url,
+      None => continue,
+    };
+    let key = canonical_url_key(&url).unwrap_or_else(|| url.clone());
+    match seen.get(&key) {
+      Some(&i) => stale[i].1.push(card.id),
+      None => {
+        seen.insert(key, stale.len());
+        stale.push((url, vec![card.id]));
+      }
+    }
+  }
+  let queued = stale.len();
+  if queued == 0 {
+    return Ok(0);
+  }
+
+  let paths = paths.inner().clone();
+  let settings = settings.current();
+  let client = board_fetch_client(&paths, &board_id, &settings, http.current().fetch)?;
+  let opts = settings.fetch_options();
+  let limiter = app.state::<WorkLimiter>().inner().clone();
+  let link_cache = app.state::<LinkCache>().inner().clone();
+  tauri::async_runtime::spawn(async move {
+    for batch in stale.chunks(PREFETCH_CONCURRENCY) {
+      let tasks: Vec<_> = batch
+        .iter()
+        .cloned()
+        .map(|(url, card_ids)| {
+          let (paths, client, opts) = (paths.clone(), client.clone(), opts.clone());
+          let (board_id, limiter, link_cache) =
+            (board_id.clone(), limiter.clone(), link_cache.clone());
+          tauri::async_runtime::spawn(async move {
+            let meta = match limiter.acquire().await {
+              Ok(_permit) => {
+                fetch_metadata(&paths, &link_cache, &client, &opts, &board_id, &url).await
+              }
+              Err(e) => Err(e),
+            };
+            (card_ids, url, meta)
+          })
+        })
+        .collect();
+      for task in tasks {
+        let (card_ids, url, meta) = match task.await {
+          Ok(result) => result,
+          Err(_) => continue,
+        };
+        let meta = match meta {
+          Ok(meta) => meta,
+          Err(e) => {
+            log::warn!("prefetch {board_id}: {url}: {e}");
+            continue;
+          }
+        };
+        match apply_prefetched_metadata(&app, &paths, &board_id, &card_ids, meta) {
+          Ok(cards) => {
+            for card in cards {
+              let update = LinkCardUpdate {
+                board_id: board_id.clone(),
+                card,
+              };
+              emit_to_board(&app, &board_id, "link-card-updated", update);
+            }
+          }
+          Err(e) => log::warn!("prefetch {board_id}: saving {url} failed: {e}"),
+        }
+      }
+    }
+  });
+  Ok(queued)
+}
+
+/// Writes refreshed metadata onto the cards still on the board and returns
+/// them.
+fn apply_prefetched_metadata(
+  app: &tauri::AppHandle,
+  paths: &AppPaths,
+  board_id: &str,
+  card_ids: &[String],
+  meta: LinkMetadata,
+) -> Result<Vec<Card>, AppError> {
+  let locks = app.state::<BoardLocks>();
+  let lock = locks.for_board(board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, index) = open_live_board(paths, board_id)?;
+  let mut updated = Vec::new();
+  for card in board.cards.iter_mut().filter(|c| card_ids.contains(&c.id)) {
+    refresh_link_card(card, meta.clone());
+    updated.push(card.clone());
+  }
+  if !updated.is_empty() {
+    commit_board(paths, &board_paths, index, &board)?;
+  }
+  Ok(updated)
+}
+
+/// Copies refreshed metadata onto a link card. The title is only filled in
+/// while it is blank or still the URL placeholder, so a title the user typed
+/// survives the refresh.
+fn refresh_link_card(card: &mut Card, meta: LinkMetadata) {
+  let placeholder = card.title.as_deref().map_or(true, |t| {
+    t.trim().is_empty() || Some(t) == card.url.as_deref()
+  });
+  if placeholder {
+    card.title = Some(meta.title);
+  }
+  if meta.description.is_some() {
+    card.description = meta.description;
+  }
+  card.site_name = meta.site_name;
+  if meta.image.is_some() {
+    card.image = meta.image;
+  }
+  if meta.favicon.is_some() {
+    card.favicon = meta.favicon;
+  }
+  card.fetched_at = Some(now_millis());
+}
+
+#[tauri::command]
+fn cancel_backfill(backfills: tauri::State<'_, Backfills>, board_id: String) -> bool {
+  backfills.cancel(&board_id)
+}
+
+const DEFAULT_OLLAMA_URL: &str = "http://127.0.0.1:11434";
+
+/// Names the server in connection failures so a wrong or offline Ollama host
+/// is obvious.
+fn ollama_request_error(base: &str, e: reqwest::Error) -> AppError {
+  if e.is_timeout() {
+    AppError::Timeout(format!("ollama timed out at {base}: {e}"))
+  } else if e.is_connect() {
+    AppError::Network(format!("ollama unreachable at {base}: {e}"))
+  } else {
+    AppError::Network(format!("ollama request failed: {e}"))
+  }
+}
+const MAX_MODEL_NAME_LEN: usize = 200;
+
+/// Model names look like `llama3`, `llama3:8b` or `library/llama3:latest`.
+fn is_valid_model_name(model: &str) -> bool {
+  !model.is_empty()
+    && model.len() <= MAX_MODEL_NAME_LEN
+    && !model.starts_with(['/', '.', ':'])
+    && !model.contains("..")
+    && model
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':' | '/'))
+}
+
+async fn ollama_tags(client: &reqwest::Client, base: &str) -> Result<Vec<OllamaTag>, AppError> {
+  let resp = client
+    .get(format!("{base}/api/tags"))
+    .send()
+    .await
+    .map_err(|e| ollama_request_error(base, e))?;
+  let status = resp.status();
+  if !status.is_success() {
+    return Err(AppError::Network(format!("ollama error ({status})")));
+  }
+  let tags: OllamaTagsResponse = resp
+    .json()
+    .await
+    .map_err(|e| AppError::Serialize(format!("ollama parse failed: {e}")))?;
+  Ok(tags.models)
+}
+
+async fn ollama_has_model(
+  client: &reqwest::Client,
+  base: &str,
+  model: &str,
+) -> Result<bool, AppError> {
+  let tags = ollama_tags(client, base).await?;
+  let latest = format!("{model}:latest");
+  Ok(tags.iter().any(|t| t.name == model || (!model.contains(':') && t.name == latest)))
+}
+
+/// Pulls a model, forwarding each NDJSON status line as `model-pull-progress`.
+async fn ollama_pull_model(
+  app: &tauri::AppHandle,
+  client: &reqwest::Client,
+  base: &str,
+  model: &str,
+) -> Result<(), AppError> {
+  let mut resp = client
+    .post(format!("{base}/api/pull"))
+    .json(&serde_json::json!({ "model": model, "stream": true }))
+    .send()
+    .await
+    .map_err(|e| ollama_request_error(base, e))?;
+  let status = resp.status();
+  if !status.is_success() {
+    let body = resp.text().await.unwrap_or_default();
+    return Err(AppError::Network(format!(
+      "model pull failed ({status}): {body}"
+    )));
+  }
+
+  let mut buf: Vec<u8> = Vec::new();
+  let handle_line = |line: &[u8]| -> Result<(), AppError> {
+    if line.iter().all(|b| b.is_ascii_whitespace()) {
+      return Ok(());
+    }
+    let update: OllamaPullStatus = serde_json::from_slice(line)
+      .map_err(|e| AppError::Serialize(format!("ollama parse failed: {e}")))?;
+    if let Some(error) = update.error {
+      return Err(AppError::Network(format!("model pull failed: {error}")));
+    }
+    let progress = ModelPullProgress {
+      model: model.to_string(),
+      status: update.status,
+      total: update.total,
+      completed: update.completed,
+    };
+    let _ = app.emit("model-pull-progress", progress);
+    Ok(())
+  };
+  while let Some(chunk) = resp
+    .chunk()
+    .await
+    .map_err(|e| AppError::Network(format!("model pull failed: {e}")))?
+  {
+    buf.extend_from_slice(&chunk);
+    while let Some(pos) = buf.iter().position(|b| *b == b'\n') {
+      let line: Vec<u8> = buf.drain(..=pos).collect();
+      handle_line(&line)?;
+    }
+  }
+  handle_line(&buf)
+}
+
+async fn ensure_ollama_model(
+  app: &tauri::AppHandle,
+  client: &reqwest::Client,
+  base: &str,
+  model: &str,
+  pull: bool,
+) -> Result<ModelStatus, AppError> {
+  if !is_valid_model_name(model) {
+    return Err(AppError::Invalid("invalid model name".to_string()));
+  }
+  if ollama_has_model(client, base, model).await? {
+    return Ok(ModelStatus {
+      model: model.to_string(),
+      pulled: false,
+    });
+  }
+  if !pull {
+    return Err(AppError::NotFound(format!("model not installed: {model}")));
+  }
+  ollama_pull_model(app, client, base, model).await?;
+  if !ollama_has_model(client, base, model).await? {
+    return Err(AppError::NotFound(format!(
+      "model pull failed: {model} still missing"
+    )));
+  }
+  Ok(ModelStatus {
+    model: model.to_string(),
+    pulled: true,
+  })
+}
+
+/// Installed Ollama models, sorted by name.
+#[tauri::command]
+async fn list_ollama_models(
+  settings: tauri::State<'_, SettingsState>,
+  http: tauri::State<'_, HttpState>,
+) -> Result<Vec<OllamaModel>, AppError> {
+  let client = http.current().chat;
+  let base = settings.current().ollama_url();
+  let mut models: Vec<OllamaModel> = ollama_tags(&client, &base)
+    .await?
+    .into_iter()
+    .map(|t| OllamaModel { name: t.name, size: t.size, modified_at: t.modified_at })
+    .collect();
+  models.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(models)
+}
+
+/// Checks that Ollama has `model`, optionally pulling it with progress events.
+#[tauri::command]
+async fn ensure_model(
+  app: tauri::AppHandle,
+  settings: tauri::State<'_, SettingsState>,
+  http: tauri::State<'_, HttpState>,
+  model: String,
+  pull: Option<bool>,
+) -> Result<ModelStatus, AppError> {
+  let client = http.current().chat;
+  let base = settings.current().ollama_url();
+  ensure_ollama_model(&app, &client, &base, model.trim(), pull.unwrap_or(false)).await
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+struct ChatCapabilities {
+  streaming: bool,
+  multimodal: bool,
+  embeddings: bool,
+}
+
+/// `chat_capabilities` answers keyed by backend URL and model. Clearing bumps
+/// the generation, so a probe started under the old settings can't store
+/// its result afterwards.
+#[derive(Default)]
+struct ChatCaps(std::sync::Mutex<ChatCapsCache>);
+
+#[derive(Default)]
+struct ChatCapsCache {
+  generation: u64,
+  entries: std::collections::HashMap<(String, String), ChatCapabilities>,
+}
+
+impl ChatCaps {
+  fn clear(&self) {
+    let mut cache = self.0.lock().unwrap_or_else(|e| e.into_inner());
+    cache.generation += 1;
+    cache.entries.clear();
+  }
+
+  /// The cached answer for `key`, and the generation to store a fresh one under.
+  fn get(&self, key: &(String, String)) -> (Option<ChatCapabilities>, u64) {
+    let cache = self.0.lock().unwrap_or_else(|e| e.into_inner());
+    (cache.entries.get(key).copied(), cache.generation)
+  }
+
+  fn store(&self, key: (String, String), generation: u64, caps: ChatCapabilities) {
+    let mut cache = self.0.lock().unwrap_or_else(|e| e.into_inner());
+    if cache.generation == generation {
+      cache.entries.insert(key, caps);
+    }
+  }
+}
+
+const CAPABILITY_PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Ollama always streams; vision and embedding support depend on which
+/// models are installed, or on `model` alone when one is given.
+async fn probe_ollama_capabilities(
+  client: &reqwest::Client,
+  base: &str,
+  model: Option<&str>,
+) -> Result<ChatCapabilities, AppError> {
+  let resp = client
+    .get(format!("{base}/api/tags"))
+    .timeout(CAPABILITY_PROBE_TIMEOUT)
+    .send()
+    .await
+    .map_err(|e| ollama_request_error(base, e))?;
+  let status = resp.status();
+  if !status.is_success() {
+    return Err(AppError::Network(format!("ollama error ({status})")));
+  }
+  let tags: OllamaTagsResponse = resp
+    .json()
+    .await
+    .map_err(|e| AppError::Serialize(format!("ollama parse failed: {e}")))?;
+  let models: Vec<&OllamaTag> = tags
+    .models
+    .iter()
+    .filter(|t| model.map_or(true, |m| t.name == m || t.name == format!("{m}:latest")))
+    .collect();
+  let has_family = |wanted: &[&str]| {
+    models.iter().any(|t| {
+      let families = t.details.families.as_deref().unwrap_or_default();
+      families.iter().any(|f| wanted.contains(&f.as_str()))
+    })
+  };
+  Ok(ChatCapabilities {
+    streaming: true,
+    multimodal: has_family(&["clip", "mllama"]),
+    embeddings: has_family(&["bert", "nomic-bert"])
+      || models.iter().any(|t| t.name.contains("embed")),
+  })
+}
+
+/// The `/v1` root of an OpenAI-compatible server. Users paste both
+/// `http://host:port` and `http://host:port/v1`, so a trailing `/v1` is not
+/// added twice.
+fn openai_api_base(base_url: &str) -> Result<String, AppError> {
+  let base = Url::parse(base_url.trim())
+    .map_err(|e| AppError::Invalid(format!("invalid backend url: {e}")))?;
+  if base.scheme() != "http" && base.scheme() != "https" {
+    return Err(AppError::Invalid(
+      "unsupported backend url scheme".to_string(),
+    ));
+  }
+  let base = base.as_str().trim_end_matches('/');
+  let base = base.strip_suffix("/v1").unwrap_or(base);
+  Ok(format!("{base}/v1"))
+}
+
+/// Reads the proxy's model list; nothing is sent to a model, so probing costs
+/// no tokens. Streaming is part of the chat completions API every compatible
+/// server implements. Vision and embedding support are guessed from model
+/// ids, only `model`'s when one is given.
+async fn probe_openai_capabilities(
+  client: &reqwest::Client,
+  base_url: &str,
+  api_key: Option<&str>,
+  model: Option<&str>,
+) -> Result<ChatCapabilities, AppError> {
+  let base = openai_api_base(base_url)?;
+  let mut req = client
+    .get(format!("{base}/models"))
+    .timeout(CAPABILITY_PROBE_TIMEOUT);
+  if let Some(key) = api_key.map(str::trim).filter(|k| !k.is_empty()) {
+    req = req.bearer_auth(key);
+  }
+  let resp = req
+    .send()
+    .await
+    .map_err(|e| AppError::Network(format!("chat request failed: {e}")))?;
+  let status = resp.status();
+  if !status.is_success() {
+    return Err(AppError::Network(format!("chat error ({status})")));
+  }
+  let models: serde_json::Value = resp
+    .json()
+    .await
+    .map_err(|e| AppError::Serialize(format!("chat parse failed: {e}")))?;
+  let model = model.map(str::to_lowercase);
+  let ids: Vec<String> = models["data"]
+    .as_array()
+    .map(|data| data.iter().filter_map(|m| m["id"].as_str().map(str::to_lowercase)).collect())
+    .unwrap_or_default();
+  let any_id = |hints: &[&str]| {
+    ids
+      .iter()
+      .filter(|id| model.as_ref().map_or(true, |m| *id == m))
+      .any(|id| hints.iter().any(|h| id.contains(h)))
+  };
+  Ok(ChatCapabilities {
+    streaming: true,
+    multimodal: any_id(&["vision", "gpt-4o", "-vl", "llava"]),
+    embeddings: any_id(&["embed"]),
+  })
+}
+
+/// What the configured chat backend can do, or `model` on it when given.
+/// Answers are cached per backend URL and model until settings change.
+#[tauri::command]
+async fn chat_capabilities(
+  settings: tauri::State<'_, SettingsState>,
+  http: tauri::State<'_, HttpState>,
+  caps: tauri::State<'_, ChatCaps>,
+  model: Option<String>,
+) -> Result<ChatCapabilities, AppError> {
+  let model = model
+    .map(|m| m.trim().to_string())
+    .filter(|m| !m.is_empty());
+  let settings = settings.current();
+  let base = match &settings.chat_backend {
+    ChatBackend::Ollama => settings.ollama_url(),
+    ChatBackend::OpenAICompatible { base_url, .. } => base_url.trim().to_string(),
+  };
+  let key = (base, model.clone().unwrap_or_default());
+  let (cached, generation) = caps.get(&key);
+  if let Some(cached) = cached {
+    return Ok(cached);
+  }
+  let client = http.current().chat;
+  let probed = match &settings.chat_backend {
+    ChatBackend::Ollama => probe_ollama_capabilities(&client, &key.0, model.as_deref()).await?,
+    ChatBackend::OpenAICompatible { api_key, .. } => {
+      probe_openai_capabilities(&client, &key.0, api_key.as_deref(), model.as_deref()).await?
+    }
+  };
+  caps.store(key, generation, probed);
+  Ok(probed)
+}
+
+async fn ollama_chat_request(
+  client: &reqwest::Client,
+  base: &str,
+  req_body: &OllamaChatRequest,
+) -> Result<ChatResult, AppError> {
+  let resp = client
+    .post(format!("{base}/api/chat"))
+    .json(req_body)
+    .send()
+    .await
+    .map_err(|e| ollama_request_error(base, e))?;
+
+  let status = resp.status();
+  let body = resp
+    .text()
+    .await
+    .map_err(|e| AppError::Network(format!("ollama read failed: {e}")))?;
+  if !status.is_success() {
+    return Err(AppError::Network(format!(
+      "ollama error ({status}): {body}"
+    )));
+  }
+
+  let parsed: OllamaChatResponse = serde_json::from_str(&body)
+    .map_err(|e| AppError::Serialize(format!("ollama parse failed: {e}")))?;
+  Ok(ChatResult {
+    metrics: parsed.stats.metrics(),
+    message: parsed.message,
+  })
+}
+
+const CHAT_CANCELLED: &str = "chat cancelled";
+
+fn chat_cancelled() -> AppError {
+  AppError::Other(CHAT_CANCELLED.to_string())
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ChatChunkEvent {
+  #[serde(rename = "requestId")]
+  request_id: String,
+  content: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ChatDoneEvent {
+  #[serde(rename = "requestId")]
+  request_id: String,
+  #[serde(flatten)]
+  result: ChatResult,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ChatErrorEvent {
+  #[serde(rename = "requestId")]
+  request_id: String,
+  error: String,
+}
+
+/// Posts a streaming `/api/chat` request, emitting `ollama-chunk` to `window`
+/// for every piece of the reply, and returns the assembled message.
+async fn ollama_chat_stream_request(
+  window: &tauri::Window,
+  client: &reqwest::Client,
+  base: &str,
+  req_body: &OllamaChatRequest,
+  request_id: &str,
+  cancel: &tokio_util::sync::CancellationToken,
+) -> Result<ChatResult, AppError> {
+  // Each await is raced against `cancel`; the losing future is dropped, which
+  // closes the connection even while a read is still pending.
+  let request = client.post(format!("{base}/api/chat")).json(req_body).send();
+  let mut resp = cancel
+    .run_until_cancelled(request)
+    .await
+    .ok_or_else(chat_cancelled)?
+    .map_err(|e| ollama_request_error(base, e))?;
+  let status = resp.status();
+  if !status.is_success() {
+    let body = cancel
+      .run_until_cancelled(resp.text())
+      .await
+      .ok_or_else(chat_cancelled)?
+      .unwrap_or_default();
+    return Err(AppError::Network(format!(
+      "ollama error ({status}): {body}"
+    )));
+  }
+
+  let mut reply = OllamaMessage {
+    role: "assistant".to_string(),
+    content: String::new(),
+  };
+  let mut stats = None;
+  let mut buf: Vec<u8> = Vec::new();
+  let mut handle_line = |line: &[u8]| -> Result<(), AppError> {
+    if line.iter().all(|b| b.is_ascii_whitespace()) {
+      return Ok(());
+    }
+    let chunk: OllamaChatChunk = serde_json::from_slice(line)
+      .map_err(|e| AppError::Serialize(format!("ollama parse failed: {e}")))?;
+    if let Some(error) = chunk.error {
+      return Err(AppError::Network(format!("ollama error: {error}")));
+    }
+    if let Some(message) = chunk.message.filter(|m| !m.content.is_empty()) {
+      reply.content.push_str(&message.content);
+      let event = ChatChunkEvent {
+        request_id: request_id.to_string(),
+        content: message.content,
+      };
+      let _ = window.emit_to(window.label(), "ollama-chunk", event);
+    }
+    if chunk.done {
+      stats = Some(chunk.stats);
+    }
+    Ok(())
+  };
+  loop {
+    let chunk = cancel
+      .run_until_cancelled(resp.chunk())
+      .await
+      .ok_or_else(chat_cancelled)?;
+    let chunk = match chunk.map_err(|e| AppError::Network(format!("ollama read failed: {e}")))? {
+      Some(chunk) => chunk,
+      None => break,
+    };
+    buf.extend_from_slice(&chunk);
+    while let Some(pos) = buf.iter().position(|b| *b == b'\n') {
+      let line: Vec<u8> = buf.drain(..=pos).collect();
+      handle_line(&line)?;
+    }
+  }
+  handle_line(&buf)?;
+  let stats = stats.ok_or_else(|| AppError::Network("ollama stream ended early".to_string()))?;
+  Ok(ChatResult {
+    message: reply,
+    metrics: stats.metrics(),
+  })
+}
+
+async fn openai_chat_request(
+  client: &reqwest::Client,
+  base_url: &str,
+  api_key: Option<&str>,
+  req_body: &OllamaChatRequest,
+) -> Result<ChatResult, AppError> {
+  let endpoint = format!("{}/chat/completions", openai_api_base(base_url)?);
+
+  let mut req = client.post(endpoint).json(req_body);
+  if let Some(key) = api_key.map(str::trim).filter(|k| !k.is_empty()) {
+    req = req.bearer_auth(key);
+  }
+  let resp = req
+    .send()
+    .await
+    .map_err(|e| AppError::Network(format!("chat request failed: {e}")))?;
+
+  let status = resp.status();
+  let body = resp
+    .text()
+    .await
+    .map_err(|e| AppError::Network(format!("chat read failed: {e}")))?;
+  if !status.is_success() {
+    return Err(AppError::Network(format!("chat error ({status}): {body}")));
+  }
+
+  let parsed: OpenAIChatResponse = serde_json::from_str(&body)
+    .map_err(|e| AppError::Serialize(format!("chat parse failed: {e}")))?;
+  let message = parsed
+    .choices
+    .into_iter()
+    .next()
+    .map(|choice| choice.message)
+    .ok_or_else(|| AppError::Serialize("chat response had no choices".to_string()))?;
+  let usage = parsed.usage;
+  Ok(ChatResult {
+    message,
+    metrics: ChatMetrics {
+      eval_count: usage.as_ref().and_then(|u| u.completion_tokens),
+      prompt_eval_count: usage.as_ref().and_then(|u| u.prompt_tokens),
+      ..ChatMetrics::default()
+    },
+  })
+}
+
+/// Resolves the model (falling back to the board's default) and prepends the
+/// board's system prompt, if any. A caller that loaded the board config and
+/// already sent the prompt as a system message doesn't get it a second time.
+fn apply_board_chat_config(
+  paths: &AppPaths,
+  board_id: Option<&str>,
+  model: Option<String>,
+  messages: &mut Vec<OllamaMessage>,
+) -> Result<String, AppError> {
+  let config = match board_id {
+    Some(id) if !is_valid_board_id(id) => return Err(AppError::InvalidBoardId),
+    Some(id) => read_board_local_meta(&board_paths(&paths.root_dir, id)).chat,
+    None => BoardChatConfig::default(),
+  };
+  let model = model
+    .filter(|m| !m.trim().is_empty())
+    .or(config.model)
+    .ok_or_else(|| AppError::Invalid("model is required".to_string()))?;
+  if let Some(prompt) = config.system_prompt {
+    prepend_system_prompt(messages, prompt);
+  }
+  Ok(model)
+}
+
+fn prepend_system_prompt(messages: &mut Vec<OllamaMessage>, prompt: String) {
+  let already_sent = messages
+    .iter()
+    .any(|m| m.role == "system" && m.content.trim() == prompt.trim());
+  if !already_sent {
+    messages.insert(
+      0,
+      OllamaMessage {
+        role: "system".to_string(),
+        content: prompt,
+      },
+    );
+  }
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn ollama_chat(
+  app: tauri::AppHandle,
+  settings: tauri::State<'_, SettingsState>,
+  http: tauri::State<'_, HttpState>,
+  paths: tauri::State<'_, AppPaths>,
+  runs: tauri::State<'_, ChatRuns>,
+  model: Option<String>,
+  mut messages: Vec<OllamaMessage>,
+  ensure: Option<bool>,
+  board_id: Option<String>,
+) -> Result<ChatResult, AppError> {
+  let model = apply_board_chat_config(&paths, board_id.as_deref(), model, &mut messages)?;
+
+  let client = http.current().chat;
+  let settings = settings.current();
+  let ollama_url = settings.ollama_url();
+  let backend = settings.chat_backend;
+  if ensure.unwrap_or(false) && matches!(backend, ChatBackend::Ollama) {
+    ensure_ollama_model(&app, &client, &ollama_url, model.trim(), true).await?;
+  }
+
+  let req_body = OllamaChatRequest {
+    model,
+    messages,
+    stream: false,
+  };
+
+  let run_id = runs.correlation_id();
+  let started_at = now_millis();
+  let started = std::time::Instant::now();
+  log::info!(
+    "chat {run_id}: model={} messages={}",
+    req_body.model,
+    req_body.messages.len()
+  );
+
+  let result = match backend {
+    ChatBackend::Ollama => ollama_chat_request(&client, &ollama_url, &req_body).await,
+    ChatBackend::OpenAICompatible { base_url, api_key } => {
+      openai_chat_request(&client, &base_url, api_key.as_deref(), &req_body).await
+    }
+  };
+
+  let duration_ms = started.elapsed().as_millis() as u64;
+  let result = result.map(|mut chat| {
+    chat.metrics.total_duration_ms.get_or_insert(duration_ms);
+    chat
+  });
+  match &result {
+    Ok(_) => log::info!("chat {run_id}: ok in {duration_ms}ms"),
+    Err(e) => log::warn!("chat {run_id}: failed in {duration_ms}ms: {e}"),
+  }
+  runs.record(ChatRun {
+    id: run_id,
+    model: req_body.model,
+    message_count: req_body.messages.len(),
+    started_at,
+    duration_ms,
+    status: if result.is_ok() { "ok" } else { "error" }.to_string(),
+    error: result.as_ref().err().map(|e| e.to_string()),
+  });
+  result
+}
+
+/// Like `ollama_chat`, but streams: pieces of the reply arrive as
+/// `ollama-chunk` events tagged with `request_id`, then `ollama-done` carries
+/// the full message and metrics. Failures emit `ollama-error` as well as
+/// rejecting. Only the Ollama backend streams. `cancel_ollama` stops it.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn ollama_chat_stream(
+  window: tauri::Window,
+  settings: tauri::State<'_, SettingsState>,
+  http: tauri::State<'_, HttpState>,
+  paths: tauri::State<'_, AppPaths>,
+  runs: tauri::State<'_, ChatRuns>,
+  streams: tauri::State<'_, ChatStreams>,
+  model: Option<String>,
+  mut messages: Vec<OllamaMessage>,
+  request_id: String,
+  board_id: Option<String>,
+) -> Result<ChatResult, AppError> {
+  let emit_error = |error: &str| {
+    let event = ChatErrorEvent {
+      request_id: request_id.clone(),
+      error: error.to_string(),
+    };
+    let _ = window.emit_to(window.label(), "ollama-error", event);
+  };
+  let settings = settings.current();
+  if !matches!(settings.chat_backend, ChatBackend::Ollama) {
+    let error = "streaming needs the Ollama backend";
+    emit_error(error);
+    return Err(AppError::Invalid(error.to_string()));
+  }
+  let model = apply_board_chat_config(&paths, board_id.as_deref(), model, &mut messages)
+    .inspect_err(|e| emit_error(&e.to_string()))?;
+  let req_body = OllamaChatRequest {
+    model,
+    messages,
+    stream: true,
+  };
+
+  let run_id = runs.correlation_id();
+  let started_at = now_millis();
+  let started = std::time::Instant::now();
+  log::info!(
+    "chat {run_id} (stream {request_id}): model={} messages={}",
+    req_body.model,
+    req_body.messages.len()
+  );
+  let client = http.current().chat;
+  let cancel = streams
+    .start(&request_id)
+    .inspect_err(|e| emit_error(e.message()))?;
+  let base = settings.ollama_url();
+  let result =
+    ollama_chat_stream_request(&window, &client, &base, &req_body, &request_id, &cancel).await;
+  streams.finish(&request_id);
+
+  let duration_ms = started.elapsed().as_millis() as u64;
+  let result = result.map(|mut chat| {
+    chat.metrics.total_duration_ms.get_or_insert(duration_ms);
+    chat
+  });
+  match &result {
+    Ok(chat) => {
+      log::info!("chat {run_id}: ok in {duration_ms}ms");
+      let event = ChatDoneEvent {
+        request_id: request_id.clone(),
+        result: chat.clone(),
+      };
+      let _ = window.emit_to(window.label(), "ollama-done", event);
+    }
+    Err(e) => {
+      log::warn!("chat {run_id}: failed in {duration_ms}ms: {e}");
+      emit_error(e.message());
+    }
+  }
+  runs.record(ChatRun {
+    id: run_id,
+    model: req_body.model,
+    message_count: req_body.messages.len(),
+    started_at,
+    duration_ms,
+    status: match &result {
+      Ok(_) => "ok",
+      Err(e) if e.message() == CHAT_CANCELLED => "cancelled",
+      Err(_) => "error",
+    }
+    .to_string(),
+    error: result.as_ref().err().map(|e| e.to_string()),
+  });
+  result
+}
+
+/// Stops the streaming chat started with `request_id`. Returns false if no
+/// such chat is running.
+#[tauri::command]
+fn cancel_ollama(streams: tauri::State<'_, ChatStreams>, request_id: String) -> bool {
+  streams.cancel(&request_id)
+}
+
+const MAX_SYSTEM_PROMPT_CHARS: usize = 20_000;
+
+#[tauri::command]
+fn get_board_chat_config(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+) -> Result<BoardChatConfig, AppError> {
+  let (board_paths, _, _) = open_live_board(&paths, &board_id)?;
+  Ok(read_board_local_meta(&board_paths).chat)
+}
+
+const DEFAULT_FETCH_TIMEOUT_MS: u64 = 10_000;
+const MIN_FETCH_TIMEOUT_MS: u64 = 1_000;
+const MAX_FETCH_TIMEOUT_MS: u64 = 120_000;
+const MAX_USER_AGENT_CHARS: usize = 512;
+
+/// The shared fetch client, or a one-off client when the board overrides the
+/// user agent, timeout or redirect handling.
+fn board_fetch_client(
+  paths: &AppPaths,
+  board_id: &str,
+  settings: &AppSettings,
+  shared: reqwest::Client,
+) -> Result<reqwest::Client, AppError> {
+  let config = read_board_local_meta(&board_paths(&paths.root_dir, board_id)).fetch;
+  if config == BoardFetchConfig::default() {
+    return Ok(shared);
+  }
+  let user_agent = config.user_agent.as_ref().unwrap_or(&settings.user_agent);
+  let timeout = match config.timeout_ms {
+    Some(ms) => {
+      std::time::Duration::from_millis(ms.clamp(MIN_FETCH_TIMEOUT_MS, MAX_FETCH_TIMEOUT_MS))
+    }
+    None => settings.fetch_timeout(),
+  };
+  let mut builder = reqwest::Client::builder()
+    .user_agent(user_agent.value())
+    .timeout(timeout)
+    .dns_resolver(std::sync::Arc::new(PublicDnsResolver));
+  if config.follow_redirects == Some(false) {
+    builder = builder.redirect(reqwest::redirect::Policy::none());
+  } else {
+    builder = builder.redirect(safe_redirect_policy());
+  }
+  builder
+    .build()
+    .map_err(|e| AppError::Other(format!("http client failed: {e}")))
+}
+
+#[tauri::command]
+fn get_board_fetch_config(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+) -> Result<BoardFetchConfig, AppError> {
+  let (board_paths, _, _) = open_live_board(&paths, &board_id)?;
+  Ok(read_board_local_meta(&board_paths).fetch)
+}
+
+/// Replaces the board's link fetch overrides; `None` fields fall back to the
+/// global settings.
+#[tauri::command]
+fn set_board_fetch_config(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+  config: BoardFetchConfig,
+) -> Result<BoardFetchConfig, AppError> {
+  let (board_paths, _, _) = open_live_board(&paths, &board_id)?;
+  if let Some(UserAgentSetting::Custom(value)) = &config.user_agent {
+    if value.trim().is_empty() || value.chars().count() > MAX_USER_AGENT_CHARS {
+      return Err(AppError::Invalid(format!(
+        "custom user agent must be 1 to {MAX_USER_AGENT_CHARS} characters"
+      )));
+    }
+    if value.chars().any(|c| c.is_control()) {
+      return Err(AppError::Invalid("user agent contains control characters".to_string()));
+    }
+  }
+  if let Some(ms) = config.timeout_ms {
+    if !(MIN_FETCH_TIMEOUT_MS..=MAX_FETCH_TIMEOUT_MS).contains(&ms) {
+      return Err(AppError::Invalid(format!(
+        "timeout must be between {MIN_FETCH_TIMEOUT_MS} and {MAX_FETCH_TIMEOUT_MS} ms"
+      )));
+    }
+  }
+  let mut meta = read_board_local_meta(&board_paths);
+  meta.fetch = config;
+  write_board_local_meta(&board_paths, &meta)?;
+  Ok(meta.fetch)
+}
+
+/// Sets the board's default chat model and system prompt; blank values clear them.
+#[tauri::command]
+fn set_board_chat_config(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+  model: Option<String>,
+  system_prompt: Option<String>,
+) -> Result<BoardChatConfig, AppError> {
+  let (board_paths, _, _) = open_live_board(&paths, &board_id)?;
+  let model = model.map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+  if model.as_deref().is_some_and(|m| !is_valid_model_name(m)) {
+    return Err(AppError::Invalid("invalid model name".into()));
+  }
+  let system_prompt = system_prompt.filter(|p| !p.trim().is_empty());
+  if system_prompt.as_ref().is_some_and(|p| p.chars().count() > MAX_SYSTEM_PROMPT_CHARS) {
+    return Err(AppError::Invalid("system prompt is too long".into()));
+  }
+  let mut meta = read_board_local_meta(&board_paths);
+  meta.chat = BoardChatConfig {
+    model,
+    system_prompt,
+  };
+  write_board_local_meta(&board_paths, &meta)?;
+  Ok(meta.chat)
+}
+
+#[tauri::command]
+fn recent_chat_runs(runs: tauri::State<'_, ChatRuns>) -> Vec<ChatRun> {
+  let runs = runs.runs.lock().unwrap_or_else(|e| e.into_inner());
+  runs.iter().rev().cloned().collect()
+}
+
+#[tauri::command]
+fn get_assets_dir(
+  app: tauri::AppHandle,
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+) -> Result<String, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let index = read_index(&paths)?;
+  let name = index
+    .boards
+    .iter()
+    .find(|b| b.id == board_id)
+    .map(|b| b.name.clone())
+    .unwrap_or_else(|| "Untitled".to_string());
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  ensure_board_file(&board_paths, &board_id, &name)?;
+  let _ = ensure_board_index_contains(&paths, index, &board_id, &name)?;
+  // Boards created since launch aren't in the scope granted at startup.
+  allow_board_scope(&app, &board_paths)?;
+  Ok(board_paths.assets_dir.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn rename_asset(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+  old_path: String,
+  new_name: String,
+) -> Result<String, AppError> {
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, board_index) = open_live_board(&paths, &board_id)?;
+
+  let pooled = pool_file(&paths, &old_path).is_some();
+  let old_file = card_file(&paths, &board_paths, &old_path)
+    .ok_or_else(|| AppError::Invalid("invalid asset path".to_string()))?;
+  if !old_file.is_file() {
+    return Err(AppError::NotFound("asset not found".into()));
+  }
+  let stem = sanitize_asset_stem(&new_name)
+    .ok_or_else(|| AppError::Invalid("invalid asset name".to_string()))?;
+  let new_filename = match old_file.extension().and_then(|e| e.to_str()) {
+    Some(ext) => format!("{stem}.{ext}"),
+    None => stem,
+  };
+  let new_file = board_paths.assets_dir.join(&new_filename);
+  if new_file == old_file {
+    return Ok(format!("assets/{new_filename}"));
+  }
+  if new_file.exists() {
+    return Err(AppError::Conflict(
+      "an asset with that name already exists".into(),
+    ));
+  }
+
+  if pooled {
+    // Other boards may share the pooled file, so this board gets a named copy.
+    std::fs::copy(&old_file, &new_file)
+      .map_err(|e| AppError::Io(format!("rename asset failed: {e}")))?;
+  } else {
+    std::fs::rename(&old_file, &new_file)
+      .map_err(|e| AppError::Io(format!("rename asset failed: {e}")))?;
+  }
+  let new_ref = format!("assets/{new_filename}");
+  for card in board.cards.iter_mut() {
+    for field in [&mut card.src, &mut card.image, &mut card.favicon] {
+      let refers = field
+        .as_deref()
+        .and_then(|value| card_file(&paths, &board_paths, value))
+        .is_some_and(|file| file == old_file);
+      if refers {
+        *field = Some(new_ref.clone());
+      }
+    }
+  }
+  if let Err(e) = commit_board(&paths, &board_paths, board_index, &board) {
+    if pooled {
+      let _ = std::fs::remove_file(&new_file);
+    } else {
+      let _ = std::fs::rename(&new_file, &old_file);
+    }
+    return Err(e);
+  }
+  Ok(new_ref)
+}
+
+#[tauri::command]
+fn get_asset_url(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+  asset_path: String,
+) -> Result<String, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  if asset_path.trim().starts_with(POOL_PREFIX) {
+    let file = pool_file(&paths, &asset_path)
+      .ok_or_else(|| AppError::Invalid("invalid asset path".to_string()))?;
+    if !file.is_file() {
+      return Err(AppError::NotFound("asset not found".into()));
+    }
+    return Ok(asset_protocol_url(&file));
+  }
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  let file = canonical_asset_path(&board_paths, &asset_path)?;
+  Ok(asset_protocol_url(&file))
+}
+
+/// Rewrites one card image reference for `storage`, copying the file across.
+/// Returns the new reference, or `None` when it is already in place or the
+/// file is missing.
+fn migrate_asset_ref(
+  paths: &AppPaths,
+  board_paths: &BoardPaths,
+  value: &str,
+  storage: AssetStorage,
+) -> Result<Option<String>, AppError> {
+  match storage {
+    AssetStorage::Pool => {
+      if pool_ref_name(value).is_some() {
+        return Ok(None);
+      }
+      let file = match card_asset_file(board_paths, value) {
+        Some(file) if file.is_file() => file,
+        _ => return Ok(None),
+      };
+      let bytes =
+        std::fs::read(&file).map_err(|e| AppError::Io(format!("read asset failed: {e}")))?;
+      let ext = file.extension().and_then(|e| e.to_str()).unwrap_or("");
+      save_pool_bytes(paths, &bytes, ext).map(Some)
+    }
+    AssetStorage::Board => {
+      let (name, file) = match (pool_ref_name(value), pool_file(paths, value)) {
+        (Some(name), Some(file)) if file.is_file() => (name.to_string(), file),
+        _ => return Ok(None),
+      };
+      std::fs::create_dir_all(&board_paths.assets_dir)
+        .map_err(|e| AppError::Io(format!("create assets dir failed: {e}")))?;
+      copy_asset_into(&file, &board_paths.assets_dir)?;
+      Ok(Some(format!("assets/{name}")))
+    }
+  }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct AssetMigration {
+  boards: usize,
+  moved: usize,
+}
+
+/// Switches the asset storage mode and moves every live board's image
+/// references to match. Files are copied, not moved; `cleanup_assets` removes
+/// board copies that are no longer referenced.
+#[tauri::command]
+fn migrate_asset_storage(
+  paths: tauri::State<'_, AppPaths>,
+  settings: tauri::State<'_, SettingsState>,
+  locks: tauri::State<'_, BoardLocks>,
+  storage: AssetStorage,
+) -> Result<AssetMigration, AppError> {
+  let index = read_index(&paths)?;
+  let mut summary = AssetMigration { boards: 0, moved: 0 };
+  for meta in index.boards.iter().filter(|b| b.deleted_at.is_none()) {
+    let lock = locks.for_board(&meta.id);
+    let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+    let board_paths = board_paths(&paths.root_dir, &meta.id);
+    let mut board = match read_board(&board_paths) {
+      Ok(board) => board,
+      Err(_) => continue,
+    };
+    let mut moved = 0;
+    for card in board.cards.iter_mut() {
+      for field in [&mut card.src, &mut card.image, &mut card.favicon] {
+        let next = match field.as_deref() {
+          Some(value) => migrate_asset_ref(&paths, &board_paths, value, storage)?,
+          None => None,
+        };
+        if next.is_some() {
+          *field = next;
+          moved += 1;
+        }
+      }
+    }
+    if moved > 0 {
+      write_board_atomic(&board_paths, &board)?;
+      summary.boards += 1;
+      summary.moved += moved;
+    }
+  }
+
+  let mut current = settings.0.lock().unwrap_or_else(|e| e.into_inner());
+  let mut next = current.clone();
+  next.asset_storage = storage;
+  write_settings_atomic(&paths, &next)?;
+  *current = next;
+  Ok(summary)
+}
+
+#[tauri::command]
+fn get_asset_pool_dir(paths: tauri::State<'_, AppPaths>) -> String {
+  paths.pool_dir.to_string_lossy().to_string()
+}
+
+/// Returns a cached thumbnail for an image asset, generating it if it is
+/// missing or older than the source. Small animations are returned as the
+/// original file so they keep playing; larger ones get a first-frame preview.
+#[tauri::command]
+async fn get_thumbnail(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+  asset_path: String,
+  size: Option<u32>,
+) -> Result<Thumbnail, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let size = size.unwrap_or(DEFAULT_THUMBNAIL_SIZE).clamp(16, MAX_THUMBNAIL_SIZE);
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  let source = match pool_file(&paths, &asset_path) {
+    Some(file) if file.is_file() => file,
+    Some(_) => return Err(AppError::NotFound("asset not found".into())),
+    None => canonical_asset_path(&board_paths, &asset_path)?,
+  };
+
+  run_blocking(move || ensure_thumbnail(&board_paths, &source, size).map(|(thumb, _)| thumb)).await
+}
+
+/// Returns the thumbnail for `source`, regenerating it when missing or stale.
+/// The flag is true when a new thumbnail was written.
+fn ensure_thumbnail(
+  board_paths: &BoardPaths,
+  source: &std::path::Path,
+  size: u32,
+) -> Result<(Thumbnail, bool), AppError> {
+  let animated = is_animated_image(source);
+  let source_meta =
+    std::fs::metadata(source).map_err(|e| AppError::Io(format!("read asset failed: {e}")))?;
+  if animated && source_meta.len() <= MAX_ANIMATED_PASSTHROUGH_BYTES {
+    let (width, height) = image::image_dimensions(source)
+      .map_err(|e| AppError::Io(format!("read image failed: {e}")))?;
+    let thumb = Thumbnail {
+      url: asset_protocol_url(source),
+      width,
+      height,
+      animated,
+      original: true,
+    };
+    return Ok((thumb, false));
+  }
+
+  let thumb = thumbnail_file(board_paths, source, size)
+    .ok_or_else(|| AppError::Invalid("invalid asset path".to_string()))?;
+  let fresh = match (std::fs::metadata(&thumb), source_meta.modified()) {
+    (Ok(meta), Ok(source_mtime)) => meta.modified().is_ok_and(|t| t >= source_mtime),
+    _ => false,
+  };
+  let (width, height) = if fresh {
+    image::image_dimensions(&thumb)
+      .map_err(|e| AppError::Io(format!("read thumbnail failed: {e}")))?
+  } else {
+    write_thumbnail(source, &thumb, size)?
+  };
+  let thumbnail = Thumbnail {
+    url: asset_protocol_url(&thumb),
+    width,
+    height,
+    animated,
+    original: false,
+  };
+  Ok((thumbnail, !fresh))
+}
+
+const THUMBNAIL_WORKERS: usize = 4;
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ThumbnailSummary {
+  total: usize,
+  generated: usize,
+  current: usize,
+  failed: usize,
+}
+
+/// Builds any missing or stale thumbnails for the board's image and link
+/// cards, pooled or not, on a few worker threads, emitting
+/// `thumbnails-progress` as it goes.
+#[tauri::command]
+async fn generate_all_thumbnails(
+  app: tauri::AppHandle,
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+  max_dim: Option<u32>,
+) -> Result<ThumbnailSummary, AppError> {
+  let size = max_dim.unwrap_or(DEFAULT_THUMBNAIL_SIZE).clamp(16, MAX_THUMBNAIL_SIZE);
+  let paths = paths.inner().clone();
+  // Wait for one slot, then take whatever else is free without waiting; each
+  // worker thread holds its slot until the job ends.
+  let limiter = app.state::<WorkLimiter>().inner().clone();
+  let mut permits = vec![limiter.acquire().await?];
+  permits.extend(std::iter::from_fn(|| limiter.try_acquire()).take(THUMBNAIL_WORKERS - 1));
+  run_blocking(move || {
+    use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+    let (board_paths, board, _) = open_live_board(&paths, &board_id)?;
+    let mut seen = std::collections::HashSet::new();
+    let sources: Vec<std::path::PathBuf> = board
+      .cards
+      .iter()
+      .filter_map(|c| match c.r#type.as_str() {
+        "image" => c.src.as_deref(),
+        "link" => c.image.as_deref(),
+        _ => None,
+      })
+      .filter_map(|value| card_file(&paths, &board_paths, value))
+      .filter(|file| file.is_file() && seen.insert(file.clone()))
+      .collect();
+    let total = sources.len();
+    let (next, done) = (AtomicUsize::new(0), AtomicUsize::new(0));
+    let (generated, failed) = (AtomicUsize::new(0), AtomicUsize::new(0));
+    std::thread::scope(|scope| {
+      for permit in permits.into_iter().take(total) {
+        scope.spawn(|| {
+          let _permit = permit;
+          while let Some(source) = sources.get(next.fetch_add(1, Relaxed)) {
+            match ensure_thumbnail(&board_paths, source, size) {
+              Ok((_, true)) => {
+                generated.fetch_add(1, Relaxed);
+              }
+              Ok((_, false)) => {}
+              Err(e) => {
+                log::warn!("thumbnail {}: {e}", source.display());
+                failed.fetch_add(1, Relaxed);
+              }
+            }
+            let progress = ScanProgress {
+              board_id: board_id.clone(),
+              done: done.fetch_add(1, Relaxed) + 1,
+              total,
+            };
+            emit_to_board(&app, &board_id, "thumbnails-progress", progress);
+          }
+        });
+      }
+    });
+    let (generated, failed) = (generated.into_inner(), failed.into_inner());
+    Ok(ThumbnailSummary {
+      total,
+      generated,
+      current: total - generated - failed,
+      failed,
+    })
+  })
+  .await
+}
+
+/// Returns the asset URL of `<board>/preview.png`, re-rendering it when the
+/// board has been saved since it was written or a different size is asked for.
+#[tauri::command]
+async fn get_board_preview(
+  app: tauri::AppHandle,
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+  size: Option<u32>,
+) -> Result<String, AppError> {
+  let size = size.unwrap_or(DEFAULT_PREVIEW_SIZE).clamp(32, MAX_PREVIEW_SIZE);
+  let (board_paths, board, index) = open_live_board(&paths, &board_id)?;
+  allow_board_scope(&app, &board_paths)?;
+  let preview = board_paths.dir.join("preview.png");
+  let updated_at = index
+    .boards
+    .iter()
+    .find(|b| b.id == board_id)
+    .map_or(0, |b| b.updated_at);
+  let fresh = file_modified_millis(&preview).is_some_and(|t| t >= updated_at)
+    && image::image_dimensions(&preview).is_ok_and(|(w, h)| w == size && h == size);
+  if fresh {
+    return Ok(asset_protocol_url(&preview));
+  }
+
+  let images: Vec<std::path::PathBuf> = board
+    .cards
+    .iter()
+    .filter_map(|c| match c.r#type.as_str() {
+      "image" => c.src.as_deref(),
+      "link" => c.image.as_deref(),
+      _ => None,
+    })
+    .filter_map(|value| card_file(&paths, &board_paths, value))
+    .filter(|file| file.is_file() && is_image_asset(file))
+    .collect();
+
+  run_blocking(move || {
+    let canvas = render_board_preview(&images, &board, size);
+    let tmp = board_paths.dir.join("preview.png.tmp");
+    canvas
+      .save_with_format(&tmp, image::ImageFormat::Png)
+      .map_err(|e| AppError::Io(format!("write preview failed: {e}")))?;
+    replace_file(&tmp, &preview)
+      .map_err(|e| AppError::Io(format!("rename preview failed: {e}")))?;
+    Ok(asset_protocol_url(&preview))
+  })
+  .await
+}
+
+#[tauri::command]
+fn resolve_asset_path(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+  asset_path: String,
+) -> Result<String, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  let file = canonical_asset_path(&board_paths, &asset_path)?;
+  Ok(file.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn load_chat(
+  paths: tauri::State<'_, AppPaths>,
+  settings: tauri::State<'_, SettingsState>,
+  chat_key: tauri::State<'_, ChatKey>,
+  board_id: String,
+) -> Result<ChatStore, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let index = read_index(&paths)?;
+  let name = index
+    .boards
+    .iter()
+    .find(|b| b.id == board_id)
+    .map(|b| b.name.as_str())
+    .unwrap_or("Untitled");
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  ensure_board_file(&board_paths, &board_id, name)?;
+
+  let passphrase = chat_key.current();
+  let mut chat = read_chat(&board_paths, passphrase.as_deref())?;
+  if normalize_chat(&mut chat) && board_paths.dir.join("chat.json").exists() {
+    log::warn!("load_chat {board_id}: reset summary bookkeeping");
+    if let Err(e) = write_chat(&board_paths, &chat, &settings.current(), passphrase.as_deref()) {
+      log::warn!("load_chat {board_id}: could not rewrite chat: {e}");
+    }
+  }
+  Ok(chat)
+}
+
+/// Explicitly checks a board's chat summary bookkeeping, rewriting chat.json
+/// if it was inconsistent. Returns true when a repair was made.
+#[tauri::command]
+fn repair_chat(
+  paths: tauri::State<'_, AppPaths>,
+  settings: tauri::State<'_, SettingsState>,
+  chat_key: tauri::State<'_, ChatKey>,
+  board_id: String,
+) -> Result<bool, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  if !board_paths.dir.join("chat.json").exists() {
+    return Ok(false);
+  }
+  let passphrase = chat_key.current();
+  let mut chat = read_chat(&board_paths, passphrase.as_deref())?;
+  if !normalize_chat(&mut chat) {
+    return Ok(false);
+  }
+  write_chat(&board_paths, &chat, &settings.current(), passphrase.as_deref())?;
+  Ok(true)
+}
+
+const MAX_CHAT_SEARCH_HITS: usize = 100;
+
+#[tauri::command]
+fn search_chats(
+  paths: tauri::State<'_, AppPaths>,
+  chat_key: tauri::State<'_, ChatKey>,
+  query: String,
+) -> Result<Vec<ChatSearchHit>, AppError> {
+  let query = query.trim();
+  if query.is_empty() {
+    return Ok(vec![]);
+  }
+  let index = read_index(&paths)?;
+  let passphrase = chat_key.current();
+  let mut hits = Vec::new();
+  for meta in index.boards.iter().filter(|b| b.deleted_at.is_none()) {
+    let board_paths = board_paths(&paths.root_dir, &meta.id);
+    let chat = match read_chat(&board_paths, passphrase.as_deref()) {
+      Ok(chat) => chat,
+      Err(e) => {
+        log::warn!("search_chats: skipping {}: {e}", meta.id);
+        continue;
+      }
+    };
+    for entry in chat.messages {
+      if let Some(snippet) = match_snippet(&entry.content, query, 60) {
+        hits.push(ChatSearchHit {
+          board_id: meta.id.clone(),
+          board_name: meta.name.clone(),
+          message_id: entry.id,
+          role: entry.role,
+          created_at: entry.created_at,
+          snippet,
+        });
+        if hits.len() >= MAX_CHAT_SEARCH_HITS {
+          return Ok(hits);
+        }
+      }
+    }
+  }
+  Ok(hits)
+}
+
+#[tauri::command]
+fn save_chat(
+  paths: tauri::State<'_, AppPaths>,
+  settings: tauri::State<'_, SettingsState>,
+  chat_key: tauri::State<'_, ChatKey>,
+  saves: tauri::State<'_, SaveCounters>,
+  board_id: String,
+  chat: ChatStore,
+) -> Result<(), AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let index = read_index(&paths)?;
+  let name = index
+    .boards
+    .iter()
+    .find(|b| b.id == board_id)
+    .map(|b| b.name.as_str())
+    .unwrap_or("Untitled");
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  ensure_board_file(&board_paths, &board_id, name)?;
+
+  let started = std::time::Instant::now();
+  let size = write_chat(&board_paths, &chat, &settings.current(), chat_key.current().as_deref())?;
+  let elapsed = started.elapsed();
+  saves.chats.record(size, elapsed);
+  log::debug!("chat save {board_id}: {size} bytes in {elapsed:?}");
+  Ok(())
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ExportedChatMessage {
+  id: String,
+  role: String,
+  content: String,
+  #[serde(rename = "createdAt")]
+  created_at: String,
+  #[serde(rename = "sessionId")]
+  session_id: Option<String>,
+  model: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ExportedChat {
+  #[serde(rename = "boardId")]
+  board_id: String,
+  #[serde(rename = "boardName")]
+  board_name: String,
+  #[serde(rename = "exportedAt")]
+  exported_at: String,
+  /// Set when the export is limited to one session.
+  #[serde(rename = "sessionId")]
+  session_id: Option<String>,
+  /// Rolling summary of the whole chat, not just the exported session.
+  summary: Option<String>,
+  /// Distinct models that answered, in first-use order.
+  models: Vec<String>,
+  messages: Vec<ExportedChatMessage>,
+}
+
+fn iso_timestamp(millis: i64) -> String {
+  chrono::TimeZone::timestamp_millis_opt(&Utc, millis)
+    .single()
+    .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+    .unwrap_or_default()
+}
+
+/// The board's chat (or one session of it) as standalone JSON with ISO-8601
+/// timestamps, for analysis outside the app.
+#[tauri::command]
+fn export_chat_json(
+  paths: tauri::State<'_, AppPaths>,
+  chat_key: tauri::State<'_, ChatKey>,
+  board_id: String,
+  session_id: Option<String>,
+) -> Result<String, AppError> {
+  let (board_paths, board, _) = open_live_board(&paths, &board_id)?;
+  let chat = read_chat(&board_paths, chat_key.current().as_deref())?;
+  let mut models: Vec<String> = Vec::new();
+  let messages: Vec<ExportedChatMessage> = chat
+    .messages
+    .into_iter()
+    .filter(|m| session_id.is_none() || m.session_id == session_id)
+    .map(|m| {
+      if let Some(model) = m.model.as_ref().filter(|model| !models.contains(model)) {
+        models.push(model.clone());
+      }
+      ExportedChatMessage {
+        id: m.id,
+        role: m.role,
+        content: m.content,
+        created_at: iso_timestamp(m.created_at),
+        session_id: m.session_id,
+        model: m.model,
+      }
+    })
+    .collect();
+  if session_id.is_some() && messages.is_empty() {
+    return Err(AppError::NotFound("no messages in that session".to_string()));
+  }
+  let export = ExportedChat {
+    board_id,
+    board_name: board.name,
+    exported_at: iso_timestamp(now_millis()),
+    session_id,
+    summary: chat.summary,
+    models,
+    messages,
+  };
+  serde_json::to_string_pretty(&export)
+    .map_err(|e| AppError::Serialize(format!("serialize chat failed: {e}")))
+}
+
+/// Sets (or with `None`/blank, forgets) the passphrase used to encrypt and
+/// decrypt chat files for this session.
+#[tauri::command]
+fn set_chat_passphrase(chat_key: tauri::State<'_, ChatKey>, passphrase: Option<String>) -> bool {
+  let passphrase = passphrase.filter(|p| !p.is_empty());
+  let set = passphrase.is_some();
+  *chat_key.0.lock().unwrap_or_else(|e| e.into_inner()) = passphrase;
+  set
+}
+
+#[tauri::command]
+fn save_metrics(saves: tauri::State<'_, SaveCounters>) -> SaveMetrics {
+  SaveMetrics {
+    boards: saves.boards.snapshot(),
+    chats: saves.chats.snapshot(),
+  }
+}
+
+/// Images larger than this on either side get a thumbnail as they are saved.
+const SAVE_THUMBNAIL_THRESHOLD: u32 = 800;
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct SavedImage {
+  src: String,
+  /// `thumbs/<asset>.<size>.jpg` under the board, for large images only. The
+  /// card keeps pointing at `src`; this just warms the cache that share
+  /// exports and `get_thumbnail` read.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  thumb: Option<String>,
+}
+
+/// Pre-renders the thumbnail for a large board asset so the first render
+/// doesn't decode the full image. Undecodable or animated images are skipped.
+fn thumbnail_on_save(board_paths: &BoardPaths, src: &str) -> Option<String> {
+  let file = card_asset_file(board_paths, src)?;
+  let (width, height) = image::image_dimensions(&file).ok()?;
+  if width.max(height) <= SAVE_THUMBNAIL_THRESHOLD {
+    return None;
+  }
+  let (thumb, _) = ensure_thumbnail(board_paths, &file, DEFAULT_THUMBNAIL_SIZE)
+    .inspect_err(|e| log::warn!("thumbnail for {src} failed: {e}"))
+    .ok()?;
+  if thumb.original {
+    return None;
+  }
+  let thumb_file = thumbnail_file(board_paths, &file, DEFAULT_THUMBNAIL_SIZE)?;
+  Some(format!("thumbs/{}", thumb_file.file_name()?.to_str()?))
+}
+
+#[tauri::command]
+async fn save_image(
+  paths: tauri::State<'_, AppPaths>,
+  settings: tauri::State<'_, SettingsState>,
+  board_id: String,
+  filename: String,
+  bytes_base64: String,
+) -> Result<SavedImage, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let paths = paths.inner().clone();
+  let pooled = settings.current().asset_storage == AssetStorage::Pool;
+  run_blocking(move || {
+    let index = read_index(&paths)?;
+    let name = index
+      .boards
+      .iter()
+      .find(|b| b.id == board_id)
+      .map(|b| b.name.as_str())
+      .unwrap_or("Untitled");
+    let board_paths = board_paths(&paths.root_dir, &board_id);
+    ensure_board_file(&board_paths, &board_id, name)?;
+
+    let decoded = base64::engine::general_purpose::STANDARD
+      .decode(bytes_base64.as_bytes())
+      .map_err(|e| AppError::Serialize(format!("base64 decode failed: {e}")))?;
+
+    let ext = std::path::Path::new(&filename)
+      .extension()
+      .and_then(|e| e.to_str())
+      .unwrap_or("");
+    if pooled {
+      let src = save_pool_bytes(&paths, &decoded, ext)?;
+      return Ok(SavedImage { src, thumb: None });
+    }
+    let src = save_board_bytes(&board_paths, AssetRole::Image, &decoded, ext)?;
+    let thumb = thumbnail_on_save(&board_paths, &src);
+    Ok(SavedImage { src, thumb })
+  })
+  .await
+}
+
+const MAX_UPLOAD_BYTES: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct UploadedImage {
+  path: String,
+  width: u32,
+  height: u32,
+}
+
+/// Starts a chunked image save for pastes too large for one IPC message.
+/// Chunks land in a temp file in the board's `uploads/` dir until finished.
+#[tauri::command]
+fn begin_image_upload(
+  paths: tauri::State<'_, AppPaths>,
+  uploads: tauri::State<'_, ImageUploads>,
+  board_id: String,
+  filename: String,
+) -> Result<String, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let index = read_index(&paths)?;
+  let name = index
+    .boards
+    .iter()
+    .find(|b| b.id == board_id)
+    .map(|b| b.name.as_str())
+    .unwrap_or("Untitled");
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  ensure_board_file(&board_paths, &board_id, name)?;
+
+  let mut filename = flatten_asset_name(filename.trim());
+  if filename.is_empty() {
+    filename = format!("image-{}", now_millis());
+  }
+  let seq = uploads.next_id.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+  let upload_id = format!("upload-{}-{seq}", now_millis());
+  std::fs::create_dir_all(&board_paths.uploads_dir)
+    .map_err(|e| AppError::Io(format!("create uploads dir failed: {e}")))?;
+  let tmp = board_paths.uploads_dir.join(format!("{upload_id}.tmp"));
+  // Created under the lock so `prune_abandoned_uploads` can't take it for a
+  // leftover before it is registered.
+  let mut pending = uploads.pending.lock().unwrap_or_else(|e| e.into_inner());
+  std::fs::File::create(&tmp).map_err(|e| AppError::Io(format!("create upload failed: {e}")))?;
+  pending.insert(
+    upload_id.clone(),
+    PendingUpload {
+      board_id,
+      filename,
+      tmp,
+      written: 0,
+    },
+  );
+  Ok(upload_id)
+}
+
+#[tauri::command]
+fn append_image_chunk(
+  uploads: tauri::State<'_, ImageUploads>,
+  upload_id: String,
+  chunk_base64: String,
+) -> Result<u64, AppError> {
+  use std::io::Write;
+  let decoded = base64::engine::general_purpose::STANDARD
+    .decode(chunk_base64.as_bytes())
+    .map_err(|e| AppError::Serialize(format!("base64 decode failed: {e}")))?;
+  let mut pending = uploads.pending.lock().unwrap_or_else(|e| e.into_inner());
+  let upload = pending
+    .get_mut(&upload_id)
+    .ok_or_else(|| AppError::NotFound("upload not found".to_string()))?;
+  if upload.written + decoded.len() as u64 > MAX_UPLOAD_BYTES {
+    let upload = pending.remove(&upload_id);
+    if let Some(upload) = upload {
+      let _ = std::fs::remove_file(&upload.tmp);
+    }
+    return Err(AppError::QuotaExceeded("image too large".into()));
+  }
+  let mut file = std::fs::OpenOptions::new()
+    .append(true)
+    .open(&upload.tmp)
+    .map_err(|e| AppError::Io(format!("open upload failed: {e}")))?;
+  file
+    .write_all(&decoded)
+    .map_err(|e| AppError::Io(format!("write upload failed: {e}")))?;
+  upload.written += decoded.len() as u64;
+  Ok(upload.written)
+}
+
+/// Checks the uploaded bytes are a decodable image and moves them into place
+/// (board assets or the pool, per settings).
+#[tauri::command]
+fn finish_image_upload(
+  paths: tauri::State<'_, AppPaths>,
+  settings: tauri::State<'_, SettingsState>,
+  uploads: tauri::State<'_, ImageUploads>,
+  upload_id: String,
+) -> Result<UploadedImage, AppError> {
+  let upload = uploads
+    .pending
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+    .remove(&upload_id)
+    .ok_or_else(|| AppError::NotFound("upload not found".to_string()))?;
+  let result = place_uploaded_image(&paths, settings.current().asset_storage, &upload);
+  if result.is_err() {
+    let _ = std::fs::remove_file(&upload.tmp);
+  }
+  result
+}
+
+fn place_uploaded_image(
+  paths: &AppPaths,
+  storage: AssetStorage,
+  upload: &PendingUpload,
+) -> Result<UploadedImage, AppError> {
+  let reader = image::ImageReader::open(&upload.tmp)
+    .and_then(|r| r.with_guessed_format())
+    .map_err(|e| AppError::Io(format!("read upload failed: {e}")))?;
+  if reader.format().is_none() {
+    return Err(AppError::Invalid(
+      "upload is not a supported image".to_string(),
+    ));
+  }
+  let (width, height) = reader
+    .into_dimensions()
+    .map_err(|e| AppError::Invalid(format!("upload is not a valid image: {e}")))?;
+
+  let ext = std::path::Path::new(&upload.filename)
+    .extension()
+    .and_then(|e| e.to_str())
+    .unwrap_or("");
+  let path = if storage == AssetStorage::Pool {
+    save_pool_file(paths, &upload.tmp, ext)?
+  } else {
+    save_board_file(
+      &board_paths(&paths.root_dir, &upload.board_id),
+      AssetRole::Image,
+      &upload.tmp,
+      ext,
+    )?
+  };
+  Ok(UploadedImage {
+    path,
+    width,
+    height,
+  })
+}
+
+/// Copies an image from a local path (e.g. an OS drag-and-drop) into the board,
+/// so the frontend doesn't have to read and base64-encode it.
+#[tauri::command]
+fn import_image_file(
+  paths: tauri::State<'_, AppPaths>,
+  settings: tauri::State<'_, SettingsState>,
+  board_id: String,
+  source_path: String,
+) -> Result<UploadedImage, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let source = std::path::PathBuf::from(source_path.trim());
+  let source_meta = std::fs::metadata(&source)
+    .map_err(|_| AppError::NotFound("source file not found".to_string()))?;
+  if !source_meta.is_file() {
+    return Err(AppError::Invalid("source is not a file".into()));
+  }
+  if source_meta.len() > MAX_UPLOAD_BYTES {
+    return Err(AppError::QuotaExceeded("image too large".into()));
+  }
+  let index = read_index(&paths)?;
+  let name = index
+    .boards
+    .iter()
+    .find(|b| b.id == board_id)
+    .map(|b| b.name.as_str())
+    .unwrap_or("Untitled");
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  ensure_board_file(&board_paths, &board_id, name)?;
+
+  let mut filename = source
+    .file_name()
+    .map(|n| flatten_asset_name(n.to_string_lossy().trim()))
+    .unwrap_or_default();
+  if filename.is_empty() {
+    filename = format!("image-{}", now_millis());
+  }
+  let tmp = board_paths
+    .assets_dir
+    .join(format!(".import-{}.tmp", now_millis()));
+  let written =
+    std::fs::copy(&source, &tmp).map_err(|e| AppError::Io(format!("copy image failed: {e}")))?;
+  let upload = PendingUpload {
+    board_id,
+    filename,
+    tmp,
+    written,
+  };
+  let result = place_uploaded_image(&paths, settings.current().asset_storage, &upload);
+  if result.is_err() {
+    let _ = std::fs::remove_file(&upload.tmp);
+  }
+  result
+}
+
+#[tauri::command]
+fn abort_image_upload(uploads: tauri::State<'_, ImageUploads>, upload_id: String) -> bool {
+  let upload = uploads.pending.lock().unwrap_or_else(|e| e.into_inner()).remove(&upload_id);
+  match upload {
+    Some(upload) => {
+      let _ = std::fs::remove_file(&upload.tmp);
+      true
+    }
+    None => false,
+  }
+}
+
+fn load_or_init_board(paths: &AppPaths, board_id: &str) -> Result<Board, AppError> {
+  if !is_valid_board_id(board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let index = read_index(paths)?;
+  let name = index
+    .boards
+    .iter()
+    .find(|b| b.id == board_id)
+    .map(|b| b.name.as_str())
+    .unwrap_or("Untitled");
+  let board_paths = board_paths(&paths.root_dir, board_id);
+  ensure_board_file(&board_paths, board_id, name)?;
+
+  let text = read_board_text(&board_paths.file)?;
+
+  match parse_board(&text) {
+    Ok(mut board) => {
+      let fixed = fix_board_geometry(&mut board);
+      if fixed > 0 {
+        log::warn!("load board {board_id}: repaired geometry on {fixed} card(s)");
+      }
+      if board.id != board_id || fixed > 0 {
+        board.id = board_id.to_string();
+        write_board_atomic(&board_paths, &board)?;
+      }
+      Ok(board)
+    }
+    // Never replace a newer board with an empty one.
+    Err(e @ AppError::Conflict(_)) => Err(e),
+    Err(_) => {
+      let board = empty_board(board_id, name);
+      write_board_atomic(&board_paths, &board)?;
+      Ok(board)
+    }
+  }
+}
+
+#[tauri::command]
+fn load_board(paths: tauri::State<'_, AppPaths>, board_id: String) -> Result<Board, AppError> {
+  load_or_init_board(&paths, &board_id)
+}
+
+/// Explicit repair for cards with NaN, infinite or runaway positions and
+/// sizes. Returns how many cards were fixed.
+#[tauri::command]
+fn sanitize_board_geometry(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+) -> Result<usize, AppError> {
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, board_index) = open_live_board(&paths, &board_id)?;
+  let fixed = fix_board_geometry(&mut board);
+  if fixed > 0 {
+    commit_board(&paths, &board_paths, board_index, &board)?;
+  }
+  Ok(fixed)
+}
+
+const DEFAULT_BOARD_PAGE_SIZE: usize = 500;
+const MAX_BOARD_PAGE_SIZE: usize = 2000;
+
+/// Returns the board's columns plus one page of its cards, in stored order, so
+/// very large boards can be rendered progressively.
+#[tauri::command]
+fn load_board_paged(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+  offset: Option<usize>,
+  limit: Option<usize>,
+) -> Result<BoardPage, AppError> {
+  let board = load_or_init_board(&paths, &board_id)?;
+  let total = board.cards.len();
+  let offset = offset.unwrap_or(0).min(total);
+  let limit = limit
+    .unwrap_or(DEFAULT_BOARD_PAGE_SIZE)
+    .clamp(1, MAX_BOARD_PAGE_SIZE);
+  let cards = board.cards.into_iter().skip(offset).take(limit).collect();
+  Ok(BoardPage {
+    id: board.id,
+    name: board.name,
+    columns: board.columns,
+    cards,
+    settings: board.settings,
+    offset,
+    total,
+  })
+}
+
+#[tauri::command]
+fn save_board(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  saves: tauri::State<'_, SaveCounters>,
+  board_id: String,
+  mut board: Board,
+  auto_fix: Option<bool>,
+) -> Result<String, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  if board.id != board_id {
+    return Err(AppError::Invalid(format!(
+      "board id mismatch (payload {}, expected {})",
+      board.id, board_id
+    )));
+  }
+  let index = read_index(&paths)?;
+  if let Some(meta) = index.boards.iter().find(|b| b.id == board_id) {
+    if meta.deleted_at.is_some() {
+      return Err(AppError::Deleted("board is deleted".into()));
+    }
+  }
+  let reassigned = fix_duplicate_card_ids(&mut board, auto_fix.unwrap_or(false))?;
+  if reassigned > 0 {
+    log::warn!("save_board {board_id}: gave {reassigned} duplicate card(s) fresh ids");
+  }
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  board.name = sanitize_board_name(&board.name);
+  let blanked = strip_foreign_asset_refs(&board_paths, &mut board);
+  if blanked > 0 {
+    log::warn!("save_board {board_id}: blanked {blanked} asset reference(s) outside the board");
+  }
+  ensure_board_file(&board_paths, &board_id, &board.name)?;
+  let previous = read_board(&board_paths).ok();
+  let started = std::time::Instant::now();
+  let size = write_board_atomic(&board_paths, &board)?;
+  saves.boards.record(size, started.elapsed());
+  if let Some(previous) = previous {
+    if let Err(e) = recycle_removed_cards(&board_paths, previous.cards, &board) {
+      log::warn!("save_board {board_id}: could not keep deleted cards: {e}");
+    }
+  }
+  if let Err(e) = snapshot_board(&board_paths, &board) {
+    log::warn!("save_board {board_id}: snapshot failed: {e}");
+  }
+  let _ = ensure_board_index_contains(&paths, index, &board_id, &board.name)?;
+  Ok(board.name)
+}
+
+/// Saves at most one snapshot per interval, so history spans days of edits.
+const SNAPSHOT_INTERVAL_MS: i64 = 10 * 60 * 1000;
+const MAX_BOARD_SNAPSHOTS: usize = 100;
+
+/// Snapshot timestamps (epoch millis, from `history/<ts>.json`), oldest first.
+fn board_snapshots(paths: &BoardPaths) -> Vec<i64> {
+  let mut stamps: Vec<i64> = std::fs::read_dir(&paths.history_dir)
+    .into_iter()
+    .flatten()
+    .flatten()
+    .filter_map(|e| e.file_name().to_str()?.strip_suffix(".json")?.parse().ok())
+    .collect();
+  stamps.sort_unstable();
+  stamps
+}
+
+fn snapshot_board(paths: &BoardPaths, board: &Board) -> Result<(), AppError> {
+  let stamps = board_snapshots(paths);
+  let now = now_millis();
+  if stamps.last().is_some_and(|last| now - last < SNAPSHOT_INTERVAL_MS) {
+    return Ok(());
+  }
+  std::fs::create_dir_all(&paths.history_dir)
+    .map_err(|e| AppError::Io(format!("create history dir failed: {e}")))?;
+  let json = serde_json::to_string(board)
+    .map_err(|e| AppError::Serialize(format!("serialize failed: {e}")))?;
+  let file = paths.history_dir.join(format!("{now}.json"));
+  let tmp = paths.history_dir.join(format!("{now}.json.tmp"));
+  std::fs::write(&tmp, json).map_err(|e| AppError::Io(format!("write temp failed: {e}")))?;
+  replace_file(&tmp, &file).map_err(|e| AppError::Io(format!("rename failed: {e}")))?;
+  let excess = (stamps.len() + 1).saturating_sub(MAX_BOARD_SNAPSHOTS);
+  for stamp in stamps.iter().take(excess) {
+    let _ = std::fs::remove_file(paths.history_dir.join(format!("{stamp}.json")));
+  }
+  Ok(())
+}
+
+/// Rejects a board whose cards share an id or, with `fix`, gives every repeat
+/// a fresh id. A column listing the id more than once gets the new ids for its
+/// later entries. Returns how many cards were renamed.
+fn fix_duplicate_card_ids(board: &mut Board, fix: bool) -> Result<usize, AppError> {
+  let mut seen = std::collections::HashSet::new();
+  let mut renamed: std::collections::HashMap<String, Vec<String>> = Default::default();
+  let stamp = now_millis();
+  for (i, card) in board.cards.iter_mut().enumerate() {
+    if seen.insert(card.id.clone()) {
+      continue;
+    }
+    if !fix {
+      return Err(AppError::Invalid(format!("duplicate card id: {}", card.id)));
+    }
+    let fresh = format!("card-{stamp}-dup-{i}");
+    renamed.entry(card.id.clone()).or_default().push(fresh.clone());
+    card.id = fresh;
+  }
+  let count = renamed.values().map(Vec::len).sum();
+  for column in board.columns.iter_mut() {
+    let mut first = std::collections::HashSet::new();
+    for id in column.card_ids.iter_mut() {
+      if !renamed.contains_key(id.as_str()) || first.insert(id.clone()) {
+        continue;
+      }
+      if let Some(fresh) = renamed.get_mut(id.as_str()).filter(|ids| !ids.is_empty()) {
+        *id = fresh.remove(0);
+      }
+    }
+  }
+  Ok(count)
+}
+
+#[cfg(test)]
+mod duplicate_card_id_tests {
+  use super::*;
+
+  fn card(id: &str) -> serde_json::Value {
+    serde_json::json!({
+      "id": id,
+      "type": "note",
+      "x": 0,
+      "y": 0,
+      "width": 9,
+      "height": 9
+    })
+  }
+
+  fn board_with_duplicates() -> Board {
+    serde_json::from_value(serde_json::json!({
+      "id": "b1",
+      "name": "Board",
+      "cards": [card("a"), card("b"), card("a"), card("a")],
+      "columns": [{
+        "id": "c1", "x": 0, "y": 0, "width": 10, "gap": 0,
+        "cardIds": ["a", "a", "b", "a"]
+      }]
+    }))
+    .unwrap()
+  }
+
+  #[test]
+  fn reject_mode_refuses_duplicates_and_leaves_board_alone() {
+    let mut board = board_with_duplicates();
+    let err = fix_duplicate_card_ids(&mut board, false).unwrap_err();
+    assert!(matches!(err, AppError::Invalid(_)));
+    assert_eq!(err.message(), "duplicate card id: a");
+    let ids: Vec<&str> = board.cards.iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(ids, ["a", "b", "a", "a"]);
+  }
+
+  #[test]
+  fn auto_fix_mode_renames_duplicates_and_their_column_slots() {
+    let mut board = board_with_duplicates();
+    assert_eq!(fix_duplicate_card_ids(&mut board, true).unwrap(), 2);
+    let ids: Vec<String> = board.cards.iter().map(|c| c.id.clone()).collect();
+    assert_eq!(ids[..2], ["a", "b"]);
+    assert!(ids[2] != "a" && ids[3] != "a" && ids[2] != ids[3]);
+    assert_eq!(
+      board.columns[0].card_ids,
+      ["a", ids[2].as_str(), "b", ids[3].as_str()]
+    );
+  }
+
+  #[test]
+  fn unique_ids_pass_either_mode() {
+    let mut board = board_with_duplicates();
+    board.cards.truncate(2);
+    board.columns[0].card_ids = vec!["a".into(), "b".into()];
+    assert_eq!(fix_duplicate_card_ids(&mut board, false).unwrap(), 0);
+    assert_eq!(fix_duplicate_card_ids(&mut board, true).unwrap(), 0);
+  }
+}
+
+/// Adds cards missing from `board` to the board's deleted-cards ring,
+/// dropping the oldest past `MAX_DELETED_CARDS`.
+fn recycle_removed_cards(
+  board_paths: &BoardPaths,
+  previous: Vec<Card>,
+  board: &Board,
+) -> Result<(), AppError> {
+  let kept: std::collections::HashSet<&str> = board.cards.iter().map(|c| c.id.as_str()).collect();
+  let removed: Vec<Card> = previous
+    .into_iter()
+    .filter(|c| !kept.contains(c.id.as_str()))
+    .collect();
+  if removed.is_empty() {
+    return Ok(());
+  }
+  let mut meta = read_board_local_meta(board_paths);
+  meta.deleted_cards.retain(|c| !removed.iter().any(|r| r.id == c.id));
+  meta.deleted_cards.extend(removed);
+  let excess = meta.deleted_cards.len().saturating_sub(MAX_DELETED_CARDS);
+  meta.deleted_cards.drain(..excess);
+  write_board_local_meta(board_paths, &meta)
+}
+
+/// Recently deleted cards, newest first.
+#[tauri::command]
+fn list_deleted_cards(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+) -> Result<Vec<Card>, AppError> {
+  let (board_paths, _, _) = open_live_board(&paths, &board_id)?;
+  let mut cards = read_board_local_meta(&board_paths).deleted_cards;
+  cards.reverse();
+  Ok(cards)
+}
+
+/// Puts a card from the deleted-cards ring back on the board.
+#[tauri::command]
+fn restore_deleted_card(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+  card_id: String,
+) -> Result<Card, AppError> {
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, index) = open_live_board(&paths, &board_id)?;
+  let mut meta = read_board_local_meta(&board_paths);
+  let pos = meta
+    .deleted_cards
+    .iter()
+    .rposition(|c| c.id == card_id)
+    .ok_or_else(|| AppError::NotFound("deleted card not found".to_string()))?;
+  if board.cards.iter().any(|c| c.id == card_id) {
+    return Err(AppError::Conflict("card is already on the board".to_string()));
+  }
+  let card = meta.deleted_cards.remove(pos);
+  board.cards.push(card.clone());
+  commit_board(&paths, &board_paths, index, &board)?;
+  write_board_local_meta(&board_paths, &meta)?;
+  Ok(card)
+}
+
+#[tauri::command]
+fn list_board_versions(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+) -> Result<Vec<i64>, AppError> {
+  let (board_paths, _, _) = open_live_board(&paths, &board_id)?;
+  let mut stamps = board_snapshots(&board_paths);
+  stamps.reverse();
+  Ok(stamps)
+}
+
+/// Lists beyond this many entries are cut short; the counts stay exact.
+const MAX_DIFF_ENTRIES: usize = 500;
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ItemChange {
+  id: String,
+  /// Names of the JSON fields that differ.
+  fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize)]
+struct ItemDiff {
+  added: Vec<String>,
+  removed: Vec<String>,
+  modified: Vec<ItemChange>,
+  #[serde(rename = "addedCount")]
+  added_count: usize,
+  #[serde(rename = "removedCount")]
+  removed_count: usize,
+  #[serde(rename = "modifiedCount")]
+  modified_count: usize,
+  truncated: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct BoardDiff {
+  from: i64,
+  to: Option<i64>,
+  cards: ItemDiff,
+  columns: ItemDiff,
+}
+
+/// Compares two id-keyed lists of serializable items field by field.
+fn diff_items<T: serde::Serialize>(
+  before: &[T],
+  after: &[T],
+  id: impl Fn(&T) -> &str,
+) -> ItemDiff {
+  let to_map = |items: &[T]| -> std::collections::BTreeMap<String, serde_json::Value> {
+    items
+      .iter()
+      .map(|item| (id(item).to_string(), serde_json::to_value(item).unwrap_or_default()))
+      .collect()
+  };
+  let (before, after) = (to_map(before), to_map(after));
+  let mut diff = ItemDiff::default();
+  for (key, old) in &before {
+    match after.get(key) {
+      None => {
+        diff.removed_count += 1;
+        if diff.removed.len() < MAX_DIFF_ENTRIES {
+          diff.removed.push(key.clone());
+        }
+      }
+      Some(new) if new != old => {
+        diff.modified_count += 1;
+        if diff.modified.len() < MAX_DIFF_ENTRIES {
+          let empty = serde_json::Map::new();
+          let old = old.as_object().unwrap_or(&empty);
+          let new = new.as_object().unwrap_or(&empty);
+          let fields = old
+            .keys()
+            .chain(new.keys().filter(|k| !old.contains_key(*k)))
+            .filter(|k| old.get(*k) != new.get(*k))
+            .cloned()
+            .collect();
+          diff.modified.push(ItemChange {
+            id: key.clone(),
+            fields,
+          });
+        }
+      }
+      Some(_) => {}
+    }
+  }
+  for key in after.keys().filter(|k| !before.contains_key(*k)) {
+    diff.added_count += 1;
+    if diff.added.len() < MAX_DIFF_ENTRIES {
+      diff.added.push(key.clone());
+    }
+  }
+  diff.truncated = diff.added_count + diff.removed_count + diff.modified_count
+    > diff.added.len() + diff.removed.len() + diff.modified.len();
+  diff
+}
+
+/// Diffs the snapshot at `ts_a` against the one at `ts_b`, or against the
+/// current board when `ts_b` is omitted. Cards and columns are matched by id.
+#[tauri::command]
+fn diff_board_versions(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+  ts_a: i64,
+  ts_b: Option<i64>,
+) -> Result<BoardDiff, AppError> {
+  let (board_paths, current, _) = open_live_board(&paths, &board_id)?;
+  let load = |ts: i64| -> Result<Board, AppError> {
+    let file = board_paths.history_dir.join(format!("{ts}.json"));
+    let text = std::fs::read_to_string(file)
+      .map_err(|_| AppError::NotFound(format!("no snapshot at {ts}")))?;
+    parse_board(&text)
+  };
+  let before = load(ts_a)?;
+  let after = match ts_b {
+    Some(ts) => load(ts)?,
+    None => current,
+  };
+  Ok(BoardDiff {
+    from: ts_a,
+    to: ts_b,
+    cards: diff_items(&before.cards, &after.cards, |c| &c.id),
+    columns: diff_items(&before.columns, &after.columns, |c| &c.id),
+  })
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct ActivityEvent {
+  /// `edited`, `chatted` or `addedImage`.
+  kind: &'static str,
+  at: i64,
+  /// Asset file name for `addedImage`, message excerpt for `chatted`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  detail: Option<String>,
+}
+
+const DEFAULT_ACTIVITY_EVENTS: usize = 200;
+const MAX_ACTIVITY_EVENTS: usize = 1000;
+const ACTIVITY_EXCERPT_CHARS: usize = 80;
+
+/// A newest-first feed built from version snapshots, the user's chat messages
+/// and asset file times, optionally limited to `since..=until` (epoch millis).
+/// Chats that can't be read (e.g. encrypted with no passphrase set) are skipped.
+#[tauri::command]
+fn board_activity(
+  paths: tauri::State<'_, AppPaths>,
+  chat_key: tauri::State<'_, ChatKey>,
+  board_id: String,
+  since: Option<i64>,
+  until: Option<i64>,
+  limit: Option<usize>,
+) -> Result<Vec<ActivityEvent>, AppError> {
+  let (board_paths, _, _) = open_live_board(&paths, &board_id)?;
+  let mut events: Vec<ActivityEvent> = board_snapshots(&board_paths)
+    .into_iter()
+    .chain(file_modified_millis(&board_paths.file))
+    .map(|at| ActivityEvent {
+      kind: "edited",
+      at,
+      detail: None,
+    })
+    .collect();
+  if let Ok(chat) = read_chat(&board_paths, chat_key.current().as_deref()) {
+    events.extend(chat.messages.into_iter().filter(|m| m.role == "user").map(|m| {
+      ActivityEvent {
+        kind: "chatted",
+        at: m.created_at,
+        detail: Some(m.content.chars().take(ACTIVITY_EXCERPT_CHARS).collect()),
+      }
+    }));
+  }
+  for entry in std::fs::read_dir(&board_paths.assets_dir).into_iter().flatten().flatten() {
+    if let Some(at) = file_modified_millis(&entry.path()) {
+      events.push(ActivityEvent {
+        kind: "addedImage",
+        at,
+        detail: Some(entry.file_name().to_string_lossy().to_string()),
+      });
+    }
+  }
+
+  events.retain(|e| since.map_or(true, |s| e.at >= s) && until.map_or(true, |u| e.at <= u));
+  events.sort_by_key(|e| std::cmp::Reverse(e.at));
+  events.truncate(limit.unwrap_or(DEFAULT_ACTIVITY_EVENTS).clamp(1, MAX_ACTIVITY_EVENTS));
+  Ok(events)
+}
+
+#[tauri::command]
+fn move_card_to_column(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+  card_id: String,
+  target_column_id: Option<String>,
+  index: Option<usize>,
+) -> Result<Vec<Column>, AppError> {
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, board_index) = open_live_board(&paths, &board_id)?;
+
+  if !board.cards.iter().any(|c| c.id == card_id) {
+    return Err(AppError::NotFound("card not found".into()));
+  }
+  if let Some(target) = &target_column_id {
+    if !board.columns.iter().any(|c| &c.id == target) {
+      return Err(AppError::NotFound("column not found".into()));
+    }
+  }
+
+  for column in board.columns.iter_mut() {
+    column.card_ids.retain(|id| id != &card_id);
+  }
+  if let Some(target) = &target_column_id {
+    if let Some(column) = board.columns.iter_mut().find(|c| &c.id == target) {
+      let at = index.unwrap_or(column.card_ids.len()).min(column.card_ids.len());
+      column.card_ids.insert(at, card_id);
+    }
+  }
+
+  commit_board(&paths, &board_paths, board_index, &board)?;
+  Ok(board.columns)
+}
+
+/// Copies a card under a fresh id, shifted by `(dx, dy)`. The copy shares the
+/// original's image file, which stays on disk while any card refers to it.
+/// With `in_column`, a copy of a column card goes right after the original.
+#[tauri::command]
+fn duplicate_card(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+  card_id: String,
+  dx: Option<f64>,
+  dy: Option<f64>,
+  in_column: Option<bool>,
+) -> Result<Card, AppError> {
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, board_index) = open_live_board(&paths, &board_id)?;
+
+  let grid = board.settings.grid();
+  let (dx, dy) = (dx.unwrap_or(grid), dy.unwrap_or(grid));
+  if !dx.is_finite() || !dy.is_finite() {
+    return Err(AppError::Invalid("offset must be finite".to_string()));
+  }
+  let mut copy = board
+    .cards
+    .iter()
+    .find(|c| c.id == card_id)
+    .cloned()
+    .ok_or_else(|| AppError::NotFound("card not found".to_string()))?;
+  let stamp = now_millis();
+  let mut n = 0;
+  copy.id = loop {
+    let id = format!("card-{stamp}-copy-{n}");
+    if !board.cards.iter().any(|c| c.id == id) {
+      break id;
+    }
+    n += 1;
+  };
+  copy.x += dx;
+  copy.y += dy;
+
+  if in_column.unwrap_or(false) {
+    for column in board.columns.iter_mut() {
+      if let Some(pos) = column.card_ids.iter().position(|id| id == &card_id) {
+        column.card_ids.insert(pos + 1, copy.id.clone());
+        break;
+      }
+    }
+  }
+  board.cards.push(copy.clone());
+  commit_board(&paths, &board_paths, board_index, &board)?;
+  Ok(copy)
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct CardPosition {
+  id: String,
+  x: f64,
+  y: f64,
+}
+
+/// Shifts `card_ids` (every card when empty) by `(dx, dy)` in one save. A
+/// column whose cards all move goes with them, since it positions its cards.
+#[tauri::command]
+fn offset_cards(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+  card_ids: Vec<String>,
+  dx: f64,
+  dy: f64,
+) -> Result<Vec<CardPosition>, AppError> {
+  if !dx.is_finite() || !dy.is_finite() {
+    return Err(AppError::Invalid("offset must be finite".to_string()));
+  }
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, board_index) = open_live_board(&paths, &board_id)?;
+
+  let targets: std::collections::HashSet<&str> = card_ids.iter().map(String::as_str).collect();
+  let moves = |id: &str| targets.is_empty() || targets.contains(id);
+  let mut moved = Vec::new();
+  for card in board.cards.iter_mut().filter(|c| moves(&c.id)) {
+    card.x += dx;
+    card.y += dy;
+    moved.push(CardPosition {
+      id: card.id.clone(),
+      x: card.x,
+      y: card.y,
+    });
+  }
+  if moved.is_empty() {
+    return Err(AppError::Invalid(
+      "none of the cards are on this board".into(),
+    ));
+  }
+  for column in board.columns.iter_mut() {
+    if !column.card_ids.is_empty() && column.card_ids.iter().all(|id| moves(id)) {
+      column.x += dx;
+      column.y += dy;
+    }
+  }
+
+  commit_board(&paths, &board_paths, board_index, &board)?;
+  Ok(moved)
+}
+
+/// Dots sit at the centre of each grid tile, so positions snap to
+/// `offset + n * grid` with `offset = grid / 2`.
+fn snap_to_grid(value: f64, grid: f64) -> f64 {
+  let offset = grid / 2.0;
+  if !value.is_finite() {
+    return offset;
+  }
+  ((value - offset) / grid).round() * grid + offset
+}
 
-  let before_len = index.boards.len();
-  index.boards.retain(|b| {
-    if b.deleted_at.is_some() {
-      seen_trash.contains(&b.id)
-    } else {
-      seen.contains(&b.id)
-    }
-  });
-  if index.boards.len() != before_len {
-    changed = true;
+#[tauri::command]
+fn snap_board_to_grid(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+) -> Result<Board, AppError> {
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, board_index) = open_live_board(&paths, &board_id)?;
+
+  let grid = board.settings.grid();
+  for card in board.cards.iter_mut() {
+    card.x = snap_to_grid(card.x, grid);
+    card.y = snap_to_grid(card.y, grid);
+  }
+  for column in board.columns.iter_mut() {
+    column.x = snap_to_grid(column.x, grid);
+    column.y = snap_to_grid(column.y, grid);
   }
 
-  if changed {
-    write_index_atomic(paths, &index)?;
+  commit_board(&paths, &board_paths, board_index, &board)?;
+  Ok(board)
+}
+
+/// Stacks `ids` top to bottom from `(x, y)`, the way a column lays them out.
+fn stack_cards(board: &mut Board, ids: &[String], x: f64, y: f64, width: f64, gap: f64) {
+  let mut y = y;
+  for id in ids {
+    if let Some(card) = board.cards.iter_mut().find(|c| &c.id == id) {
+      card.x = x;
+      card.y = y;
+      card.width = width;
+      y += card.height + gap;
+    }
   }
-  Ok(index)
 }
 
+/// Removes every column, leaving its cards as free cards. Columns are laid
+/// side by side in order from the first one's position so nothing overlaps.
 #[tauri::command]
-fn open_external_url(url: String) -> Result<(), String> {
-  let parsed = Url::parse(&url).map_err(|e| format!("invalid url: {e}"))?;
-  let scheme = parsed.scheme();
-  if scheme != "http" && scheme != "https" {
-    return Err("unsupported url scheme".to_string());
+fn flatten_columns(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+) -> Result<Board, AppError> {
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, board_index) = open_live_board(&paths, &board_id)?;
+
+  let columns = std::mem::take(&mut board.columns);
+  if let Some(first) = columns.first() {
+    let grid = board.settings.grid();
+    let (mut x, y) = (first.x, first.y);
+    for column in &columns {
+      stack_cards(&mut board, &column.card_ids, x, y, column.width, column.gap);
+      x += column.width + grid * 2.0;
+    }
   }
 
-  #[cfg(target_os = "macos")]
-  {
-    std::process::Command::new("open")
-      .arg(parsed.as_str())
-      .status()
-      .map_err(|e| format!("open failed: {e}"))?;
+  commit_board(&paths, &board_paths, board_index, &board)?;
+  Ok(board)
+}
+
+const MAX_COLUMNIZE_COLUMNS: usize = 50;
+
+/// Groups the cards that are not in a column into `column_count` evenly
+/// spaced columns, splitting them left to right by x and ordering each
+/// column by y.
+#[tauri::command]
+fn columnize(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+  column_count: usize,
+) -> Result<Board, AppError> {
+  if column_count == 0 || column_count > MAX_COLUMNIZE_COLUMNS {
+    return Err(AppError::Invalid(format!(
+      "column count must be between 1 and {MAX_COLUMNIZE_COLUMNS}"
+    )));
   }
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, board_index) = open_live_board(&paths, &board_id)?;
 
-  #[cfg(target_os = "windows")]
-  {
-    std::process::Command::new("cmd")
-      .arg("/C")
-      .arg("start")
-      .arg("")
-      .arg(parsed.as_str())
-      .status()
-      .map_err(|e| format!("open failed: {e}"))?;
+  let in_columns: std::collections::HashSet<&str> = board
+    .columns
+    .iter()
+    .flat_map(|c| c.card_ids.iter().map(String::as_str))
+    .collect();
+  let mut loose: Vec<&Card> =
+    board.cards.iter().filter(|c| !in_columns.contains(c.id.as_str())).collect();
+  if loose.is_empty() {
+    return Err(AppError::Invalid("no loose cards to put in columns".into()));
   }
+  loose.sort_by(|a, b| a.x.total_cmp(&b.x));
+  let origin_x = loose[0].x;
+  let origin_y = loose.iter().map(|c| c.y).fold(f64::INFINITY, f64::min);
+  let per_column = loose.len().div_ceil(column_count);
+  let groups: Vec<Vec<(f64, String)>> = loose
+    .chunks(per_column)
+    .map(|chunk| chunk.iter().map(|c| (c.y, c.id.clone())).collect())
+    .collect();
 
-  #[cfg(target_os = "linux")]
-  {
-    std::process::Command::new("xdg-open")
-      .arg(parsed.as_str())
-      .status()
-      .map_err(|e| format!("open failed: {e}"))?;
+  let (width, gap) = (board.settings.default_card_width, board.settings.grid());
+  let stamp = now_millis();
+  for (i, mut group) in groups.into_iter().enumerate() {
+    group.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let card_ids: Vec<String> = group.into_iter().map(|(_, id)| id).collect();
+    let x = origin_x + i as f64 * (width + gap * 2.0);
+    stack_cards(&mut board, &card_ids, x, origin_y, width, gap);
+    board.columns.push(Column {
+      id: format!("column-{stamp}-{i}"),
+      name: default_column_name(),
+      x,
+      y: origin_y,
+      width,
+      gap,
+      card_ids,
+    });
   }
 
-  Ok(())
+  commit_board(&paths, &board_paths, board_index, &board)?;
+  Ok(board)
 }
 
 #[tauri::command]
-fn list_boards(paths: tauri::State<'_, AppPaths>) -> Result<Vec<BoardMeta>, String> {
-  let index = read_index(&paths)?;
-  Ok(index
-    .boards
-    .into_iter()
-    .filter(|b| b.deleted_at.is_none())
-    .collect())
+fn compact_board(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+  unnamed_only: Option<bool>,
+) -> Result<CompactSummary, AppError> {
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, board_index) = open_live_board(&paths, &board_id)?;
+
+  // Drop ids that no longer match a card, and keep each card in at most one
+  // column (first occurrence wins).
+  let card_ids: std::collections::HashSet<&str> =
+    board.cards.iter().map(|c| c.id.as_str()).collect();
+  let mut placed = std::collections::HashSet::new();
+  let mut removed_card_refs = 0;
+  for column in board.columns.iter_mut() {
+    let before = column.card_ids.len();
+    column
+      .card_ids
+      .retain(|id| card_ids.contains(id.as_str()) && placed.insert(id.clone()));
+    removed_card_refs += before - column.card_ids.len();
+  }
+
+  let unnamed_only = unnamed_only.unwrap_or(false);
+  let mut removed_columns = Vec::new();
+  board.columns.retain(|column| {
+    let unnamed = column.name.trim().is_empty() || column.name == default_column_name();
+    let remove = column.card_ids.is_empty() && (!unnamed_only || unnamed);
+    if remove {
+      removed_columns.push(column.id.clone());
+    }
+    !remove
+  });
+
+  if removed_card_refs > 0 || !removed_columns.is_empty() {
+    commit_board(&paths, &board_paths, board_index, &board)?;
+  }
+  Ok(CompactSummary {
+    removed_columns,
+    removed_card_refs,
+  })
 }
 
 #[tauri::command]
-fn list_trashed_boards(paths: tauri::State<'_, AppPaths>) -> Result<Vec<BoardMeta>, String> {
-  let mut boards: Vec<BoardMeta> = read_index(&paths)?
-    .boards
-    .into_iter()
-    .filter(|b| b.deleted_at.is_some())
-    .collect();
-  boards.sort_by_key(|b| b.deleted_at.unwrap_or(0));
-  boards.reverse();
-  Ok(boards)
+fn export_board_html(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+) -> Result<String, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  if !board_paths.file.exists() {
+    return Err(AppError::NotFound("board not found".into()));
+  }
+  let board = read_board(&board_paths)?;
+  Ok(render_board_html(&paths, &board_paths, &board))
 }
 
+/// Writes the board as a `.canvas` file in `dest_dir`, with the images it
+/// uses (pool images included) copied into `dest_dir/assets/`. Returns the
+/// canvas file's path.
 #[tauri::command]
-fn cleanup_assets(paths: tauri::State<'_, AppPaths>) -> Result<u32, String> {
-  ensure_root_dir(&paths)?;
-  let mut removed = 0u32;
-  let entries = std::fs::read_dir(&paths.root_dir)
-    .map_err(|e| format!("read boards dir failed: {e}"))?;
-  for entry in entries.flatten() {
-    let path = entry.path();
-    if !path.is_dir() {
-      continue;
-    }
-    let board_id = match path.file_name().and_then(|n| n.to_str()) {
-      Some(name) => name.to_string(),
-      None => continue,
-    };
-    if board_id == "trash" {
-      continue;
-    }
-    if !is_valid_board_id(&board_id) {
-      continue;
-    }
-    let board_file = path.join("board.json");
-    if !board_file.exists() {
-      continue;
-    }
-    let text = match std::fs::read_to_string(&board_file) {
-      Ok(text) => text,
-      Err(_) => continue,
-    };
-    let board: Board = match serde_json::from_str(&text) {
-      Ok(board) => board,
-      Err(_) => continue,
-    };
-    let mut keep: std::collections::HashSet<String> = std::collections::HashSet::new();
-    for card in board.cards {
-      if let Some(src) = card.src {
-        if let Some(name) = src.strip_prefix("assets/") {
-          keep.insert(name.to_string());
-        }
-      }
-      if let Some(image) = card.image {
-        if let Some(name) = image.strip_prefix("assets/") {
-          keep.insert(name.to_string());
-        }
-      }
-    }
-    let assets_dir = path.join("assets");
-    if !assets_dir.exists() {
+fn export_board_canvas(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+  dest_dir: String,
+) -> Result<String, AppError> {
+  if !is_valid_board_id(&board_id) {
+    return Err(AppError::InvalidBoardId);
+  }
+  let dest = std::path::PathBuf::from(dest_dir.trim());
+  if !dest.is_dir() {
+    return Err(AppError::NotFound("export folder does not exist".into()));
+  }
+  let board_paths = board_paths(&paths.root_dir, &board_id);
+  if !board_paths.file.exists() {
+    return Err(AppError::NotFound("board not found".into()));
+  }
+  let board = read_board(&board_paths)?;
+  let json = serde_json::to_string_pretty(&render_board_canvas(&paths, &board_paths, &board))
+    .map_err(|e| AppError::Serialize(format!("serialize canvas failed: {e}")))?;
+
+  let assets = collect_export_assets(&paths, &board_paths, &mut board.clone());
+  let assets_dir = dest.join("assets");
+  if !assets.is_empty() {
+    std::fs::create_dir_all(&assets_dir)
+      .map_err(|e| AppError::Io(format!("create assets dir failed: {e}")))?;
+  }
+  for (name, file) in &assets {
+    if !file.is_file() || is_symlink(file) {
       continue;
     }
-    let assets = match std::fs::read_dir(&assets_dir) {
-      Ok(assets) => assets,
-      Err(_) => continue,
-    };
-    for asset in assets.flatten() {
-      let asset_path = asset.path();
-      if !asset_path.is_file() {
-        continue;
-      }
-      let name = match asset_path.file_name().and_then(|n| n.to_str()) {
-        Some(name) => name.to_string(),
-        None => continue,
-      };
-      if !keep.contains(&name) {
-        if std::fs::remove_file(&asset_path).is_ok() {
-          removed += 1;
-        }
-      }
-    }
+    std::fs::copy(file, assets_dir.join(name))
+      .map_err(|e| AppError::Io(format!("copy asset failed: {e}")))?;
   }
-  Ok(removed)
+
+  let timestamp = Utc::now().format("%Y%m%d-%H%M%S").to_string();
+  let out_path = dest.join(format!("LANA-{board_id}-{timestamp}.canvas"));
+  std::fs::write(&out_path, json).map_err(|e| AppError::Io(format!("write canvas failed: {e}")))?;
+  Ok(out_path.to_string_lossy().to_string())
 }
 
+/// Writes a zip holding a new board made of just `card_ids` (plus the columns
+/// that contain them) and the assets those cards use, laid out like a backup
+/// so `import_board_zip` can add it as its own board. Returns the zip's path.
 #[tauri::command]
-fn create_backup(paths: tauri::State<'_, AppPaths>, dest_dir: String) -> Result<String, String> {
+fn export_board_subset(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+  card_ids: Vec<String>,
+  dest_dir: String,
+  remap_ids: Option<bool>,
+) -> Result<String, AppError> {
   let dest = std::path::PathBuf::from(dest_dir.trim());
-  if dest.as_os_str().is_empty() {
-    return Err("backup folder not set".to_string());
-  }
-  if !dest.exists() {
-    return Err("backup folder does not exist".to_string());
-  }
   if !dest.is_dir() {
-    return Err("backup folder is not a directory".to_string());
+    return Err(AppError::NotFound("export folder does not exist".into()));
   }
-  if dest.starts_with(&paths.root_dir) {
-    return Err("backup folder must be outside boards directory".to_string());
+  let (board_paths, board, index) = open_live_board(&paths, &board_id)?;
+  let wanted: std::collections::HashSet<&str> = card_ids.iter().map(String::as_str).collect();
+  let subset_name = sanitize_board_name(&format!("{} (subset)", board.name));
+  let mut subset = empty_board(&generate_board_id(&paths, &index), &subset_name);
+  subset.settings = board.settings.clone();
+  subset.cards = board
+    .cards
+    .into_iter()
+    .filter(|c| wanted.contains(c.id.as_str()))
+    .collect();
+  if subset.cards.is_empty() {
+    return Err(AppError::Invalid(
+      "none of the cards are on this board".into(),
+    ));
   }
+  let kept: std::collections::HashSet<String> = subset.cards.iter().map(|c| c.id.clone()).collect();
+  subset.columns = board
+    .columns
+    .into_iter()
+    .filter_map(|mut column| {
+      column.card_ids.retain(|id| kept.contains(id));
+      (!column.card_ids.is_empty()).then_some(column)
+    })
+    .collect();
 
-  let timestamp = Utc::now().format("%Y%m%d-%H%M%S").to_string();
-  let filename = format!("LANA-backup-{timestamp}.zip");
-  let out_path = dest.join(&filename);
+  let assets = collect_export_assets(&paths, &board_paths, &mut subset);
+  if remap_ids.unwrap_or(false) {
+    remap_board_ids(&mut subset);
+  }
 
-  let file = std::fs::File::create(&out_path).map_err(|e| format!("create backup failed: {e}"))?;
-  let mut zip = zip::ZipWriter::new(file);
-  let options = zip::write::FileOptions::default()
-    .compression_method(zip::CompressionMethod::Deflated)
-    .unix_permissions(0o644);
+  let timestamp = Utc::now().format("%Y%m%d-%H%M%S").to_string();
+  let out_path = dest.join(format!("LANA-{board_id}-subset-{timestamp}.zip"));
+  write_board_zip(&out_path, &subset, &assets)?;
+  Ok(out_path.to_string_lossy().to_string())
+}
 
-  for entry in WalkDir::new(&paths.root_dir)
-    .into_iter()
-    .filter_map(Result::ok)
-    .filter(|e| e.file_type().is_file())
-  {
-    let path = entry.path();
-    let rel = match path.strip_prefix(&paths.root_dir) {
-      Ok(rel) => rel,
-      Err(_) => continue,
-    };
-    if rel.as_os_str().is_empty() {
-      continue;
+/// Asset files the board's cards use, keyed by the name they get in the zip.
+/// Pool images are bundled as ordinary board assets so the zip stands alone.
+fn collect_export_assets(
+  paths: &AppPaths,
+  board_paths: &BoardPaths,
+  board: &mut Board,
+) -> std::collections::BTreeMap<String, std::path::PathBuf> {
+  let mut assets = std::collections::BTreeMap::new();
+  for card in board.cards.iter_mut() {
+    for value in [&mut card.src, &mut card.image, &mut card.favicon].into_iter().flatten() {
+      if let Some(file) = pool_file(paths, value) {
+        let name = pool_ref_name(value).unwrap_or_default().to_string();
+        *value = format!("assets/{name}");
+        assets.insert(name, file);
+      } else if let Some(file) = card_asset_file(board_paths, value) {
+        if let Some(name) = file.file_name().and_then(|n| n.to_str()) {
+          assets.insert(name.to_string(), file.clone());
+        }
+      }
     }
-    let name = rel.to_string_lossy().replace('\\', "/");
-    let mut input = std::fs::File::open(path).map_err(|e| format!("zip open failed: {e}"))?;
-    zip
-      .start_file(name, options)
-      .map_err(|e| format!("zip start failed: {e}"))?;
-    std::io::copy(&mut input, &mut zip).map_err(|e| format!("zip write failed: {e}"))?;
   }
-  zip.finish().map_err(|e| format!("zip finish failed: {e}"))?;
+  assets
+}
 
-  let mut backups: Vec<(String, std::path::PathBuf)> = Vec::new();
-  if let Ok(entries) = std::fs::read_dir(&dest) {
-    for entry in entries.flatten() {
-      let path = entry.path();
-      if !path.is_file() {
+/// Writes `<board id>/board.json` plus `<board id>/assets/*`, laid out like a
+/// backup. A partial zip is removed on failure.
+fn write_board_zip(
+  out_path: &std::path::Path,
+  board: &Board,
+  assets: &std::collections::BTreeMap<String, std::path::PathBuf>,
+) -> Result<(), AppError> {
+  let write = || -> Result<(), AppError> {
+    let file = std::fs::File::create(out_path)
+      .map_err(|e| AppError::Io(format!("create export failed: {e}")))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default()
+      .compression_method(zip::CompressionMethod::Deflated)
+      .unix_permissions(0o644);
+    let json = serde_json::to_vec_pretty(board)
+      .map_err(|e| AppError::Serialize(format!("serialize board failed: {e}")))?;
+    zip
+      .start_file(format!("{}/board.json", board.id), options)
+      .map_err(|e| AppError::Io(format!("zip start failed: {e}")))?;
+    std::io::Write::write_all(&mut zip, &json)
+      .map_err(|e| AppError::Io(format!("zip write failed: {e}")))?;
+    for (name, file) in assets {
+      if !file.is_file() || is_symlink(file) {
         continue;
       }
-      let name = match path.file_name().and_then(|n| n.to_str()) {
-        Some(name) => name.to_string(),
-        None => continue,
-      };
-      if is_backup_filename(&name) {
-        backups.push((name, path));
-      }
-    }
-  }
-  backups.sort_by(|a, b| a.0.cmp(&b.0));
-  if backups.len() > 3 {
-    for (_, path) in backups.iter().take(backups.len() - 3) {
-      let _ = std::fs::remove_file(path);
+      let mut input =
+        std::fs::File::open(file).map_err(|e| AppError::Io(format!("zip open failed: {e}")))?;
+      zip
+        .start_file(format!("{}/assets/{name}", board.id), options)
+        .map_err(|e| AppError::Io(format!("zip start failed: {e}")))?;
+      std::io::copy(&mut input, &mut zip)
+        .map_err(|e| AppError::Io(format!("zip write failed: {e}")))?;
     }
-  }
-
-  Ok(filename)
+    zip
+      .finish()
+      .map_err(|e| AppError::Io(format!("zip finish failed: {e}")))?;
+    Ok(())
+  };
+  write().inspect_err(|_| {
+    let _ = std::fs::remove_file(out_path);
+  })
 }
 
-#[tauri::command]
-fn empty_trash(paths: tauri::State<'_, AppPaths>) -> Result<(), String> {
-  let mut index = read_index(&paths)?;
-  index.boards.retain(|b| b.deleted_at.is_none());
-  write_index_atomic(&paths, &index)?;
-
-  let trash_dir = paths.root_dir.join("trash");
-  if trash_dir.exists() {
-    std::fs::remove_dir_all(&trash_dir).map_err(|e| format!("empty trash failed: {e}"))?;
+/// Reads one zip entry, refusing anything larger than `MAX_UPLOAD_BYTES`.
+fn read_zip_entry(entry: &mut zip::read::ZipFile) -> Result<Vec<u8>, AppError> {
+  let mut bytes = Vec::new();
+  std::io::Read::read_to_end(
+    &mut std::io::Read::take(entry, MAX_UPLOAD_BYTES + 1),
+    &mut bytes,
+  )
+  .map_err(|e| AppError::Io(format!("read zip entry failed: {e}")))?;
+  if bytes.len() as u64 > MAX_UPLOAD_BYTES {
+    return Err(AppError::QuotaExceeded("zip entry is too large".into()));
   }
-  Ok(())
+  Ok(bytes)
 }
 
+/// Adds a board zip written by `export_board` or `export_board_subset` as a
+/// new board with its own id. References that would reach outside the new
+/// board's assets dir are blanked, as `save_board` does.
 #[tauri::command]
-fn create_board(paths: tauri::State<'_, AppPaths>, name: String) -> Result<BoardMeta, String> {
+fn import_board_zip(
+  paths: tauri::State<'_, AppPaths>,
+  zip_path: String,
+  new_name: Option<String>,
+) -> Result<BoardMeta, AppError> {
+  let file = std::fs::File::open(zip_path.trim())
+    .map_err(|e| AppError::NotFound(format!("open zip failed: {e}")))?;
+  let mut zip =
+    zip::ZipArchive::new(file).map_err(|e| AppError::Invalid(format!("not a zip file: {e}")))?;
+  let board_entries: Vec<String> = zip
+    .file_names()
+    .filter(|name| name.split('/').count() == 2 && name.ends_with("/board.json"))
+    .map(str::to_string)
+    .collect();
+  let board_entry = match board_entries.as_slice() {
+    [entry] => entry.clone(),
+    [] => return Err(AppError::Invalid("zip holds no board".into())),
+    _ => return Err(AppError::Invalid("zip holds more than one board".into())),
+  };
+  let assets_prefix = board_entry.replace("board.json", "assets/");
+
+  let text = {
+    let mut entry = zip
+      .by_name(&board_entry)
+      .map_err(|e| AppError::Invalid(format!("read board failed: {e}")))?;
+    let bytes = read_zip_entry(&mut entry)?;
+    String::from_utf8(bytes).map_err(|_| AppError::Invalid("board.json is not UTF-8".into()))?
+  };
+  let mut board = parse_board(&text)?;
+  fix_board_geometry(&mut board);
+
   let index = read_index(&paths)?;
-  let board_id = generate_board_id(&paths, &index);
-  let safe_name = if name.trim().is_empty() {
-    "Untitled"
-  } else {
-    name.trim()
+  let id = generate_board_id(&paths, &index);
+  let name = sanitize_board_name(new_name.as_deref().unwrap_or(&board.name));
+  let board_paths = board_paths(&paths.root_dir, &id);
+  ensure_board_file(&board_paths, &id, &name)?;
+  board.id = id.clone();
+  board.name = name.clone();
+
+  let mut write = || -> Result<(), AppError> {
+    for i in 0..zip.len() {
+      let mut entry = zip
+        .by_index(i)
+        .map_err(|e| AppError::Invalid(format!("read zip entry failed: {e}")))?;
+      let asset = match entry.name().strip_prefix(&assets_prefix) {
+        Some(asset) if entry.is_file() && !asset.is_empty() => asset.to_string(),
+        _ => continue,
+      };
+      if flatten_asset_name(&asset) != asset {
+        continue;
+      }
+      let bytes = read_zip_entry(&mut entry)?;
+      std::fs::write(board_paths.assets_dir.join(&asset), bytes)
+        .map_err(|e| AppError::Io(format!("write asset failed: {e}")))?;
+    }
+    let blanked = strip_foreign_asset_refs(&board_paths, &mut board);
+    if blanked > 0 {
+      log::warn!("import board {id}: blanked {blanked} asset reference(s) outside the board");
+    }
+    write_board_atomic(&board_paths, &board)?;
+    Ok(())
   };
-  let board_paths = board_paths(&paths.root_dir, &board_id);
-  ensure_board_file(&board_paths, &board_id, safe_name)?;
+  if let Err(e) = write() {
+    let _ = std::fs::remove_dir_all(&board_paths.dir);
+    return Err(e);
+  }
+
   let meta = BoardMeta {
-    id: board_id,
-    name: safe_name.to_string(),
+    id,
+    name,
     updated_at: now_millis(),
     deleted_at: None,
+    pinned: false,
   };
   let mut next = index;
   next.boards.push(meta.clone());
@@ -959,365 +9552,748 @@ fn create_board(paths: tauri::State<'_, AppPaths>, name: String) -> Result<Board
   Ok(meta)
 }
 
-#[tauri::command]
-fn delete_board(paths: tauri::State<'_, AppPaths>, board_id: String) -> Result<(), String> {
-  if !is_valid_board_id(&board_id) {
-    return Err("invalid board id".to_string());
-  }
-  let mut index = read_index(&paths)?;
-  let mut found = false;
-  for meta in index.boards.iter_mut() {
-    if meta.id == board_id {
-      meta.deleted_at = Some(now_millis());
-      found = true;
-      break;
+#[derive(Debug, Clone, serde::Serialize)]
+struct BoardExport {
+  path: String,
+  #[serde(rename = "includeOriginals")]
+  include_originals: bool,
+  /// Size of the written zip.
+  bytes: u64,
+  /// Combined size of the original assets, whether or not they were bundled,
+  /// so the UI can show what share mode saved.
+  #[serde(rename = "originalBytes")]
+  original_bytes: u64,
+  /// Images replaced by their thumbnail.
+  thumbnailed: usize,
+}
+
+/// Zips the whole board. With `include_originals` false ("share" mode) each
+/// image is swapped for its thumbnail, generated if missing, and card
+/// references point at it; animations small enough to play as-is and files
+/// that can't be decoded keep their original. Share zips are much smaller but
+/// lose full-resolution images, so they are no substitute for a backup.
+#[tauri::command]
+async fn export_board(
+  paths: tauri::State<'_, AppPaths>,
+  board_id: String,
+  dest_dir: String,
+  include_originals: bool,
+) -> Result<BoardExport, AppError> {
+  let dest = std::path::PathBuf::from(dest_dir.trim());
+  if !dest.is_dir() {
+    return Err(AppError::NotFound("export folder does not exist".into()));
+  }
+  let (board_paths, mut board, _) = open_live_board(&paths, &board_id)?;
+  let mut assets = collect_export_assets(&paths, &board_paths, &mut board);
+  let original_bytes = assets
+    .values()
+    .filter_map(|file| std::fs::metadata(file).ok())
+    .map(|m| m.len())
+    .sum();
+
+  let timestamp = Utc::now().format("%Y%m%d-%H%M%S").to_string();
+  let suffix = if include_originals { "" } else { "-share" };
+  let out_path = dest.join(format!("LANA-{board_id}{suffix}-{timestamp}.zip"));
+  let out = out_path.clone();
+  let thumbnailed = run_blocking(move || {
+    let mut thumbnailed = 0;
+    if !include_originals {
+      let mut renamed = std::collections::HashMap::new();
+      for (name, file) in std::mem::take(&mut assets) {
+        let thumb = ensure_thumbnail(&board_paths, &file, DEFAULT_THUMBNAIL_SIZE)
+          .ok()
+          .filter(|(thumb, _)| !thumb.original)
+          .and_then(|_| thumbnail_file(&board_paths, &file, DEFAULT_THUMBNAIL_SIZE));
+        match thumb {
+          Some(thumb) => {
+            let thumb_name = format!("{name}.{DEFAULT_THUMBNAIL_SIZE}.jpg");
+            renamed.insert(format!("assets/{name}"), format!("assets/{thumb_name}"));
+            assets.insert(thumb_name, thumb);
+            thumbnailed += 1;
+          }
+          None => {
+            assets.insert(name, file);
+          }
+        }
+      }
+      for card in board.cards.iter_mut() {
+        for value in [&mut card.src, &mut card.image, &mut card.favicon]
+          .into_iter()
+          .flatten()
+        {
+          if let Some(thumb) = renamed.get(value.as_str()) {
+            *value = thumb.clone();
+          }
+        }
+      }
+    }
+    write_board_zip(&out, &board, &assets).map(|()| thumbnailed)
+  })
+  .await?;
+
+  let bytes = std::fs::metadata(&out_path).map(|m| m.len()).unwrap_or(0);
+  Ok(BoardExport {
+    path: out_path.to_string_lossy().to_string(),
+    include_originals,
+    bytes,
+    original_bytes,
+    thumbnailed,
+  })
+}
+
+#[tauri::command]
+fn tag_cards(
+  paths: tauri::State<'_, AppPaths>,
+  locks: tauri::State<'_, BoardLocks>,
+  board_id: String,
+  card_ids: Vec<String>,
+  add_tags: Vec<String>,
+  remove_tags: Vec<String>,
+) -> Result<usize, AppError> {
+  let add: Vec<String> = add_tags.iter().filter_map(|t| normalize_tag(t)).collect();
+  let remove: std::collections::HashSet<String> =
+    remove_tags.iter().filter_map(|t| normalize_tag(t)).collect();
+  let targets: std::collections::HashSet<&str> = card_ids.iter().map(|id| id.as_str()).collect();
+
+  let lock = locks.for_board(&board_id);
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+  let (board_paths, mut board, board_index) = open_live_board(&paths, &board_id)?;
+
+  let mut updated = 0;
+  for card in board.cards.iter_mut().filter(|c| targets.contains(c.id.as_str())) {
+    let mut seen = std::collections::HashSet::new();
+    let next: Vec<String> = card
+      .tags
+      .iter()
+      .filter_map(|t| normalize_tag(t))
+      .chain(add.iter().cloned())
+      .filter(|t| !remove.contains(t) && seen.insert(t.clone()))
+      .collect();
+    if next != card.tags {
+      card.tags = next;
+      updated += 1;
     }
   }
-  if !found {
-    return Err("board not found".to_string());
-  }
-  write_index_atomic(&paths, &index)?;
-  let board_paths = board_paths(&paths.root_dir, &board_id);
-  if board_paths.dir.exists() {
-    let trash_dir = paths.root_dir.join("trash");
-    std::fs::create_dir_all(&trash_dir)
-      .map_err(|e| format!("create trash dir failed: {e}"))?;
-    let dest = trash_dir.join(&board_id);
-    if dest.exists() {
-      let _ = std::fs::remove_dir_all(&dest);
-    }
-    std::fs::rename(&board_paths.dir, &dest)
-      .map_err(|e| format!("move board to trash failed: {e}"))?;
+
+  if updated > 0 {
+    commit_board(&paths, &board_paths, board_index, &board)?;
   }
-  Ok(())
+  Ok(updated)
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct TagBoardRef {
+  #[serde(rename = "boardId")]
+  board_id: String,
+  #[serde(rename = "boardName")]
+  board_name: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct TagUsage {
+  tag: String,
+  /// Cards carrying the tag, across all boards.
+  count: usize,
+  boards: Vec<TagBoardRef>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+struct TagPage {
+  tags: Vec<TagUsage>,
+  offset: usize,
+  total: usize,
 }
 
+const DEFAULT_TAG_PAGE_SIZE: usize = 200;
+const MAX_TAG_PAGE_SIZE: usize = 1000;
+
+/// Every tag used on a live board, most used first, one page at a time.
 #[tauri::command]
-fn restore_board(paths: tauri::State<'_, AppPaths>, board_id: String) -> Result<(), String> {
-  if !is_valid_board_id(&board_id) {
-    return Err("invalid board id".to_string());
-  }
-  let trash_dir = paths.root_dir.join("trash");
-  let src = trash_dir.join(&board_id);
-  if !src.exists() {
-    return Err("board not found in trash".to_string());
-  }
-  let dest = paths.root_dir.join(&board_id);
-  if dest.exists() {
-    return Err("board already exists".to_string());
+fn all_tags(
+  paths: tauri::State<'_, AppPaths>,
+  offset: Option<usize>,
+  limit: Option<usize>,
+) -> Result<TagPage, AppError> {
+  let mut usage: std::collections::HashMap<String, TagUsage> = std::collections::HashMap::new();
+  for (meta, board) in live_boards(&paths)? {
+    for tag in board.cards.iter().flat_map(|c| c.tags.iter()).filter_map(|t| normalize_tag(t)) {
+      let entry = usage.entry(tag.clone()).or_insert_with(|| TagUsage {
+        tag,
+        count: 0,
+        boards: Vec::new(),
+      });
+      entry.count += 1;
+      if entry.boards.last().map_or(true, |b| b.board_id != meta.id) {
+        entry.boards.push(TagBoardRef {
+          board_id: meta.id.clone(),
+          board_name: meta.name.clone(),
+        });
+      }
+    }
   }
-  std::fs::rename(&src, &dest).map_err(|e| format!("restore board failed: {e}"))?;
 
-  let board_file = dest.join("board.json");
-  let name = read_board_name(&board_file).unwrap_or_else(|| board_id.clone());
-  let mut index = read_index(&paths)?;
-  index = ensure_board_index_contains(&paths, index, &board_id, &name)?;
-  write_index_atomic(&paths, &index)?;
-  Ok(())
+  let mut tags: Vec<TagUsage> = usage.into_values().collect();
+  tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+  let total = tags.len();
+  let offset = offset.unwrap_or(0).min(total);
+  let limit = limit.unwrap_or(DEFAULT_TAG_PAGE_SIZE).clamp(1, MAX_TAG_PAGE_SIZE);
+  let tags = tags.into_iter().skip(offset).take(limit).collect();
+  Ok(TagPage {
+    tags,
+    offset,
+    total,
+  })
 }
 
+const DEFAULT_SIMILARITY_THRESHOLD: u32 = 6;
+const MAX_SIMILARITY_THRESHOLD: u32 = 32;
+const MAX_SIMILARITY_ASSETS: usize = 2000;
+
+/// Groups a board's images whose perceptual hashes are within `threshold` bits.
+/// Emits `similar-images-progress` while hashing.
 #[tauri::command]
-async fn fetch_link_metadata(
+async fn find_similar_images(
+  app: tauri::AppHandle,
   paths: tauri::State<'_, AppPaths>,
   board_id: String,
-  url: String,
-) -> Result<LinkMetadata, String> {
+  threshold: Option<u32>,
+) -> Result<Vec<SimilarImageGroup>, AppError> {
   if !is_valid_board_id(&board_id) {
-    return Err("invalid board id".to_string());
-  }
-  let parsed = Url::parse(&url).map_err(|e| format!("invalid url: {e}"))?;
-  let scheme = parsed.scheme();
-  if scheme != "http" && scheme != "https" {
-    return Err("unsupported url scheme".to_string());
-  }
-  if !is_safe_url(&parsed) {
-    return Err("blocked url host".to_string());
+    return Err(AppError::InvalidBoardId);
   }
+  let threshold = threshold
+    .unwrap_or(DEFAULT_SIMILARITY_THRESHOLD)
+    .min(MAX_SIMILARITY_THRESHOLD);
+  let board_paths = board_paths(&paths.root_dir, &board_id);
 
-  let client = reqwest::Client::builder()
-    .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 14_5) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/125.0.0.0 Safari/537.36")
-    .build()
-    .map_err(|e| format!("http client failed: {e}"))?;
+  run_blocking(move || {
+    let mut files: Vec<std::path::PathBuf> = match std::fs::read_dir(&board_paths.assets_dir) {
+      Ok(entries) => entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && is_image_asset(p))
+        .collect(),
+      Err(_) => return Ok(vec![]),
+    };
+    files.sort();
+    files.truncate(MAX_SIMILARITY_ASSETS);
 
-  let resp = client
-    .get(parsed.clone())
-    .header(reqwest::header::ACCEPT, "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8")
-    .header(reqwest::header::ACCEPT_LANGUAGE, "en-US,en;q=0.9")
-    .header(reqwest::header::ACCEPT_ENCODING, "gzip, deflate, br")
-    .send()
-    .await
-    .map_err(|e| format!("fetch failed: {e}"))?;
+    let total = files.len();
+    let mut hashes = Vec::with_capacity(total);
+    for (i, file) in files.iter().enumerate() {
+      if let Some(hash) = dhash(file) {
+        hashes.push((file, hash));
+      }
+      emit_to_board(
+        &app,
+        &board_id,
+        "similar-images-progress",
+        ScanProgress {
+          board_id: board_id.clone(),
+          done: i + 1,
+          total,
+        },
+      );
+    }
 
-  let final_url = resp.url().clone();
-  let text = resp.text().await.map_err(|e| format!("read body failed: {e}"))?;
+    // Union-find over every pair within the threshold.
+    let mut parent: Vec<usize> = (0..hashes.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+      while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+      }
+      i
+    }
+    for a in 0..hashes.len() {
+      for b in a + 1..hashes.len() {
+        if (hashes[a].1 ^ hashes[b].1).count_ones() <= threshold {
+          let (ra, rb) = (root(&mut parent, a), root(&mut parent, b));
+          if ra != rb {
+            parent[rb] = ra;
+          }
+        }
+      }
+    }
 
-  let (title, site_name, image_url) = {
-    let doc = Html::parse_document(&text);
-    let mut title = meta_content(&doc, "meta[property='og:title']")
-      .or_else(|| meta_content(&doc, "meta[name='twitter:title']"))
-      .or_else(|| meta_content(&doc, "meta[name='title']"))
-      .or_else(|| meta_content(&doc, "meta[itemprop='name']"))
-      .or_else(|| title_text(&doc))
-      .or_else(|| final_url.host_str().map(|h| h.to_string()))
-      .unwrap_or_else(|| "Link".to_string());
-    if title == "Google Maps" || title == "Link" {
-      if let Some(map_title) = google_maps_title(&final_url) {
-        title = map_title;
+    let mut groups: std::collections::BTreeMap<usize, Vec<String>> =
+      std::collections::BTreeMap::new();
+    for (i, (file, _)) in hashes.iter().enumerate() {
+      let r = root(&mut parent, i);
+      if let Some(name) = file.file_name().and_then(|n| n.to_str()) {
+        groups.entry(r).or_default().push(format!("assets/{name}"));
       }
     }
+    Ok(
+      groups
+        .into_values()
+        .filter(|assets| assets.len() > 1)
+        .map(|assets| SimilarImageGroup { assets })
+        .collect(),
+    )
+  })
+  .await
+}
 
-    let site_name = meta_content(&doc, "meta[property='og:site_name']")
-      .or_else(|| final_url.host_str().map(|h| h.to_string()));
+#[cfg(test)]
+mod tests {
+  use super::*;
 
-    let image_url = meta_content(&doc, "meta[property='og:image']")
-      .or_else(|| meta_content(&doc, "meta[name='twitter:image']"));
+  /// Serves `302 Found` with `location` to every request on a loopback port.
+  fn redirect_server(location: &'static str) -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+      for mut stream in listener.incoming().flatten() {
+        let mut buf = [0u8; 4096];
+        let _ = std::io::Read::read(&mut stream, &mut buf);
+        let response = format!(
+          "HTTP/1.1 302 Found\r\nLocation: {location}\r\nContent-Length: 0\r\n\
+           Connection: close\r\n\r\n"
+        );
+        let _ = std::io::Write::write_all(&mut stream, response.as_bytes());
+      }
+    });
+    format!("http://{addr}/")
+  }
 
-    (title, site_name, image_url)
-  };
+  async fn follow(location: &'static str) -> reqwest::Error {
+    let client = reqwest::Client::builder()
+      .redirect(safe_redirect_policy())
+      .build()
+      .unwrap();
+    client
+      .get(redirect_server(location))
+      .send()
+      .await
+      .unwrap_err()
+  }
 
-  let mut image: Option<String> = None;
-  if let Some(raw_image) = image_url {
-    if let Ok(resolved) = final_url.join(&raw_image) {
-      if is_safe_url(&resolved) {
-        if let Ok(img_resp) = client.get(resolved.clone()).send().await {
-          if img_resp.status().is_success() {
-            let content_type = img_resp
-              .headers()
-              .get(CONTENT_TYPE)
-              .and_then(|v| v.to_str().ok())
-              .unwrap_or("")
-              .to_string();
-            if content_type.starts_with("image/") {
-              if let Ok(bytes) = img_resp.bytes().await {
-                if bytes.len() <= 5 * 1024 * 1024 {
-                  let ext = ext_from_content_type(&content_type).unwrap_or(".img");
-                  if let Ok(saved) = save_asset_bytes(&paths, &board_id, &bytes, ext) {
-                    image = Some(saved);
-                  }
-                }
-              }
-            }
-          }
-        }
-      }
+  #[tokio::test]
+  async fn redirect_to_private_ip_is_blocked() {
+    for target in [
+      "http://10.0.0.1/admin",
+      "http://[::ffff:127.0.0.1]/",
+      "http://localhost:8080/",
+    ] {
+      let e = follow(target).await;
+      assert!(caused_by(&e, BLOCKED_REDIRECT), "{target}: {e}");
     }
   }
 
-  Ok(LinkMetadata {
-    url: final_url.to_string(),
-    title,
-    image,
-    site_name,
-  })
-}
+  #[test]
+  fn redirect_refusal_checks_scheme_host_and_hops() {
+    let refusal = |s: &str, hops| redirect_refusal(&Url::parse(s).unwrap(), hops);
+    assert_eq!(refusal("https://example.com/next", 1), None);
+    assert_eq!(
+      refusal("https://example.com/next", MAX_FETCH_REDIRECTS),
+      None
+    );
+    assert_eq!(
+      refusal("https://example.com/next", MAX_FETCH_REDIRECTS + 1),
+      Some("too many redirects")
+    );
+    assert_eq!(refusal("file:///etc/passwd", 1), Some(BLOCKED_REDIRECT));
+    assert_eq!(refusal("ftp://example.com/", 1), Some(BLOCKED_REDIRECT));
+    assert_eq!(refusal("http://192.168.0.1/", 1), Some(BLOCKED_REDIRECT));
+    assert_eq!(
+      refusal("http://100.100.100.200/latest", 1),
+      Some(BLOCKED_REDIRECT)
+    );
+    assert_eq!(
+      refusal("http://[::ffff:a9fe:a9fe]/", 1),
+      Some(BLOCKED_REDIRECT)
+    );
+    assert_eq!(refusal("http://printer.local/", 1), Some(BLOCKED_REDIRECT));
+  }
 
-#[tauri::command]
-async fn ollama_chat(model: String, messages: Vec<OllamaMessage>) -> Result<OllamaMessage, String> {
-  if model.trim().is_empty() {
-    return Err("model is required".to_string());
+  #[test]
+  fn charset_param_reads_headers_and_meta_tags() {
+    assert_eq!(
+      charset_param("text/html; charset=ISO-8859-1").as_deref(),
+      Some("iso-8859-1")
+    );
+    assert_eq!(
+      charset_param("text/html; charset=\"utf-8\"").as_deref(),
+      Some("utf-8")
+    );
+    assert_eq!(
+      charset_param("<meta charset='windows-1252'>").as_deref(),
+      Some("windows-1252")
+    );
+    assert_eq!(
+      charset_param(r#"<meta http-equiv="Content-Type" content="text/html; charset=Shift_JIS">"#)
+        .as_deref(),
+      Some("shift_jis")
+    );
+    assert_eq!(charset_param("text/html"), None);
+    assert_eq!(charset_param("text/html; charset="), None);
   }
 
-  let client = reqwest::Client::builder()
-    .user_agent("LANA/0.1")
-    .build()
-    .map_err(|e| format!("http client failed: {e}"))?;
+  #[test]
+  fn decode_html_uses_header_charset() {
+    let page = b"<title>Caf\xe9 cr\xe8me br\xfbl\xe9e</title>";
+    let html = decode_html(page, Some("text/html; charset=iso-8859-1"));
+    let doc = Html::parse_document(&html);
+    assert_eq!(
+      title_text(&doc).as_deref(),
+      Some("Caf\u{e9} cr\u{e8}me br\u{fb}l\u{e9}e")
+    );
+  }
 
-  let req_body = OllamaChatRequest {
-    model,
-    messages,
-    stream: false,
-  };
+  #[test]
+  fn decode_html_falls_back_to_meta_charset() {
+    let page = b"<meta charset=\"windows-1252\"><title>\x93Quoted\x94 na\xefve</title>";
+    let doc = Html::parse_document(&decode_html(page, Some("text/html")));
+    assert_eq!(
+      title_text(&doc).as_deref(),
+      Some("\u{201c}Quoted\u{201d} na\u{ef}ve")
+    );
+  }
 
-  let resp = client
-    .post("http://127.0.0.1:11434/api/chat")
-    .json(&req_body)
-    .send()
-    .await
-    .map_err(|e| format!("ollama request failed: {e}"))?;
+  #[test]
+  fn decode_html_prefers_byte_order_mark() {
+    let mut page = vec![0xef, 0xbb, 0xbf];
+    page.extend_from_slice("<title>\u{e9}t\u{e9}</title>".as_bytes());
+    let html = decode_html(&page, Some("text/html; charset=iso-8859-1"));
+    assert_eq!(
+      title_text(&Html::parse_document(&html)).as_deref(),
+      Some("\u{e9}t\u{e9}")
+    );
+  }
 
-  let status = resp.status();
-  let body = resp.text().await.map_err(|e| format!("ollama read failed: {e}"))?;
-  if !status.is_success() {
-    return Err(format!("ollama error ({status}): {body}"));
+  #[test]
+  fn decode_html_skips_unknown_labels() {
+    let page = b"<meta charset=\"iso-8859-1\"><title>Z\xfcrich</title>";
+    let html = decode_html(page, Some("text/html; charset=x-made-up"));
+    assert_eq!(
+      title_text(&Html::parse_document(&html)).as_deref(),
+      Some("Z\u{fc}rich")
+    );
+    let html = decode_html(
+      "<title>\u{e9}</title>".as_bytes(),
+      Some("charset=x-made-up"),
+    );
+    assert_eq!(
+      title_text(&Html::parse_document(&html)).as_deref(),
+      Some("\u{e9}")
+    );
   }
 
-  let parsed: OllamaChatResponse =
-    serde_json::from_str(&body).map_err(|e| format!("ollama parse failed: {e}"))?;
-  Ok(parsed.message)
-}
+  /// An empty directory under the system temp dir, unique to this test.
+  fn scratch_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("lana-test-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
 
-#[tauri::command]
-fn get_assets_dir(paths: tauri::State<'_, AppPaths>, board_id: String) -> Result<String, String> {
-  if !is_valid_board_id(&board_id) {
-    return Err("invalid board id".to_string());
+  #[test]
+  fn data_dir_move_keeps_originals_edited_after_copy() {
+    let root = scratch_dir("data-dir-move");
+    let (old, new) = (root.join("old"), root.join("new"));
+    std::fs::create_dir_all(old.join("boards/b1")).unwrap();
+    std::fs::write(old.join("boards/b1/board.json"), "{}").unwrap();
+    std::fs::create_dir_all(new.join("boards/b1")).unwrap();
+    std::fs::copy(
+      old.join("boards/b1/board.json"),
+      new.join("boards/b1/board.json"),
+    )
+    .unwrap();
+    assert_eq!(first_unmoved_change(&old, &new), None);
+
+    let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+    let edited = std::fs::File::options()
+      .write(true)
+      .open(old.join("boards/b1/board.json"))
+      .unwrap();
+    edited.set_modified(later).unwrap();
+    assert!(first_unmoved_change(&old, &new).is_some());
+
+    std::fs::remove_dir_all(&new).unwrap();
+    std::fs::create_dir_all(&new).unwrap();
+    assert!(first_unmoved_change(&old, &new).is_some());
+    let _ = std::fs::remove_dir_all(root);
   }
-  let index = read_index(&paths)?;
-  let name = index
-    .boards
-    .iter()
-    .find(|b| b.id == board_id)
-    .map(|b| b.name.clone())
-    .unwrap_or_else(|| "Untitled".to_string());
-  let board_paths = board_paths(&paths.root_dir, &board_id);
-  ensure_board_file(&board_paths, &board_id, &name)?;
-  let _ = ensure_board_index_contains(&paths, index, &board_id, &name)?;
-  Ok(board_paths.assets_dir.to_string_lossy().to_string())
-}
 
-#[tauri::command]
-fn load_chat(paths: tauri::State<'_, AppPaths>, board_id: String) -> Result<ChatStore, String> {
-  if !is_valid_board_id(&board_id) {
-    return Err("invalid board id".to_string());
+  #[test]
+  fn rebuilt_index_keeps_pins() {
+    let root = scratch_dir("index-pins");
+    let paths = AppPaths::at(root.clone());
+    for id in ["pinned", "plain"] {
+      let board = board_paths(&paths.root_dir, id);
+      std::fs::create_dir_all(&board.dir).unwrap();
+      std::fs::write(&board.file, format!(r#"{{"id":"{id}","name":"{id}"}}"#)).unwrap();
+    }
+    let mut index = ensure_board_index(&paths).unwrap();
+    for meta in index.boards.iter_mut() {
+      meta.pinned = meta.id == "pinned";
+    }
+    write_index_atomic(&paths, &index).unwrap();
+
+    let index = ensure_board_index(&paths).unwrap();
+    let pinned: Vec<_> = index
+      .boards
+      .iter()
+      .filter(|b| b.pinned)
+      .map(|b| b.id.as_str())
+      .collect();
+    assert_eq!(pinned, ["pinned"]);
+    let _ = std::fs::remove_dir_all(root);
   }
-  let index = read_index(&paths)?;
-  let name = index
-    .boards
-    .iter()
-    .find(|b| b.id == board_id)
-    .map(|b| b.name.as_str())
-    .unwrap_or("Untitled");
-  let board_paths = board_paths(&paths.root_dir, &board_id);
-  ensure_board_file(&board_paths, &board_id, name)?;
 
-  let chat_path = board_paths.dir.join("chat.json");
-  if !chat_path.exists() {
-    return Ok(ChatStore {
-      version: 1,
-      messages: vec![],
-      summary: None,
-      summary_up_to: 0,
-      last_session_id: None,
-    });
+  #[test]
+  fn sized_img_sources_skips_absurd_dimensions() {
+    let doc = Html::parse_document(
+      r#"<img src="huge.png" width="9999999999" height="9999999999">
+         <img src="big.png" width="4294967295" height="4294967295">
+         <img src="small.png" width="10px" height="10">"#,
+    );
+    assert_eq!(sized_img_sources(&doc), ["big.png", "small.png"]);
   }
 
-  let raw = std::fs::read_to_string(&chat_path).map_err(|e| format!("read chat failed: {e}"))?;
-  let chat: ChatStore = serde_json::from_str(&raw).map_err(|e| format!("parse chat failed: {e}"))?;
-  Ok(chat)
-}
+  #[test]
+  fn board_assets_are_named_by_role_and_hash() {
+    let root = scratch_dir("asset-roles");
+    let paths = board_paths(&root, "b1");
+    let icon = save_board_bytes(&paths, AssetRole::Favicon, b"icon", "ico").unwrap();
+    assert!(icon.starts_with("assets/favicon-sha256-"), "{icon}");
+    assert_eq!(
+      save_board_bytes(&paths, AssetRole::Favicon, b"icon", "ico").unwrap(),
+      icon
+    );
+    let preview = save_board_bytes(&paths, AssetRole::Link, b"icon", "ico").unwrap();
+    assert!(preview.starts_with("assets/link-sha256-"), "{preview}");
+    let _ = std::fs::remove_dir_all(root);
+  }
 
-#[tauri::command]
-fn save_chat(
-  paths: tauri::State<'_, AppPaths>,
-  board_id: String,
-  chat: ChatStore,
-) -> Result<(), String> {
-  if !is_valid_board_id(&board_id) {
-    return Err("invalid board id".to_string());
+  #[test]
+  fn chat_saves_twice_over_existing_file() {
+    let root = scratch_dir("chat-resave");
+    let paths = board_paths(&root, "board-1");
+    std::fs::create_dir_all(&paths.dir).unwrap();
+    let settings = AppSettings::default();
+    let mut chat = read_chat(&paths, None).unwrap();
+    for text in ["first", "second"] {
+      chat.messages.push(ChatEntry {
+        id: text.into(),
+        role: "user".into(),
+        content: text.into(),
+        created_at: 0,
+        session_id: None,
+        model: None,
+      });
+      write_chat(&paths, &chat, &settings, None).unwrap();
+    }
+    let saved = read_chat(&paths, None).unwrap();
+    let contents: Vec<&str> = saved.messages.iter().map(|m| m.content.as_str()).collect();
+    assert_eq!(contents, ["first", "second"]);
+    assert!(!paths.dir.join("chat.json.tmp").exists());
+    let _ = std::fs::remove_dir_all(&root);
   }
-  let index = read_index(&paths)?;
-  let name = index
-    .boards
-    .iter()
-    .find(|b| b.id == board_id)
-    .map(|b| b.name.as_str())
-    .unwrap_or("Untitled");
-  let board_paths = board_paths(&paths.root_dir, &board_id);
-  ensure_board_file(&board_paths, &board_id, name)?;
 
-  let chat_path = board_paths.dir.join("chat.json");
-  let tmp_path = board_paths.dir.join("chat.json.tmp");
-  let serialized =
-    serde_json::to_string_pretty(&chat).map_err(|e| format!("serialize chat failed: {e}"))?;
-  std::fs::write(&tmp_path, serialized).map_err(|e| format!("write chat failed: {e}"))?;
-  std::fs::rename(&tmp_path, &chat_path).map_err(|e| format!("write chat failed: {e}"))?;
-  Ok(())
-}
+  #[test]
+  fn snapshot_references_keep_assets_out_of_gc() {
+    let root = scratch_dir("gc-history");
+    let paths = board_paths(&root, "board-1");
+    std::fs::create_dir_all(&paths.assets_dir).unwrap();
+    std::fs::create_dir_all(&paths.history_dir).unwrap();
+    for name in ["live.png", "old.png", "orphan.png"] {
+      std::fs::write(paths.assets_dir.join(name), b"x").unwrap();
+    }
+    let snapshot = r#"{"version": 99, "cards": [{"id": "c", "src": "assets/old.png"}]}"#;
+    std::fs::write(paths.history_dir.join("1000.json"), snapshot).unwrap();
+    let mut board = empty_board("board-1", "Board");
+    board.cards = serde_json::from_value(serde_json::json!([{
+      "id": "c1", "type": "image", "x": 0, "y": 0, "width": 9, "height": 9,
+      "src": "assets/live.png"
+    }]))
+    .unwrap();
 
-#[tauri::command]
-fn save_image(
-  paths: tauri::State<'_, AppPaths>,
-  board_id: String,
-  filename: String,
-  bytes_base64: String,
-) -> Result<String, String> {
-  if !is_valid_board_id(&board_id) {
-    return Err("invalid board id".to_string());
+    let orphans = orphan_asset_files(&paths, &board);
+    assert_eq!(orphans, [paths.assets_dir.join("orphan.png")]);
+    let _ = std::fs::remove_dir_all(&root);
   }
-  let index = read_index(&paths)?;
-  let name = index
-    .boards
-    .iter()
-    .find(|b| b.id == board_id)
-    .map(|b| b.name.as_str())
-    .unwrap_or("Untitled");
-  let board_paths = board_paths(&paths.root_dir, &board_id);
-  ensure_board_file(&board_paths, &board_id, name)?;
 
-  let decoded = base64::engine::general_purpose::STANDARD
-    .decode(bytes_base64.as_bytes())
-    .map_err(|e| format!("base64 decode failed: {e}"))?;
+  #[test]
+  fn newer_board_is_a_conflict_not_a_parse_error() {
+    let newer = format!(
+      r#"{{"version": {}, "id": "b", "name": "B", "cards": []}}"#,
+      BOARD_VERSION + 1
+    );
+    assert!(matches!(parse_board(&newer), Err(AppError::Conflict(_))));
+    assert!(matches!(
+      parse_board("{not json"),
+      Err(AppError::Serialize(_))
+    ));
+  }
 
-  let safe_name = filename.replace('\\', "_").replace('/', "_").replace("..", "_");
+  #[test]
+  fn favicon_candidates_try_first_declared_icon_then_root() {
+    let base = Url::parse("https://example.com/post").unwrap();
+    let doc = Html::parse_document(concat!(
+      r#"<link rel="icon" href="/a.png"><link rel="icon" href="/b.png">"#,
+      r#"<link rel="apple-touch-icon" href="/touch.png">"#
+    ));
+    let icons: Vec<String> = favicon_candidates(&doc, &base)
+      .iter()
+      .map(Url::to_string)
+      .collect();
+    assert_eq!(
+      icons,
+      [
+        "https://example.com/touch.png",
+        "https://example.com/favicon.ico"
+      ]
+    );
 
-  let out = board_paths.assets_dir.join(&safe_name);
-  let tmp = board_paths.assets_dir.join(format!("{safe_name}.tmp"));
+    let doc = Html::parse_document("<title>No icons</title>");
+    let icons: Vec<String> = favicon_candidates(&doc, &base)
+      .iter()
+      .map(Url::to_string)
+      .collect();
+    assert_eq!(icons, ["https://example.com/favicon.ico"]);
+  }
 
-  std::fs::write(&tmp, decoded).map_err(|e| format!("write temp image failed: {e}"))?;
-  if out.exists() {
-    let _ = std::fs::remove_file(&out);
+  #[test]
+  fn canonical_link_stays_on_the_same_site() {
+    let canonical = |base: &str, href: &str| {
+      let doc = Html::parse_document(&format!(r#"<link rel="canonical" href="{href}">"#));
+      canonical_link(&doc, &Url::parse(base).unwrap()).map(|u| u.to_string())
+    };
+    let base = "https://www.example.com/a?utm_source=x";
+    assert_eq!(
+      canonical(base, "/a").as_deref(),
+      Some("https://www.example.com/a")
+    );
+    assert_eq!(
+      canonical(base, "https://example.com/a").as_deref(),
+      Some("https://example.com/a")
+    );
+    assert_eq!(canonical(base, "https://evil.test/a"), None);
+    assert_eq!(canonical(base, "http://127.0.0.1/a"), None);
+    assert_eq!(canonical(base, "http://[::ffff:10.0.0.1]/a"), None);
+    assert_eq!(canonical(base, "ftp://example.com/a"), None);
+    assert_eq!(
+      canonical("https://news.bbc.co.uk/x", "https://www.bbc.co.uk/x").as_deref(),
+      Some("https://www.bbc.co.uk/x")
+    );
+    assert_eq!(canonical("https://a.co.uk/x", "https://b.co.uk/x"), None);
+    assert_eq!(canonical("http://1.2.3.4/x", "http://5.6.3.4/x"), None);
   }
-  std::fs::rename(&tmp, &out).map_err(|e| format!("rename image failed: {e}"))?;
 
-  Ok(format!("assets/{safe_name}"))
-}
+  #[test]
+  fn stale_capability_probe_is_not_cached() {
+    let caps = ChatCaps::default();
+    let probed = ChatCapabilities {
+      streaming: true,
+      multimodal: false,
+      embeddings: false,
+    };
+    let key = ("http://localhost:11434".to_string(), "llama3".to_string());
+    let (_, started) = caps.get(&key);
+    caps.clear();
+    caps.store(key.clone(), started, probed);
+    assert!(caps.get(&key).0.is_none());
 
-#[tauri::command]
-fn load_board(paths: tauri::State<'_, AppPaths>, board_id: String) -> Result<Board, String> {
-  if !is_valid_board_id(&board_id) {
-    return Err("invalid board id".to_string());
+    let (_, current) = caps.get(&key);
+    caps.store(key.clone(), current, probed);
+    assert!(caps.get(&key).0.is_some());
+    let other_model = (key.0.clone(), "llava".to_string());
+    assert!(caps.get(&other_model).0.is_none());
   }
-  let index = read_index(&paths)?;
-  let name = index
-    .boards
-    .iter()
-    .find(|b| b.id == board_id)
-    .map(|b| b.name.as_str())
-    .unwrap_or("Untitled");
-  let board_paths = board_paths(&paths.root_dir, &board_id);
-  ensure_board_file(&board_paths, &board_id, name)?;
 
-  let text = std::fs::read_to_string(&board_paths.file).map_err(|e| format!("read failed: {e}"))?;
+  #[test]
+  fn board_system_prompt_is_sent_once() {
+    let message = |role: &str, content: &str| OllamaMessage {
+      role: role.to_string(),
+      content: content.to_string(),
+    };
+    let prompt = "Be a pirate.";
+    let sent = |messages: &[OllamaMessage]| messages.iter().filter(|m| m.content == prompt).count();
 
-  match serde_json::from_str::<Board>(&text) {
-    Ok(mut board) => {
-      if board.id != board_id {
-        board.id = board_id.clone();
-        write_board_atomic(&board_paths, &board)?;
-      }
-      Ok(board)
-    }
-    Err(_) => {
-      let board = empty_board(&board_id, name);
-      write_board_atomic(&board_paths, &board)?;
-      Ok(board)
+    let mut messages = vec![message("user", "hi")];
+    prepend_system_prompt(&mut messages, prompt.to_string());
+    assert_eq!(sent(&messages), 1);
+    assert_eq!(messages[0].role, "system");
+
+    let mut messages = vec![message("system", prompt), message("user", "hi")];
+    prepend_system_prompt(&mut messages, prompt.to_string());
+    assert_eq!(messages.len(), 2);
+    assert_eq!(sent(&messages), 1);
+  }
+
+  #[test]
+  fn refresh_keeps_user_title() {
+    let card = |title: Option<&str>| -> Card {
+      serde_json::from_value(serde_json::json!({
+        "id": "c1",
+        "type": "link",
+        "x": 0,
+        "y": 0,
+        "width": 9,
+        "height": 9,
+        "url": "https://example.com/a",
+        "title": title
+      }))
+      .unwrap()
+    };
+    let meta: LinkMetadata = serde_json::from_value(serde_json::json!({
+      "url": "https://example.com/a",
+      "title": "Fetched",
+      "siteName": "example.com"
+    }))
+    .unwrap();
+
+    let mut edited = card(Some("My notes"));
+    refresh_link_card(&mut edited, meta.clone());
+    assert_eq!(edited.title.as_deref(), Some("My notes"));
+    assert_eq!(edited.site_name.as_deref(), Some("example.com"));
+    assert!(edited.fetched_at.is_some());
+
+    for title in [None, Some(""), Some("https://example.com/a")] {
+      let mut blank = card(title);
+      refresh_link_card(&mut blank, meta.clone());
+      assert_eq!(blank.title.as_deref(), Some("Fetched"));
     }
   }
-}
 
-#[tauri::command]
-fn save_board(
-  paths: tauri::State<'_, AppPaths>,
-  board_id: String,
-  board: Board,
-) -> Result<(), String> {
-  if !is_valid_board_id(&board_id) {
-    return Err("invalid board id".to_string());
+  #[test]
+  fn openai_base_adds_v1_once() {
+    let base = |s: &str| openai_api_base(s).unwrap();
+    assert_eq!(base("http://localhost:1234"), "http://localhost:1234/v1");
+    assert_eq!(base("http://localhost:1234/v1"), "http://localhost:1234/v1");
+    assert_eq!(
+      base("http://localhost:1234/v1/"),
+      "http://localhost:1234/v1"
+    );
+    assert_eq!(
+      base("https://proxy.example/openai/"),
+      "https://proxy.example/openai/v1"
+    );
+    assert!(openai_api_base("file:///v1").is_err());
   }
-  if board.id != board_id {
-    return Err(format!(
-      "board id mismatch (payload {}, expected {})",
-      board.id, board_id
-    ));
+
+  #[test]
+  fn public_ip_excludes_cgnat_and_mapped_private() {
+    let public = |s: &str| is_public_ip(s.parse().unwrap());
+    assert!(public("93.184.216.34"));
+    assert!(public("100.128.0.1"));
+    assert!(public("2606:4700::1111"));
+    assert!(!public("100.64.0.1"));
+    assert!(!public("100.127.255.254"));
+    assert!(!public("::ffff:192.168.1.10"));
+    assert!(!public("::ffff:127.0.0.1"));
+    assert!(!public("fd00::1"));
   }
-  let index = read_index(&paths)?;
-  if let Some(meta) = index.boards.iter().find(|b| b.id == board_id) {
-    if meta.deleted_at.is_some() {
-      return Err("board is deleted".to_string());
-    }
+
+  #[tokio::test]
+  async fn resolver_refuses_loopback_names() {
+    let name: reqwest::dns::Name = "localhost".parse().unwrap();
+    let result = reqwest::dns::Resolve::resolve(&PublicDnsResolver, name).await;
+    assert_eq!(
+      result.err().map(|e| e.to_string()).as_deref(),
+      Some(BLOCKED_ADDRESS)
+    );
   }
-  let board_paths = board_paths(&paths.root_dir, &board_id);
-  ensure_board_file(&board_paths, &board_id, &board.name)?;
-  write_board_atomic(&board_paths, &board)?;
-  let _ = ensure_board_index_contains(&paths, index, &board_id, &board.name)?;
-  Ok(())
 }
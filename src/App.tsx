@@ -19,6 +19,7 @@ import {
 import './App.css'
 import assistantAvatar from '../src-tauri/icons/32x32.png'
 import { convertFileSrc, isTauri } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
 import { getCurrentWindow } from '@tauri-apps/api/window'
 import { open } from '@tauri-apps/plugin-dialog'
 import {
@@ -27,7 +28,9 @@ import {
   cleanupAssets,
   deleteBoard,
   emptyTrash,
+  errorMessage,
   fetchLinkMetadata,
+  getAssetPoolDir,
   getAssetsDir,
   listBoards,
   listTrashedBoards,
@@ -39,6 +42,8 @@ import {
   saveBoard,
   saveChat,
   saveImage,
+  setActiveBoard,
+  takePendingBoardLink,
 } from './persistence/board'
 import type {
   Board,
@@ -884,6 +889,7 @@ function App() {
     return prefersLight ? 'light' : 'dark'
   })
   const [assetsDir, setAssetsDir] = useState<string | null>(null)
+  const [assetPoolDir, setAssetPoolDir] = useState<string | null>(null)
   const [uiNotice, setUiNotice] = useState<UiNotice | null>(null)
   const noticeTimerRef = useRef<number | null>(null)
   const [board, setBoard] = useState<Board | null>(null)
@@ -1093,6 +1099,13 @@ function App() {
         .catch(() => {
           setAssetsDir(null)
         })
+      getAssetPoolDir()
+        .then((dir) => {
+          setAssetPoolDir(dir)
+        })
+        .catch(() => {
+          setAssetPoolDir(null)
+        })
 
       layoutResetPendingRef.current = true
       const wrapper = transformRef.current?.instance.wrapperComponent ?? panZoomEl
@@ -1124,6 +1137,9 @@ function App() {
       setBoard(withLayout)
       setCurrentBoardId(boardId)
       markBoardAsRecent(boardId)
+      setActiveBoard(boardId).catch((err) => {
+        console.error('set active board failed', err)
+      })
       setSelectionIds([])
       setColumnSelectionIds([])
       setEditingColumnId(null)
@@ -1151,7 +1167,7 @@ function App() {
         })
     } catch (e) {
       setStatus('error')
-      setError(String(e))
+      setError(errorMessage(e))
     }
   }, [])
 
@@ -1203,6 +1219,18 @@ function App() {
     },
     [board, currentBoardId, loadBoardById],
   )
+  const switchBoardRef = useRef(switchBoard)
+  switchBoardRef.current = switchBoard
+
+  useEffect(() => {
+    if (!isTauri()) return
+    const unlisten = listen<string>('open-board', (event) => {
+      void switchBoardRef.current(event.payload)
+    })
+    return () => {
+      void unlisten.then((fn) => fn())
+    }
+  }, [])
 
   const removeBoard = useCallback(
     async (boardId: string) => {
@@ -2227,7 +2255,7 @@ function App() {
 
           const id = replaceId && i === 0 ? replaceId : nanoid()
           const filename = `${id}.png`
-          const rel = await saveImage(currentBoardId, filename, bytesBase64) // "assets/<id>.png"
+          const { src: rel } = await saveImage(currentBoardId, filename, bytesBase64) // "assets/image-sha256-<hex>.png"
 
           const cardH = imageCardHeight(width, height)
           const x = clamp(
@@ -2322,8 +2350,11 @@ function App() {
                     ...c,
                     url: meta.url || c.url,
                     title: meta.title || c.title,
+                    description: meta.description ?? c.description,
                     image: meta.image ?? c.image,
+                    favicon: meta.favicon ?? c.favicon,
                     siteName: meta.siteName ?? c.siteName,
+                    fetchedAt: Date.now(),
                     noteExpanded: noteOpen,
                     height: noteOpen
                       ? contentHeight ?? Math.max(c.height, nextBase + LINK_NOTE_MIN_HEIGHT)
@@ -2405,8 +2436,11 @@ function App() {
                   ...c,
                   url: meta.url || c.url,
                   title: meta.title || c.title,
+                  description: meta.description ?? c.description,
                   image: meta.image ?? c.image,
+                  favicon: meta.favicon ?? c.favicon,
                   siteName: meta.siteName ?? c.siteName,
+                  fetchedAt: Date.now(),
                   noteExpanded: noteOpen,
                   height: noteOpen
                     ? contentHeight ?? Math.max(c.height, nextBase + LINK_NOTE_MIN_HEIGHT)
@@ -2476,7 +2510,7 @@ function App() {
           await createImageCards(imageFiles, center, null)
         } catch (err) {
           console.error('image paste failed', err)
-          const msg = err instanceof Error ? err.message : String(err)
+          const msg = errorMessage(err)
           flashNotice(`Failed to paste image: ${msg}`)
         }
       })()
@@ -2501,6 +2535,9 @@ function App() {
         if (cancelled) return
         const storedLastId = readLastBoardId()
         const validLastId = storedLastId && nextBoards.some((b) => b.id === storedLastId) ? storedLastId : null
+        const linkedId = isTauri() ? await takePendingBoardLink() : null
+        if (cancelled) return
+        const validLinkedId = linkedId && nextBoards.some((b) => b.id === linkedId) ? linkedId : null
         setBoards(nextBoards)
         setTrashedBoards(
           trashed
@@ -2513,12 +2550,12 @@ function App() {
           return filtered
         })
 
-        const initialId = validLastId ?? nextBoards[0].id
+        const initialId = validLinkedId ?? validLastId ?? nextBoards[0].id
         await loadBoardById(initialId)
       } catch (e) {
         if (cancelled) return
         setStatus('error')
-        setError(String(e))
+        setError(errorMessage(e))
       }
     })()
 
@@ -3388,6 +3425,9 @@ function App() {
   }
 
   function assetUrl(relPath?: string | null): string | null {
+    if (relPath?.startsWith('pool://')) {
+      return assetPoolDir ? convertFileSrc(`${assetPoolDir}/${relPath.slice('pool://'.length)}`) : null
+    }
     if (!assetsDir || !relPath) return null
     const rel = relPath.startsWith('assets/') ? relPath.slice('assets/'.length) : relPath
     const abs = `${assetsDir}/${rel}`
@@ -3483,7 +3523,7 @@ function App() {
           ]
           try {
             const summaryResponse = await ollamaChat(chatModel, summaryPrompt)
-            nextSummary = summaryResponse.content.trim()
+            nextSummary = summaryResponse.message.content.trim()
             nextSummaryUpTo = targetSummaryUpTo
             setChatSummaryByBoard((prev) => ({ ...prev, [currentBoardId]: nextSummary }))
             setChatSummaryUpToByBoard((prev) => ({ ...prev, [currentBoardId]: nextSummaryUpTo }))
@@ -3511,9 +3551,10 @@ function App() {
       const responseEntry: ChatEntry = {
         id: nanoid(),
         role: 'assistant',
-        content: response.content,
+        content: response.message.content,
         createdAt: Date.now(),
         sessionId,
+        model: chatModel,
       }
       const finalMessages = [...nextMessages, responseEntry]
       setChatByBoard((prev) => ({ ...prev, [currentBoardId]: finalMessages }))
@@ -3530,7 +3571,7 @@ function App() {
         console.error('chat save failed', err)
       })
     } catch (err) {
-      const detail = err instanceof Error ? err.message : 'Failed to contact Ollama'
+      const detail = err ? errorMessage(err) : 'Failed to contact Ollama'
       setChatStatus('error')
       setChatError(
         `Could not reach Ollama at http://127.0.0.1:11434. ` +
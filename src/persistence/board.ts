@@ -1,5 +1,15 @@
 import { invoke } from '@tauri-apps/api/core'
-import type { Board, BoardMeta, ChatMessage, ChatStore, LinkMetadata } from '../types'
+import type { AppError, Board, BoardMeta, ChatMessage, ChatResult, ChatStore, LinkMetadata, OllamaModel, SavedImage } from '../types'
+
+// Commands reject with an AppError ({ code, message }); other failures are plain Errors.
+export function isAppError(err: unknown): err is AppError {
+  return typeof err === 'object' && err !== null && 'code' in err && 'message' in err
+}
+
+export function errorMessage(err: unknown): string {
+  if (isAppError(err) || err instanceof Error) return err.message
+  return String(err)
+}
 
 export async function listBoards(): Promise<BoardMeta[]> {
   return await invoke<BoardMeta[]>('list_boards')
@@ -25,8 +35,8 @@ export async function deleteBoard(boardId: string): Promise<void> {
   await invoke('delete_board', { boardId })
 }
 
-export async function restoreBoard(boardId: string): Promise<void> {
-  await invoke('restore_board', { boardId })
+export async function restoreBoard(boardId: string, forceCopy = false): Promise<string> {
+  return await invoke<string>('restore_board', { boardId, forceCopy })
 }
 
 export async function emptyTrash(): Promise<void> {
@@ -37,8 +47,13 @@ export async function loadBoard(boardId: string): Promise<Board> {
   return await invoke<Board>('load_board', { boardId })
 }
 
-export async function saveBoard(boardId: string, board: Board): Promise<void> {
-  await invoke('save_board', { boardId, board })
+// Tells the backend which board this window shows so board events reach only its viewers.
+export async function setActiveBoard(boardId: string | null): Promise<void> {
+  await invoke('set_active_board', { boardId })
+}
+
+export async function saveBoard(boardId: string, board: Board): Promise<string> {
+  return invoke<string>('save_board', { boardId, board })
 }
 
 export async function loadChat(boardId: string): Promise<ChatStore> {
@@ -53,17 +68,43 @@ export async function getAssetsDir(boardId: string): Promise<string> {
   return await invoke<string>('get_assets_dir', { boardId })
 }
 
-export async function saveImage(boardId: string, filename: string, bytesBase64: string): Promise<string> {
+export async function getAssetPoolDir(): Promise<string> {
+  return await invoke<string>('get_asset_pool_dir')
+}
+
+export async function saveImage(boardId: string, filename: string, bytesBase64: string): Promise<SavedImage> {
   // Tauri invokes use camelCase args and map to Rust snake_case params.
-  return await invoke<string>('save_image', { boardId, filename, bytesBase64 })
+  return await invoke<SavedImage>('save_image', { boardId, filename, bytesBase64 })
 }
 
 export async function fetchLinkMetadata(boardId: string, url: string): Promise<LinkMetadata> {
   return await invoke<LinkMetadata>('fetch_link_metadata', { boardId, url })
 }
 
-export async function ollamaChat(model: string, messages: ChatMessage[]): Promise<ChatMessage> {
-  return await invoke<ChatMessage>('ollama_chat', { model, messages })
+export async function ollamaChat(model: string, messages: ChatMessage[]): Promise<ChatResult> {
+  return await invoke<ChatResult>('ollama_chat', { model, messages })
+}
+
+// Streams the reply as `ollama-chunk` events tagged with requestId; resolves with the full result.
+export async function ollamaChatStream(
+  model: string,
+  messages: ChatMessage[],
+  requestId: string,
+): Promise<ChatResult> {
+  return await invoke<ChatResult>('ollama_chat_stream', { model, messages, requestId })
+}
+
+export async function listOllamaModels(): Promise<OllamaModel[]> {
+  return await invoke<OllamaModel[]>('list_ollama_models')
+}
+
+export async function cancelOllama(requestId: string): Promise<boolean> {
+  return await invoke<boolean>('cancel_ollama', { requestId })
+}
+
+// Board id from the lana:// link that launched the app, if any; cleared once read.
+export async function takePendingBoardLink(): Promise<string | null> {
+  return await invoke<string | null>('take_pending_board_link')
 }
 
 export async function openExternalUrl(url: string): Promise<void> {
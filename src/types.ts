@@ -6,6 +6,7 @@ export type TextCard = {
   width: number
   height: number
   text: string
+  tags?: string[]
 }
 
 export type ImageCard = {
@@ -21,6 +22,7 @@ export type ImageCard = {
   naturalHeight: number
   note?: string
   noteExpanded?: boolean
+  tags?: string[]
 }
 
 export type LinkCard = {
@@ -32,11 +34,16 @@ export type LinkCard = {
   height: number
   url: string
   title: string
+  description?: string
   // Stored as a relative path under the board folder, e.g. "assets/<id>.png"
   image?: string
+  favicon?: string
   siteName?: string
+  // Epoch ms of the last metadata fetch; used to find stale previews.
+  fetchedAt?: number
   note?: string
   noteExpanded?: boolean
+  tags?: string[]
 }
 
 export type Card = TextCard | ImageCard | LinkCard
@@ -52,10 +59,19 @@ export type Column = {
 }
 
 export type Board = {
+  version?: number
   id: string
   name: string
   cards: Card[]
   columns: Column[]
+  settings?: BoardSettings
+}
+
+export type BoardSettings = {
+  background?: string | null
+  gridSize: number
+  snapToGrid: boolean
+  defaultCardWidth: number
 }
 
 export type BoardMeta = {
@@ -63,13 +79,20 @@ export type BoardMeta = {
   name: string
   updatedAt: number
   deletedAt?: number
+  pinned?: boolean
 }
 
 export type LinkMetadata = {
   url: string
   title: string
+  description?: string
   image?: string
+  favicon?: string
   siteName?: string
+  keywords?: string[]
+  imageWidth?: number
+  imageHeight?: number
+  imageCandidates?: string[]
 }
 
 export type ChatMessage = {
@@ -77,12 +100,39 @@ export type ChatMessage = {
   content: string
 }
 
+export type ChatMetrics = {
+  totalDurationMs?: number
+  evalCount?: number
+  promptEvalCount?: number
+  tokensPerSecond?: number
+}
+
+export type ChatResult = {
+  message: ChatMessage
+  metrics: ChatMetrics
+}
+
+// `thumb` is set for large images: "thumbs/<asset>.<size>.jpg" under the board folder.
+// Cards still show `src`; the thumbnail is pre-rendered for share exports.
+export type SavedImage = {
+  src: string
+  thumb?: string
+}
+
+export type OllamaModel = {
+  name: string
+  size: number
+  modifiedAt: string | null
+}
+
 export type ChatEntry = {
   id: string
   role: 'user' | 'assistant' | 'system-note'
   content: string
   createdAt: number
   sessionId?: string
+  // Model that produced an assistant reply.
+  model?: string
 }
 
 export type ChatStore = {
@@ -92,3 +142,8 @@ export type ChatStore = {
   summaryUpTo: number
   lastSessionId?: string
 }
+
+export type AppError = {
+  code: string
+  message: string
+}